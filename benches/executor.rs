@@ -0,0 +1,182 @@
+//! Benchmarks of the executor, with many coroutines spawned on their own entity.
+//!
+//! The goal is for 100k mostly sleeping coroutines to tick in under a millisecond.
+
+use std::time::Duration;
+
+use bevy::{
+    ecs::system::{Command, EntityCommand},
+    prelude::{Component, Mut, World},
+};
+use corentin::{executor::Executor, prelude::*};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+#[derive(Component)]
+struct Example(u32);
+
+fn world_with_executor() -> World {
+    let mut world = World::new();
+    world.insert_resource(Executor::with_manual_time());
+    world
+}
+
+fn tick(world: &mut World, frame: Duration) {
+    world.resource_scope(|w, mut executor: Mut<Executor>| {
+        executor.advance(frame);
+        executor.tick(w);
+    });
+}
+
+/// Every coroutine is resumed each tick.
+fn tick_only(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick_only");
+    for n in [1_000, 10_000, 100_000] {
+        let mut world = world_with_executor();
+        for _ in 0..n {
+            root_coroutine(|mut s: Scope| async move {
+                loop {
+                    s.next_tick().await;
+                }
+            })
+            .apply(&mut world);
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, _| {
+            b.iter(|| tick(&mut world, Duration::from_millis(16)));
+        });
+    }
+    group.finish();
+}
+
+/// Most coroutines wait on a long duration, only a few of them wake up each tick.
+fn mostly_sleeping(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mostly_sleeping");
+    for n in [1_000, 10_000, 100_000] {
+        let mut world = world_with_executor();
+        for i in 0..n {
+            // Spread the deadlines, so about 1% of the coroutines wake up each tick
+            let period = Duration::from_millis(16 * (100 + (i % 100)));
+            root_coroutine(move |mut s: Scope| async move {
+                loop {
+                    s.duration(period).await;
+                }
+            })
+            .apply(&mut world);
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, _| {
+            b.iter(|| tick(&mut world, Duration::from_millis(16)));
+        });
+    }
+    group.finish();
+}
+
+/// Like `mostly_sleeping`, but each coroutine is owned by its own entity, some of which are
+/// despawned and replaced each tick.
+fn mostly_sleeping_owned(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mostly_sleeping_owned");
+    for n in [1_000, 10_000, 100_000] {
+        let mut world = world_with_executor();
+        let sleep = |i: usize| {
+            let period = Duration::from_millis(16 * (100 + (i % 100) as u64));
+            move |mut s: Scope| async move {
+                loop {
+                    s.duration(period).await;
+                }
+            }
+        };
+        let mut owners: Vec<_> = (0..n)
+            .map(|i| {
+                let e = world.spawn(Example(0)).id();
+                coroutine(sleep(i)).apply(e, &mut world);
+                e
+            })
+            .collect();
+
+        let mut i = 0;
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, _| {
+            b.iter(|| {
+                // Replace about 0.1% of the owners
+                for _ in 0..n / 1000 {
+                    i = (i + 1) % n;
+                    world.despawn(owners[i]);
+                    owners[i] = world.spawn(Example(0)).id();
+                    coroutine(sleep(i)).apply(owners[i], &mut world);
+                }
+                tick(&mut world, Duration::from_millis(16));
+                world.clear_trackers();
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Half of the coroutines write to a component each tick, the other half wait on its changes.
+fn change_heavy(c: &mut Criterion) {
+    let mut group = c.benchmark_group("change_heavy");
+    for n in [1_000, 10_000, 100_000] {
+        let mut world = world_with_executor();
+        for _ in 0..n / 2 {
+            let e = world
+                .spawn((Example(0), ChangeTracker::<Example>::new()))
+                .id();
+            coroutine(|mut s: Scope, mut ex: Wr<Example>| async move {
+                loop {
+                    s.next_tick().await;
+                    ex.get_mut(&s).0 += 1;
+                }
+            })
+            .apply(e, &mut world);
+            coroutine(|mut s: Scope, on_change: OnChange<Example>| async move {
+                loop {
+                    on_change.observe(&mut s).await;
+                }
+            })
+            .apply(e, &mut world);
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, _| {
+            b.iter(|| tick(&mut world, Duration::from_millis(16)));
+        });
+    }
+    group.finish();
+}
+
+/// Sleeping coroutines, which regularly spawn and await short lived sub-coroutines.
+fn mixed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mixed");
+    for n in [1_000, 10_000, 100_000] {
+        let mut world = world_with_executor();
+        for i in 0..n {
+            let e = world.spawn(Example(0)).id();
+            let period = Duration::from_millis(16 * (10 + (i % 50)));
+            coroutine(move |mut s: Scope| async move {
+                loop {
+                    s.duration(period).await;
+                    let a = s.start(|mut s: Scope| async move { s.next_tick().await });
+                    let b = s.start(|mut s: Scope, mut ex: Wr<Example>| async move {
+                        ex.get_mut(&s).0 += 1;
+                        s.next_tick().await;
+                    });
+                    s.all((a, b)).await;
+                }
+            })
+            .apply(e, &mut world);
+        }
+
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, _| {
+            b.iter(|| tick(&mut world, Duration::from_millis(16)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    tick_only,
+    mostly_sleeping,
+    mostly_sleeping_owned,
+    change_heavy,
+    mixed
+);
+criterion_main!(benches);
@@ -0,0 +1,190 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, FnArg, GenericArgument, ItemFn, PatType, PathArguments,
+    ReturnType, Type,
+};
+
+/// Turn an `async fn` into a function returning a coroutine, which can be given directly to
+/// `root_coroutine`, `coroutine` or `Scope::start`.
+///
+/// The first parameter is the `Scope` of the coroutine. The other parameters are arguments given
+/// when calling the function, except those marked with `#[param]`, which are coroutine
+/// parameters such as `Rd`, `Wr` or `OnChange`.
+///
+/// ```ignore
+/// #[coroutine_fn]
+/// async fn boss_intro(mut s: Scope, boss: Entity, #[param] hp: Rd<Hp>) {
+///     // ...
+/// }
+///
+/// commands.add(root_coroutine(boss_intro(boss)));
+/// ```
+///
+/// Parameters aliasing the same component, such as `#[param] a: Wr<Hp>, #[param] b: Rd<Hp>`, are
+/// rejected at compile time, as long as the component is spelled the same way in both.
+///
+/// The returned future is boxed, since the type of an `async fn` future can't be named. The
+/// attribute isn't named `coroutine`, as it would be ambiguous with the built-in attribute of the
+/// same name.
+#[proc_macro_attribute]
+pub fn coroutine_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = proc_macro2::TokenStream::from(attr);
+        return syn::Error::new(attr.span(), "`#[coroutine_fn]` doesn't take any argument")
+            .to_compile_error()
+            .into();
+    }
+
+    let item = parse_macro_input!(item as ItemFn);
+    match expand(item) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = item;
+
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "`#[coroutine_fn]` can only be used on an `async fn`",
+        ));
+    }
+
+    let mut inputs = sig.inputs.into_iter();
+    let scope = match inputs.next() {
+        Some(FnArg::Typed(scope)) => scope,
+        Some(arg) => {
+            return Err(syn::Error::new(
+                arg.span(),
+                "the first parameter of a coroutine must be its `Scope`",
+            ))
+        }
+        None => {
+            return Err(syn::Error::new(
+                sig.ident.span(),
+                "a coroutine must take its `Scope` as first parameter",
+            ))
+        }
+    };
+
+    let mut args: Vec<PatType> = Vec::new();
+    let mut params: Vec<PatType> = Vec::new();
+    for input in inputs {
+        let FnArg::Typed(mut input) = input else {
+            return Err(syn::Error::new(input.span(), "unexpected `self` parameter"));
+        };
+
+        let len = input.attrs.len();
+        input.attrs.retain(|attr| !attr.path().is_ident("param"));
+        if input.attrs.len() != len {
+            params.push(input);
+        } else {
+            args.push(input);
+        }
+    }
+
+    check_aliasing(&params)?;
+
+    let output = match sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+
+    let ident = sig.ident;
+    let (impl_generics, _, where_clause) = sig.generics.split_for_impl();
+    let scope_ty = &scope.ty;
+    let param_tys = params.iter().map(|p| &p.ty);
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis fn #ident #impl_generics (#(#args),*) -> impl FnOnce(#scope_ty, #(#param_tys),*)
+            -> ::corentin::function_coroutine::BoxedFuture<#output>
+            + ::corentin::MaybeSend + 'static
+        #where_clause
+        {
+            move |#scope, #(#params),*| ::std::boxed::Box::pin(async move #block)
+        }
+    })
+}
+
+/// The component accessed by a `Rd<T>` or `Wr<T>` parameter, and whether it is written.
+fn component_access(ty: &Type) -> Option<(String, bool)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let write = match segment.ident.to_string().as_str() {
+        "Rd" => false,
+        "Wr" => true,
+        _ => return None,
+    };
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(component) => Some((quote!(#component).to_string(), write)),
+        _ => None,
+    }
+}
+
+/// Reject the parameters writing to a component which is also accessed by another parameter.
+/// The same check is done when the coroutine is started, this one only catches it earlier.
+fn check_aliasing(params: &[PatType]) -> syn::Result<()> {
+    let mut accesses: Vec<(String, bool)> = Vec::new();
+    for param in params {
+        let Some((component, write)) = component_access(&param.ty) else {
+            continue;
+        };
+        if accesses
+            .iter()
+            .any(|(other, other_write)| *other == component && (write || *other_write))
+        {
+            return Err(syn::Error::new(
+                param.ty.span(),
+                format!(
+                    "conflicting coroutine parameters: `{component}` is written by one of them, \
+                    and accessed by another"
+                ),
+            ));
+        }
+        accesses.push((component, write));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn rejecting_aliasing_params() {
+        let conflicting: ItemFn = parse_quote! {
+            async fn f(s: Scope, #[param] a: Wr<Hp>, #[param] b: Rd<Hp>) {}
+        };
+        assert!(expand(conflicting).is_err());
+        let both_written: ItemFn = parse_quote! {
+            async fn f(s: Scope, #[param] a: Wr<Hp>, #[param] b: Wr<Hp>) {}
+        };
+        assert!(expand(both_written).is_err());
+
+        let shared: ItemFn = parse_quote! {
+            async fn f(s: Scope, #[param] a: Rd<Hp>, #[param] b: Rd<Hp>, #[param] c: Wr<Mana>) {}
+        };
+        assert!(expand(shared).is_ok());
+        // Only the parameters of the coroutine access components
+        let args: ItemFn = parse_quote! {
+            async fn f(s: Scope, a: Wr<Hp>, #[param] b: Wr<Hp>) {}
+        };
+        assert!(expand(args).is_ok());
+    }
+}
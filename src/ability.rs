@@ -0,0 +1,117 @@
+//! Helpers for abilities: cooldowns and charged actions.
+
+use std::{sync::Mutex, time::Duration};
+
+use bevy::{
+    prelude::{Entity, Resource, World},
+    utils::HashMap,
+};
+
+use crate::{
+    executor::TickTime,
+    expect_resource,
+    function_coroutine::{await_time::IntoDuration, scope::Scope},
+};
+
+/// The time (since the executor started) at which each cooldown is over. Cooldowns are identified
+/// by the owner of the coroutine which started them, and a key.
+///
+/// The times are behind a lock, so that a coroutine starts a cooldown as soon as it checked it is
+/// over, before any other coroutine checks it.
+#[derive(Resource, Default)]
+pub struct Cooldowns {
+    ready_at: Mutex<HashMap<(Option<Entity>, &'static str), Duration>>,
+}
+
+impl Cooldowns {
+    /// Returns the remaining time before the cooldown `key` of `owner` is over.
+    pub fn remaining(&self, owner: Option<Entity>, key: &'static str, now: Duration) -> Duration {
+        self.ready_at
+            .lock()
+            .unwrap()
+            .get(&(owner, key))
+            .map_or(Duration::ZERO, |ready_at| ready_at.saturating_sub(now))
+    }
+
+    /// If the cooldown `key` of `owner` is over, start it again until `ready_at`. Otherwise,
+    /// returns the remaining time.
+    ///
+    /// This writes to the resource while the coroutine only reported a read of the world, so
+    /// that coroutines using cooldowns can still run in the same parallel batch. The lock keeps
+    /// this sound, but which coroutine of a batch goes through first depends on the thread it runs
+    /// on.
+    fn reserve(
+        &self,
+        owner: Option<Entity>,
+        key: &'static str,
+        now: Duration,
+        ready_at: Duration,
+    ) -> Result<(), Duration> {
+        let mut times = self.ready_at.lock().unwrap();
+        let remaining = times
+            .get(&(owner, key))
+            .map_or(Duration::ZERO, |ready_at| ready_at.saturating_sub(now));
+        if !remaining.is_zero() {
+            return Err(remaining);
+        }
+        times.insert((owner, key), ready_at);
+        Ok(())
+    }
+}
+
+/// The outcome of [`Scope::charge_up`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Charge {
+    /// The charge completed.
+    Completed,
+    /// The charge was interrupted, after charging for the given duration.
+    Interrupted(Duration),
+}
+
+impl Scope {
+    /// Wait until the cooldown `key` of the owner of this coroutine is over, and starts it again
+    /// for `duration`. This is typically awaited right before using an ability. When several
+    /// coroutines wait on the same cooldown, only one of them goes through each time it is over.
+    ///
+    /// # Panics
+    /// If the [`Cooldowns`] resource is missing. It is added by the [`CorentinPlugin`].
+    ///
+    /// [`CorentinPlugin`]: crate::plugin::CorentinPlugin
+    pub async fn cooldown(&mut self, key: &'static str, duration: impl IntoDuration) {
+        let duration = duration.into_duration();
+        let owner = self.owner();
+        loop {
+            let reserved = {
+                let world = self.world();
+                let now = TickTime::get(&world).elapsed;
+                expect_resource::<Cooldowns>(&world).reserve(owner, key, now, now + duration)
+            };
+
+            match reserved {
+                Ok(()) => return,
+                // Another coroutine may restart the cooldown in the meantime, hence the loop
+                Err(remaining) => self.duration(remaining).await,
+            }
+        }
+    }
+
+    /// Charge for `duration`, unless `cancel` returns true before. `cancel` is checked right
+    /// away, and then once per tick.
+    pub async fn charge_up(
+        &mut self,
+        duration: impl IntoDuration,
+        mut cancel: impl FnMut(&World) -> bool,
+    ) -> Charge {
+        let duration = duration.into_duration();
+        let mut charged = Duration::ZERO;
+        loop {
+            if cancel(&self.world()) {
+                return Charge::Interrupted(charged);
+            }
+            if charged >= duration {
+                return Charge::Completed;
+            }
+            charged += self.next_tick().await;
+        }
+    }
+}
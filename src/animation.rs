@@ -0,0 +1,116 @@
+//! Waits on [`AnimationPlayer`] playback.
+
+use bevy::{
+    prelude::{AnimationClip, AnimationPlayer, Assets, Component, Entity, Handle, World},
+    reflect::GetPath,
+    utils::HashMap,
+};
+
+use crate::{
+    expect_resource,
+    function_coroutine::{await_condition::AwaitCondition, scope::Scope},
+};
+
+/// Named points in time (in seconds) of the clips played by an [`AnimationPlayer`], which can be
+/// awaited with [`Scope::animation_marker`]. It must be added to the entity holding the player.
+#[derive(Component, Default, Clone, Debug)]
+pub struct AnimationMarkers {
+    pub markers: HashMap<String, f32>,
+}
+
+impl AnimationMarkers {
+    /// Add a marker named `name`, `time` seconds into the clip.
+    pub fn with(mut self, name: impl Into<String>, time: f32) -> Self {
+        self.markers.insert(name.into(), time);
+        self
+    }
+}
+
+/// Returns the elapsed time and the duration of the clip currently played by `entity`.
+fn playback(world: &World, entity: Entity) -> Option<(f32, f32)> {
+    let player = world.get::<AnimationPlayer>(entity)?;
+    let clips = expect_resource::<Assets<AnimationClip>>(world);
+    // The player doesn't expose the clip it plays, but reflects it
+    let handle = player
+        .path::<Handle<AnimationClip>>("animation.animation_clip")
+        .ok()?;
+    let clip = clips.get(handle)?;
+    Some((player.elapsed(), clip.duration()))
+}
+
+/// Returns a condition resolving once the playback of `entity` goes past `time(world)` seconds
+/// into the clip, on any loop. The time is checked from the first time the condition is polled.
+fn crossing(
+    entity: Entity,
+    mut time: impl FnMut(&World) -> Option<f32>,
+) -> impl FnMut(&World) -> Option<()> {
+    let mut previous: Option<f32> = None;
+    move |world| {
+        let (elapsed, duration) = playback(world, entity)?;
+        let target = time(world)?;
+        let prev = previous.replace(elapsed)?;
+        if duration <= 0.0 || elapsed < prev {
+            // The clip was restarted or changed
+            return None;
+        }
+
+        // Look for a `k` such that `prev < k * duration + target <= elapsed`
+        let k = ((prev - target) / duration).floor() + 1.0;
+        (k * duration + target <= elapsed).then_some(())
+    }
+}
+
+impl Scope {
+    /// Returns a future that resolve once the clip played by the [`AnimationPlayer`] of `entity`
+    /// reaches its end. Note that a repeating clip never finishes.
+    pub fn animation_finished(
+        &mut self,
+        entity: Entity,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            let (elapsed, duration) = playback(world, entity)?;
+            // The elapsed time of a repeating clip keeps growing past its duration
+            let repeating = *world
+                .get::<AnimationPlayer>(entity)?
+                .path::<bool>("animation.repeat")
+                .ok()?;
+            (!repeating && elapsed >= duration).then_some(())
+        })
+    }
+
+    /// Returns a future that resolve the next time the clip played by the [`AnimationPlayer`] of
+    /// `entity` loops (or finishes, if it doesn't repeat).
+    pub fn animation_looped(
+        &mut self,
+        entity: Entity,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(crossing(entity, |_| Some(0.0)))
+    }
+
+    /// Returns a future that resolve the next time the clip played by the [`AnimationPlayer`] of
+    /// `entity` goes past `time` seconds.
+    pub fn animation_reached(
+        &mut self,
+        entity: Entity,
+        time: f32,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(crossing(entity, move |_| Some(time)))
+    }
+
+    /// Returns a future that resolve the next time the clip played by the [`AnimationPlayer`] of
+    /// `entity` goes past the marker `name`, defined in the [`AnimationMarkers`] of `entity`.
+    pub fn animation_marker(
+        &mut self,
+        entity: Entity,
+        name: impl Into<String>,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        let name = name.into();
+        self.until(crossing(entity, move |world| {
+            world
+                .get::<AnimationMarkers>(entity)?
+                .markers
+                .get(&name)
+                .copied()
+        }))
+    }
+}
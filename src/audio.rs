@@ -0,0 +1,36 @@
+//! Waits on audio playback.
+
+use bevy::{
+    audio::{AudioSink, AudioSinkPlayback, SpatialAudioSink},
+    prelude::{Entity, World},
+};
+
+use crate::function_coroutine::{await_condition::AwaitCondition, scope::Scope};
+
+impl Scope {
+    /// Returns a future that resolve once the audio played by `entity` (spawned with an
+    /// `AudioBundle` or a `SpatialAudioBundle`) has finished.
+    ///
+    /// The playback is also considered finished if `entity` is despawned, which is what happens
+    /// with [`PlaybackMode::Despawn`](bevy::audio::PlaybackMode::Despawn).
+    pub fn audio_finished(
+        &mut self,
+        entity: Entity,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            let Some(entity) = world.get_entity(entity) else {
+                return Some(());
+            };
+
+            // The sink is only added once the audio source is loaded
+            let finished = if let Some(sink) = entity.get::<AudioSink>() {
+                sink.empty()
+            } else if let Some(sink) = entity.get::<SpatialAudioSink>() {
+                sink.empty()
+            } else {
+                false
+            };
+            finished.then_some(())
+        })
+    }
+}
@@ -0,0 +1,189 @@
+//! Behavior trees whose leaves are coroutines, to mix the structure of a behavior tree with
+//! leaves written as plain `async` code. Available behind the `behavior-tree` feature.
+//!
+//! A leaf is a coroutine returning a `bool`, `true` being a success. Each node of the tree runs
+//! in its own coroutine, which is dropped along with all its children once the node is done.
+//! Since a node can run several times, leaves must be [`Clone`], which is the case of functions
+//! and closures only capturing clonable values.
+//!
+//! ```ignore
+//! let guard = Node::selector([
+//!     Node::sequence([Node::leaf(see_intruder), Node::leaf(chase)]),
+//!     Node::leaf(patrol),
+//! ])
+//! .repeat_forever();
+//!
+//! guard.run(&mut s).await;
+//! ```
+
+use std::{future::Future, sync::Arc};
+
+use crate::function_coroutine::{
+    handle::{CoroHandle, HandleTuple, Status},
+    scope::Scope,
+    CoroutineParamFunction,
+};
+
+type Start = Arc<dyn Fn(&mut Scope) -> CoroHandle<bool> + Send + Sync>;
+
+/// When a [`Node::parallel`] node is done.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Policy {
+    /// Succeed as soon as one child succeeds, and fail once all of them failed.
+    Any,
+    /// Fail as soon as one child fails, and succeed once all of them succeeded.
+    All,
+}
+
+/// A node of a behavior tree, see the [module level documentation](self). Cloning a node is
+/// cheap, the clone shares the same children.
+#[derive(Clone)]
+pub struct Node {
+    start: Start,
+}
+
+impl Node {
+    /// A node running `coroutine`, and succeeding if it returns `true`.
+    pub fn leaf<Marker: 'static, C>(coroutine: C) -> Self
+    where
+        C: CoroutineParamFunction<Marker, bool> + Clone + Send + Sync,
+    {
+        Self {
+            start: Arc::new(move |s: &mut Scope| s.start(coroutine.clone())),
+        }
+    }
+
+    /// A node running `children` one after the other, until one of them fails. Succeeds if none
+    /// of them failed.
+    pub fn sequence(children: impl IntoIterator<Item = Node>) -> Self {
+        Self::in_order(children, false)
+    }
+
+    /// A node running `children` one after the other, until one of them succeeds. Fails if none
+    /// of them succeeded.
+    pub fn selector(children: impl IntoIterator<Item = Node>) -> Self {
+        Self::in_order(children, true)
+    }
+
+    /// A node running all of its `children` concurrently, until the `policy` is fulfilled. The
+    /// children which are still running are then dropped.
+    pub fn parallel(policy: Policy, children: impl IntoIterator<Item = Node>) -> Self {
+        let children: Arc<[Node]> = children.into_iter().collect();
+        let stop_on = policy == Policy::Any;
+        Self::with(move |mut s| {
+            let children = Arc::clone(&children);
+            async move {
+                let mut handles: Vec<_> = children.iter().map(|c| c.start(&mut s)).collect();
+                loop {
+                    let mut running = false;
+                    for handle in handles.iter_mut() {
+                        match handle.update_status() {
+                            Status::Done => {
+                                if handle.try_fetch() == Some(stop_on) {
+                                    return stop_on;
+                                }
+                            }
+                            Status::StillWaiting(_) => running = true,
+                            // A canceled child counts as a failure
+                            Status::Canceled if !stop_on => return false,
+                            Status::Canceled | Status::Consumed => {}
+                        }
+                    }
+
+                    if !running {
+                        return !stop_on;
+                    }
+                    s.next_tick().await;
+                }
+            }
+        })
+    }
+
+    /// Invert the result of this node.
+    pub fn invert(self) -> Self {
+        self.map(|success| !success)
+    }
+
+    /// Succeed once this node is done, whatever its result.
+    pub fn succeed(self) -> Self {
+        self.map(|_| true)
+    }
+
+    /// Run this node again until it fails, at most `times` times. Succeeds if it never failed.
+    pub fn repeat(self, times: usize) -> Self {
+        self.again(Some(times), false)
+    }
+
+    /// Run this node again until it fails. Succeeds only if the node never fails, that is never.
+    pub fn repeat_forever(self) -> Self {
+        self.again(None, false)
+    }
+
+    /// Run this node again until it succeeds, at most `times` times. Fails if it never succeeded.
+    pub fn retry(self, times: usize) -> Self {
+        self.again(Some(times), true)
+    }
+
+    /// Run this tree from `scope`, and returns whether it succeeded. If the scope is dropped, the
+    /// whole tree is dropped as well.
+    pub async fn run(&self, scope: &mut Scope) -> bool {
+        let handle = self.start(scope);
+        scope.on(handle).await
+    }
+
+    /// Start this node as a coroutine, and returns a handle to its result.
+    pub fn start(&self, scope: &mut Scope) -> CoroHandle<bool> {
+        (self.start)(scope)
+    }
+
+    fn with<Fut>(f: impl Fn(Scope) -> Fut + Send + Sync + 'static) -> Self
+    where
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        let f = Arc::new(f);
+        Self {
+            start: Arc::new(move |s: &mut Scope| {
+                let f = Arc::clone(&f);
+                s.start(move |s: Scope| f(s))
+            }),
+        }
+    }
+
+    fn in_order(children: impl IntoIterator<Item = Node>, stop_on: bool) -> Self {
+        let children: Arc<[Node]> = children.into_iter().collect();
+        Self::with(move |mut s| {
+            let children = Arc::clone(&children);
+            async move {
+                for child in children.iter() {
+                    if child.run(&mut s).await == stop_on {
+                        return stop_on;
+                    }
+                }
+                !stop_on
+            }
+        })
+    }
+
+    fn map(self, f: fn(bool) -> bool) -> Self {
+        Self::with(move |mut s| {
+            let node = self.clone();
+            async move { f(node.run(&mut s).await) }
+        })
+    }
+
+    fn again(self, times: Option<usize>, stop_on: bool) -> Self {
+        Self::with(move |mut s| {
+            let node = self.clone();
+            async move {
+                let mut count = 0;
+                while times.map_or(true, |t| count < t) {
+                    count += 1;
+                    if node.run(&mut s).await == stop_on {
+                        return stop_on;
+                    }
+                }
+                !stop_on
+            }
+        })
+    }
+}
@@ -0,0 +1,257 @@
+//! Helpers for scripted camera work. They work on any entity with a [`Transform`], but are meant
+//! to be used with cameras.
+
+use std::time::Duration;
+
+use bevy::prelude::{Component, Entity, Quat, Transform, Vec3, World};
+
+use crate::{
+    function_coroutine::{await_time::IntoDuration, scope::Scope},
+    tween::Ease,
+};
+
+impl Scope {
+    /// Move `camera` from its current transform to `target` over `duration`, following the `ease`
+    /// curve. If the camera is shaking, this moves the camera itself and the shake keeps being
+    /// applied on top of it.
+    ///
+    /// Returns early if `camera` or its [`Transform`] is removed.
+    pub async fn camera_to(
+        &mut self,
+        camera: Entity,
+        target: Transform,
+        duration: impl IntoDuration,
+        ease: Ease,
+    ) {
+        let duration = duration.into_duration();
+        let Some(start) = unshaken(&self.world(), camera) else {
+            return;
+        };
+
+        let mut elapsed = Duration::ZERO;
+        loop {
+            let t = if duration.is_zero() {
+                1.0
+            } else {
+                elapsed.as_secs_f32() / duration.as_secs_f32()
+            };
+            let k = ease.sample(t);
+            let transform = Transform {
+                translation: start.translation.lerp(target.translation, k),
+                rotation: start.rotation.slerp(target.rotation, k),
+                scale: start.scale.lerp(target.scale, k),
+            };
+            if !self.set_unshaken(camera, transform) || t >= 1.0 {
+                return;
+            }
+            elapsed += self.next_tick().await;
+        }
+    }
+
+    /// Make `camera` follow `target` for `duration`, staying at `offset` from it. `smoothing` is
+    /// the rate (per second) at which the camera catches up with the target, use `f32::INFINITY`
+    /// to stick to it. Like [`Scope::camera_to`], this moves the camera under its shake.
+    ///
+    /// Returns early if `camera` or `target` is despawned.
+    pub async fn camera_follow(
+        &mut self,
+        camera: Entity,
+        target: Entity,
+        offset: Vec3,
+        duration: impl IntoDuration,
+        smoothing: f32,
+    ) {
+        let duration = duration.into_duration();
+        let mut elapsed = Duration::ZERO;
+        let mut dt = Duration::ZERO;
+        while elapsed < duration {
+            let (mut transform, goal) = {
+                let world = self.world();
+                let (Some(camera_transform), Some(target_transform)) =
+                    (unshaken(&world, camera), world.get::<Transform>(target))
+                else {
+                    return;
+                };
+                (camera_transform, target_transform.translation + offset)
+            };
+
+            let k = if smoothing.is_finite() {
+                1.0 - (-smoothing * dt.as_secs_f32()).exp()
+            } else {
+                1.0
+            };
+            transform.translation = transform.translation.lerp(goal, k);
+            self.set_unshaken(camera, transform);
+
+            dt = self.next_tick().await;
+            elapsed += dt;
+        }
+    }
+
+    /// Set the transform of `camera` without its shake, and apply the shake on top of it if the
+    /// camera is shaking. Returns false if `camera` has no [`Transform`].
+    fn set_unshaken(&mut self, camera: Entity, transform: Transform) -> bool {
+        let shaken = self.component_mut::<Shaken>(camera).map(|mut shaken| {
+            shaken.unshaken = transform;
+            shaken.transform()
+        });
+        match self.component_mut::<Transform>(camera) {
+            Some(mut current) => {
+                *current = shaken.unwrap_or(transform);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns the transform of `camera` without the shake of [`Scope::camera_shake`].
+fn unshaken(world: &World, camera: Entity) -> Option<Transform> {
+    let transform = world.get::<Transform>(camera)?;
+    match world.get::<Shaken>(camera) {
+        Some(shaken) => Some(shaken.unshaken),
+        None => Some(*transform),
+    }
+}
+
+/// Added to a camera while [`Scope::camera_shake`] runs. The transform without the shake is kept
+/// apart, so that the other camera helpers move it instead of the shaken one, and so that it can
+/// be restored exactly once the shake is over.
+#[derive(Component, Clone, Copy)]
+struct Shaken {
+    unshaken: Transform,
+    offset: Vec3,
+    roll: Quat,
+}
+
+impl Shaken {
+    /// The transform of the camera with the shake applied.
+    fn transform(&self) -> Transform {
+        Transform {
+            translation: self.unshaken.translation + self.offset,
+            rotation: self.unshaken.rotation * self.roll,
+            scale: self.unshaken.scale,
+        }
+    }
+}
+
+/// The parameters of a camera shake, see [`Scope::camera_shake`]. It must be added to the camera.
+///
+/// The strength of the shake depends on the trauma, between 0 and 1, which can be accumulated
+/// from anywhere with [`CameraShake::add_trauma`] and decays over time.
+#[derive(Component, Clone, Debug)]
+pub struct CameraShake {
+    /// The current trauma, between 0 and 1.
+    pub trauma: f32,
+    /// The maximum translation offset, reached at full trauma.
+    pub max_offset: Vec3,
+    /// The maximum roll (rotation around the forward axis) in radians, reached at full trauma.
+    pub max_roll: f32,
+    /// How fast the camera shakes.
+    pub frequency: f32,
+    /// How much trauma is lost per second.
+    pub decay: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            max_offset: Vec3::new(10.0, 10.0, 0.0),
+            max_roll: 0.05,
+            frequency: 15.0,
+            decay: 1.0,
+        }
+    }
+}
+
+impl CameraShake {
+    /// Add some trauma, the result being clamped to 1.
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+}
+
+/// A cheap smooth noise between -1 and 1, `seed` decorrelating the different axes.
+fn noise(t: f32, seed: f32) -> f32 {
+    ((t + seed * 17.3).sin() * 0.6 + (t * 2.31 + seed * 5.7).sin() * 0.4).clamp(-1.0, 1.0)
+}
+
+impl Scope {
+    /// Shake `camera` according to its [`CameraShake`], until its trauma reaches 0. This is meant
+    /// to run as a background coroutine, for instance with [`Scope::start_local`]. The shake
+    /// starts on the next tick, and only one shake per camera should run at a time.
+    ///
+    /// The shake is applied on top of the [`Transform`] of the camera, so it can be combined with
+    /// the other camera helpers, and with systems moving the camera. The transform without the
+    /// shake is restored when this returns, or when the coroutine running it is canceled.
+    pub async fn camera_shake(&mut self, camera: Entity) {
+        let _guard = self.cleanup_guard(move |world| {
+            let Some(mut entity) = world.get_entity_mut(camera) else {
+                return;
+            };
+            if let Some(shaken) = entity.take::<Shaken>() {
+                if let Some(mut transform) = entity.get_mut::<Transform>() {
+                    *transform = shaken.unshaken;
+                }
+            }
+        });
+        // Adding a component is a structural change, so it can only happen at the end of the tick
+        self.commands().add(move |world: &mut World| {
+            let Some(mut entity) = world.get_entity_mut(camera) else {
+                return;
+            };
+            if let Some(transform) = entity.get::<Transform>().copied() {
+                entity.insert(Shaken {
+                    unshaken: transform,
+                    offset: Vec3::ZERO,
+                    roll: Quat::IDENTITY,
+                });
+            }
+        });
+        self.next_tick().await;
+
+        let mut time = 0.0;
+        let mut dt = 0.0;
+        loop {
+            let Some(shake) = self.component_mut::<CameraShake>(camera).map(|mut shake| {
+                shake.trauma = (shake.trauma - shake.decay * dt).max(0.0);
+                shake.clone()
+            }) else {
+                return;
+            };
+            let Some(current) = self.world().get::<Transform>(camera).copied() else {
+                return;
+            };
+
+            time += dt * shake.frequency;
+            let strength = shake.trauma * shake.trauma;
+            let offset = shake.max_offset
+                * Vec3::new(noise(time, 0.0), noise(time, 1.0), noise(time, 2.0))
+                * strength;
+            let roll = Quat::from_rotation_z(shake.max_roll * strength * noise(time, 3.0));
+
+            let Some(shaken) = self.component_mut::<Shaken>(camera).map(|mut shaken| {
+                // The camera was moved by something else than the camera helpers, which is kept
+                if current != shaken.transform() {
+                    shaken.unshaken = Transform {
+                        translation: current.translation - shaken.offset,
+                        rotation: current.rotation * shaken.roll.inverse(),
+                        scale: current.scale,
+                    };
+                }
+                shaken.offset = offset;
+                shaken.roll = roll;
+                shaken.transform()
+            }) else {
+                return;
+            };
+            self.modify(camera, move |t: &mut Transform| *t = shaken);
+
+            if shake.trauma <= 0.0 {
+                return;
+            }
+            dt = self.next_tick().await.as_secs_f32();
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Color interpolation, in a selectable color space.
+
+use bevy::prelude::{BackgroundColor, Color, Component, Entity, Sprite, Text, TextureAtlasSprite};
+
+use crate::{
+    function_coroutine::{await_time::IntoDuration, scope::Scope},
+    tween::Ease,
+};
+
+/// The color space in which colors are interpolated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Interpolate the gamma encoded components. This is what a naive lerp does, and tends to
+    /// produce dark intermediate colors.
+    Srgb,
+    /// Interpolate the linear components.
+    Linear,
+    /// Interpolate in the perceptual Oklab space, which gives the most even gradients.
+    #[default]
+    Oklab,
+}
+
+impl ColorSpace {
+    /// Interpolate between `from` and `to`, `k` being the interpolation factor. The alpha channel
+    /// is always interpolated linearly.
+    pub fn lerp(&self, from: Color, to: Color, k: f32) -> Color {
+        let lerp = |a: [f32; 4], b: [f32; 4]| -> [f32; 4] {
+            std::array::from_fn(|i| a[i] + (b[i] - a[i]) * k)
+        };
+
+        match self {
+            ColorSpace::Srgb => {
+                let [r, g, b, a] = lerp(from.as_rgba_f32(), to.as_rgba_f32());
+                Color::rgba(r, g, b, a)
+            }
+            ColorSpace::Linear => {
+                let [r, g, b, a] = lerp(from.as_linear_rgba_f32(), to.as_linear_rgba_f32());
+                Color::rgba_linear(r, g, b, a)
+            }
+            ColorSpace::Oklab => {
+                let [l, a, b, alpha] = lerp(to_oklab(from), to_oklab(to));
+                from_oklab([l, a, b, alpha])
+            }
+        }
+    }
+}
+
+/// Convert a color to Oklab, the alpha being kept as the last component.
+#[allow(clippy::excessive_precision)]
+fn to_oklab(color: Color) -> [f32; 4] {
+    let [r, g, b, alpha] = color.as_linear_rgba_f32();
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+        alpha,
+    ]
+}
+
+/// Convert a color from Oklab, the alpha being the last component.
+#[allow(clippy::excessive_precision)]
+fn from_oklab([l, a, b, alpha]: [f32; 4]) -> Color {
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    Color::rgba_linear(
+        4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+        -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+        -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+        alpha,
+    )
+}
+
+/// A component with a color, which can be interpolated with [`Scope::lerp_color`].
+pub trait Colored: Component {
+    /// Returns the current color.
+    fn color(&self) -> Color;
+
+    /// Set the color.
+    fn set_color(&mut self, color: Color);
+}
+
+impl Colored for Sprite {
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+}
+
+impl Colored for TextureAtlasSprite {
+    fn color(&self) -> Color {
+        self.color
+    }
+
+    fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+}
+
+impl Colored for BackgroundColor {
+    fn color(&self) -> Color {
+        self.0
+    }
+
+    fn set_color(&mut self, color: Color) {
+        self.0 = color;
+    }
+}
+
+/// The color of a [`Text`] is the one of its first section, but all sections are colored.
+impl Colored for Text {
+    fn color(&self) -> Color {
+        self.sections
+            .first()
+            .map_or(Color::WHITE, |s| s.style.color)
+    }
+
+    fn set_color(&mut self, color: Color) {
+        for section in &mut self.sections {
+            section.style.color = color;
+        }
+    }
+}
+
+impl Scope {
+    /// Interpolate the color of the component `C` of `entity` from its current value to `target`
+    /// over `duration`, in the color `space`. Returns `false` if the entity or its component was
+    /// removed before the end.
+    pub async fn lerp_color<C: Colored>(
+        &mut self,
+        entity: Entity,
+        target: Color,
+        duration: impl IntoDuration,
+        space: ColorSpace,
+    ) -> bool {
+        let duration = duration.into_duration();
+        let Some(start) = self.world().get::<C>(entity).map(C::color) else {
+            return false;
+        };
+
+        self.tween(entity, duration, Ease::Linear, |c: &mut C, k| {
+            c.set_color(space.lerp(start, target, k))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oklab_roundtrip() {
+        let color = Color::rgba_linear(0.2, 0.5, 0.9, 0.3);
+        let [r, g, b, a] = from_oklab(to_oklab(color)).as_linear_rgba_f32();
+        assert!((r - 0.2).abs() < 1e-4);
+        assert!((g - 0.5).abs() < 1e-4);
+        assert!((b - 0.9).abs() < 1e-4);
+        assert_eq!(a, 0.3);
+    }
+
+    #[test]
+    fn lerp_bounds() {
+        for space in [ColorSpace::Srgb, ColorSpace::Linear, ColorSpace::Oklab] {
+            let from = Color::RED;
+            let to = Color::BLUE;
+            let [r, _, b, _] = space.lerp(from, to, 0.0).as_rgba_f32();
+            assert!((r - 1.0).abs() < 1e-3 && b.abs() < 1e-3);
+            let [r, _, b, _] = space.lerp(from, to, 1.0).as_rgba_f32();
+            assert!(r.abs() < 1e-3 && (b - 1.0).abs() < 1e-3);
+        }
+    }
+}
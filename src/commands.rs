@@ -26,7 +26,26 @@ where
 {
     fn apply(self, owner: Entity, world: &mut World) {
         world.resource_scope::<Executor, ()>(|world, mut executor| {
-            executor.add_function_coroutine(Some(owner), world, self.coroutine);
+            executor.build(self.coroutine).owner(owner).spawn(world);
+        });
+    }
+}
+
+pub struct AddCoroutineBatch<Marker, T, C> {
+    coroutines: Vec<(Option<Entity>, C)>,
+    _phantom1: PhantomData<Marker>,
+    _phantom2: PhantomData<T>,
+}
+
+impl<Marker, C, T> Command for AddCoroutineBatch<Marker, T, C>
+where
+    C: CoroutineParamFunction<Marker, T>,
+    T: Sync + Send + 'static,
+    Marker: 'static + Send,
+{
+    fn apply(self, world: &mut World) {
+        world.resource_scope::<Executor, ()>(|w, mut executor| {
+            executor.add_batch(w, self.coroutines);
         });
     }
 }
@@ -39,12 +58,28 @@ where
 {
     fn apply(self, world: &mut World) {
         world.resource_scope::<Executor, ()>(|w, mut executor| {
-            executor.add_function_coroutine(None, w, self.coroutine);
+            executor.build(self.coroutine).spawn(w);
         });
     }
 }
 
-pub fn root_coroutine<M, C, T>(coroutine: C) -> AddRootCoroutine<M, T, C> {
+/// A [`Command`] adding `coroutine` to the [`Executor`], without any owner entity.
+///
+/// `coroutine` is either an `async fn`, or a closure returning a future. Its first parameter is
+/// the [`Scope`](crate::prelude::Scope), and the others are [`CoroParam`]s:
+///
+/// ```ignore
+/// async fn intro(mut s: Scope) { /* ... */ }
+///
+/// commands.add(root_coroutine(intro));
+/// commands.add(root_coroutine(|mut s: Scope| async move { /* ... */ }));
+/// ```
+///
+/// [`CoroParam`]: crate::function_coroutine::coro_param::CoroParam
+pub fn root_coroutine<M, C, T>(coroutine: C) -> AddRootCoroutine<M, T, C>
+where
+    C: CoroutineParamFunction<M, T>,
+{
     AddRootCoroutine {
         coroutine,
         _phantom1: PhantomData,
@@ -52,10 +87,47 @@ pub fn root_coroutine<M, C, T>(coroutine: C) -> AddRootCoroutine<M, T, C> {
     }
 }
 
-pub fn coroutine<M, C, T>(coroutine: C) -> AddCoroutineTo<M, T, C> {
+/// An [`EntityCommand`] adding `coroutine` to the [`Executor`], owned by the entity. Accepts the
+/// same functions as [`root_coroutine`], which can also take the owner as an [`Entity`]
+/// parameter:
+///
+/// ```ignore
+/// async fn blink(mut s: Scope, owner: Entity) { /* ... */ }
+///
+/// commands.entity(e).add(coroutine(blink));
+/// ```
+///
+/// The entity is given a [`CoroutineOwner`] marker component on the next tick, so that its
+/// coroutines are canceled as soon as it is despawned. Like any insertion, this moves the entity
+/// to another archetype.
+///
+/// [`CoroutineOwner`]: crate::executor::owners::CoroutineOwner
+pub fn coroutine<M, C, T>(coroutine: C) -> AddCoroutineTo<M, T, C>
+where
+    C: CoroutineParamFunction<M, T>,
+{
     AddCoroutineTo {
         coroutine,
         _phantom1: PhantomData,
         _phantom2: PhantomData,
     }
 }
+
+/// A [`Command`] adding many coroutines to the [`Executor`] at once, each with its owner entity
+/// if any. See [`Executor::add_batch`].
+///
+/// ```ignore
+/// commands.add(coroutine_batch(enemies.iter().map(|&e| (Some(e), patrol))));
+/// ```
+pub fn coroutine_batch<M, C, T>(
+    coroutines: impl IntoIterator<Item = (Option<Entity>, C)>,
+) -> AddCoroutineBatch<M, T, C>
+where
+    C: CoroutineParamFunction<M, T>,
+{
+    AddCoroutineBatch {
+        coroutines: coroutines.into_iter().collect(),
+        _phantom1: PhantomData,
+        _phantom2: PhantomData,
+    }
+}
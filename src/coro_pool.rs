@@ -0,0 +1,190 @@
+//! Recycling of the allocations of coroutines. Scripts tend to spawn the same shapes of
+//! sub-coroutines over and over (every frame, or every loop iteration), so instead of going
+//! through the allocator each time, freed blocks are kept per size class and reused.
+
+use std::{
+    alloc::{alloc, dealloc, handle_alloc_error, Layout},
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    ptr::NonNull,
+    sync::{Arc, Mutex},
+};
+
+use crate::Coroutine;
+
+/// The smallest size class, in bytes. Each class is twice as big as the previous one.
+const MIN_SIZE: usize = 64;
+const CLASSES: usize = 8;
+/// Every pooled block has this alignment, coroutines which need more are simply boxed.
+const ALIGN: usize = 16;
+/// How many free blocks are kept per size class, the others are given back to the allocator.
+const MAX_FREE: usize = 256;
+
+struct Block(NonNull<u8>);
+
+// Safety: A free block is just memory, not owned by any thread.
+unsafe impl Send for Block {}
+
+#[derive(Default)]
+struct Classes([Mutex<Vec<Block>>; CLASSES]);
+
+impl Drop for Classes {
+    fn drop(&mut self) {
+        for (class, blocks) in self.0.iter_mut().enumerate() {
+            for block in blocks.get_mut().unwrap().drain(..) {
+                // Safety: Every block of a class was allocated with this layout.
+                unsafe { dealloc(block.0.as_ptr(), class_layout(class)) };
+            }
+        }
+    }
+}
+
+/// A pool of allocations for coroutines, shared by an [`Executor`](crate::executor::Executor) and
+/// all of its scopes.
+#[derive(Default, Clone)]
+pub(crate) struct CoroPool {
+    classes: Arc<Classes>,
+}
+
+impl CoroPool {
+    /// Move `coroutine` to the heap, reusing a free block of the right size class if any.
+    pub fn alloc<C: Coroutine>(&self, coroutine: C) -> Pin<PooledCoro> {
+        let Some(class) = class_of(Layout::new::<C>()) else {
+            let ptr: *mut dyn Coroutine = Box::into_raw(Box::new(coroutine));
+            // Safety: The coroutine is never moved out of its box.
+            return unsafe {
+                Pin::new_unchecked(PooledCoro {
+                    ptr: NonNull::new_unchecked(ptr),
+                    class: None,
+                    pool: self.clone(),
+                })
+            };
+        };
+
+        let block = self.take_block(class);
+        let ptr = block.0.as_ptr() as *mut C;
+        // Safety: The block is big enough and aligned for `C`, and isn't used by anything else.
+        unsafe {
+            ptr.write(coroutine);
+            let ptr: *mut dyn Coroutine = ptr;
+            Pin::new_unchecked(PooledCoro {
+                ptr: NonNull::new_unchecked(ptr),
+                class: Some(class),
+                pool: self.clone(),
+            })
+        }
+    }
+
+    fn take_block(&self, class: usize) -> Block {
+        if let Some(block) = self.classes.0[class].lock().unwrap().pop() {
+            return block;
+        }
+
+        let layout = class_layout(class);
+        // Safety: The layout has a non zero size.
+        let ptr = unsafe { alloc(layout) };
+        match NonNull::new(ptr) {
+            Some(ptr) => Block(ptr),
+            None => handle_alloc_error(layout),
+        }
+    }
+
+    fn release(&self, class: usize, block: Block) {
+        let mut free = self.classes.0[class].lock().unwrap();
+        if free.len() < MAX_FREE {
+            free.push(block);
+        } else {
+            // Safety: Every block of a class was allocated with this layout.
+            unsafe { dealloc(block.0.as_ptr(), class_layout(class)) };
+        }
+    }
+
+    #[cfg(test)]
+    fn free_blocks(&self, class: usize) -> usize {
+        self.classes.0[class].lock().unwrap().len()
+    }
+}
+
+fn class_of(layout: Layout) -> Option<usize> {
+    if layout.align() > ALIGN {
+        return None;
+    }
+
+    let size = layout.size().max(MIN_SIZE).next_power_of_two();
+    let class = (size / MIN_SIZE).trailing_zeros() as usize;
+    (class < CLASSES).then_some(class)
+}
+
+fn class_layout(class: usize) -> Layout {
+    Layout::from_size_align(MIN_SIZE << class, ALIGN).unwrap()
+}
+
+/// A coroutine allocated from a [`CoroPool`]. Its block goes back to the pool once dropped.
+pub struct PooledCoro {
+    ptr: NonNull<dyn Coroutine>,
+    /// `None` if the coroutine didn't fit any size class, and was boxed instead.
+    class: Option<usize>,
+    pool: CoroPool,
+}
+
+// Safety: `PooledCoro` owns its coroutine, which is `Send`.
+unsafe impl Send for PooledCoro {}
+
+impl Deref for PooledCoro {
+    type Target = dyn Coroutine;
+
+    fn deref(&self) -> &Self::Target {
+        // Safety: The pointer is valid until `self` is dropped.
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl DerefMut for PooledCoro {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        // Safety: The pointer is valid until `self` is dropped, and uniquely owned.
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl Drop for PooledCoro {
+    fn drop(&mut self) {
+        // Safety: The coroutine is dropped exactly once, and its memory is not used afterward.
+        unsafe {
+            match self.class {
+                None => drop(Box::from_raw(self.ptr.as_ptr())),
+                Some(class) => {
+                    std::ptr::drop_in_place(self.ptr.as_ptr());
+                    self.pool.release(class, Block(self.ptr.cast()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_classes() {
+        assert_eq!(class_of(Layout::new::<()>()), Some(0));
+        assert_eq!(class_of(Layout::new::<[u8; 64]>()), Some(0));
+        assert_eq!(class_of(Layout::new::<[u8; 65]>()), Some(1));
+        assert_eq!(class_of(Layout::new::<[u8; 8192]>()), Some(7));
+        assert_eq!(class_of(Layout::new::<[u8; 8193]>()), None);
+    }
+
+    #[test]
+    fn blocks_are_reused() {
+        let pool = CoroPool::default();
+        let block = pool.take_block(2);
+        let ptr = block.0;
+        pool.release(2, block);
+        assert_eq!(pool.free_blocks(2), 1);
+
+        let block = pool.take_block(2);
+        assert_eq!(block.0, ptr);
+        assert_eq!(pool.free_blocks(2), 0);
+        pool.release(2, block);
+    }
+}
@@ -0,0 +1,157 @@
+//! A small set of helpers to write cutscenes and dialogues, built on top of the other primitives
+//! of this crate. Available behind the `cutscene` feature.
+//!
+//! ```ignore
+//! async fn intro(mut s: Scope, text: Entity, hero: Entity) {
+//!     show_text(&mut s, text, "Where am I?").await;
+//!     move_actor_to(&mut s, hero, Vec3::new(100., 0., 0.), 50.).await;
+//!     typewriter(&mut s, text, 30.).await;
+//!     wait_for_advance_input(&mut s).await;
+//! }
+//! ```
+
+use bevy::prelude::{
+    Entity, GamepadButton, GamepadButtonType, Gamepads, Input, KeyCode, MouseButton, Resource,
+    Text, Vec3, World,
+};
+
+use std::time::Duration;
+
+use crate::{function_coroutine::scope::Scope, transform::Pace};
+
+/// The inputs which advance a cutscene, see [`wait_for_advance_input`]. Insert it as a resource
+/// to override the default ones (space, enter, left click and the south gamepad button).
+#[derive(Resource, Clone, Debug)]
+pub struct AdvanceInput {
+    pub keys: Vec<KeyCode>,
+    pub mouse_buttons: Vec<MouseButton>,
+    pub gamepad_buttons: Vec<GamepadButtonType>,
+}
+
+impl Default for AdvanceInput {
+    fn default() -> Self {
+        Self {
+            keys: vec![KeyCode::Space, KeyCode::Return],
+            mouse_buttons: vec![MouseButton::Left],
+            gamepad_buttons: vec![GamepadButtonType::South],
+        }
+    }
+}
+
+impl AdvanceInput {
+    fn just_pressed(&self, world: &World) -> bool {
+        if let Some(keys) = world.get_resource::<Input<KeyCode>>() {
+            if keys.any_just_pressed(self.keys.iter().copied()) {
+                return true;
+            }
+        }
+
+        if let Some(buttons) = world.get_resource::<Input<MouseButton>>() {
+            if buttons.any_just_pressed(self.mouse_buttons.iter().copied()) {
+                return true;
+            }
+        }
+
+        if let (Some(gamepads), Some(buttons)) = (
+            world.get_resource::<Gamepads>(),
+            world.get_resource::<Input<GamepadButton>>(),
+        ) {
+            for gamepad in gamepads.iter() {
+                let mut pressed = self
+                    .gamepad_buttons
+                    .iter()
+                    .map(|b| GamepadButton::new(gamepad, *b));
+                if pressed.any(|b| buttons.just_pressed(b)) {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// Wait until the player presses any of the [`AdvanceInput`].
+pub async fn wait_for_advance_input(s: &mut Scope) {
+    s.until(|world| {
+        let default = AdvanceInput::default();
+        let input = world.get_resource::<AdvanceInput>().unwrap_or(&default);
+        input.just_pressed(world).then_some(())
+    })
+    .await;
+}
+
+/// Replace the value of the first section of the [`Text`] of `entity` by `text`, and wait until
+/// the player advances.
+pub async fn show_text(s: &mut Scope, entity: Entity, text: impl Into<String>) {
+    let text = text.into();
+    s.commands().add(move |world: &mut World| {
+        if let Some(mut t) = world.get_mut::<Text>(entity) {
+            if let Some(section) = t.sections.first_mut() {
+                section.value = text;
+            }
+        }
+    });
+    // Let the text be displayed before checking the inputs, so the input which advanced the
+    // previous text isn't counted twice
+    s.next_tick().await;
+    wait_for_advance_input(s).await;
+}
+
+/// Progressively reveal the current content of the [`Text`] of `entity`, at `chars_per_second`,
+/// across all its sections. If the player presses any of the [`AdvanceInput`] in the meantime,
+/// the whole text is shown at once.
+///
+/// Returns once the text is entirely visible, or `false` if `entity` or its [`Text`] was removed
+/// before that.
+pub async fn typewriter(s: &mut Scope, entity: Entity, chars_per_second: f32) -> bool {
+    let full: Vec<String> = match s.component_mut::<Text>(entity) {
+        Some(mut text) => text
+            .sections
+            .iter_mut()
+            .map(|section| std::mem::take(&mut section.value))
+            .collect(),
+        None => return false,
+    };
+    let total: usize = full.iter().map(|v| v.chars().count()).sum();
+
+    let mut elapsed = Duration::ZERO;
+    let mut shown = 0;
+    while shown < total {
+        elapsed += s.next_tick().await;
+
+        let skip = {
+            let default = AdvanceInput::default();
+            let world = s.world();
+            let input = world.get_resource::<AdvanceInput>().unwrap_or(&default);
+            input.just_pressed(&world)
+        };
+        let target = if skip {
+            total
+        } else {
+            ((elapsed.as_secs_f32() * chars_per_second) as usize).min(total)
+        };
+        if target == shown {
+            continue;
+        }
+        shown = target;
+
+        let Some(mut text) = s.component_mut::<Text>(entity) else {
+            return false;
+        };
+        let mut remaining = shown;
+        for (section, value) in text.sections.iter_mut().zip(&full) {
+            section.value = value.chars().take(remaining).collect();
+            remaining = remaining.saturating_sub(value.chars().count());
+        }
+    }
+
+    true
+}
+
+/// Move `actor` in a straight line toward `target`, at `speed` units per second, and returns
+/// once it is reached. Returns early if `actor` or its
+/// [`Transform`](bevy::prelude::Transform) is removed.
+pub async fn move_actor_to(s: &mut Scope, actor: Entity, target: Vec3, speed: f32) {
+    s.move_to(actor, target, Pace::Speed(speed)).await;
+}
@@ -0,0 +1,72 @@
+//! A debug window listing the live coroutines, built with `bevy_egui`. Available behind the
+//! `debug-overlay` feature.
+//!
+//! ```ignore
+//! App::new()
+//!     .add_plugins((DefaultPlugins, EguiPlugin, CorentinPlugin, DebugOverlayPlugin))
+//!     .run();
+//! ```
+
+use bevy::prelude::{App, Plugin, Res, ResMut, Update};
+use bevy_egui::{egui, EguiContexts};
+
+use crate::{
+    executor::{info::ExecutorInfo, Executor},
+    id_alloc::Id,
+    plugin::ExecutorInfoPlugin,
+};
+
+/// Shows a window listing every coroutine with its name, owner, what it is waiting on and its
+/// age, along with buttons to pause or cancel it. Requires the
+/// [`CorentinPlugin`](crate::plugin::CorentinPlugin) and the
+/// [`EguiPlugin`](bevy_egui::EguiPlugin).
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<ExecutorInfoPlugin>() {
+            app.add_plugins(ExecutorInfoPlugin);
+        }
+        app.add_systems(Update, show_overlay);
+    }
+}
+
+fn show_overlay(
+    mut contexts: EguiContexts,
+    info: Res<ExecutorInfo>,
+    mut executor: ResMut<Executor>,
+) {
+    egui::Window::new("Coroutines").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!("{} running", info.coroutines.len()));
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("coroutines").striped(true).show(ui, |ui| {
+                for header in ["id", "name", "owner", "waiting on", "age", ""] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+
+                for coro in &info.coroutines {
+                    let id = Id::from_bits(coro.id);
+                    ui.label(coro.id.to_string());
+                    ui.label(&coro.name);
+                    ui.label(coro.owner.map_or("-".to_owned(), |e| format!("{e:?}")));
+                    ui.label(coro.state.to_string());
+                    ui.label(format!("{:.1}s", coro.age));
+                    ui.horizontal(|ui| {
+                        if coro.paused {
+                            if ui.button("Unpause").clicked() {
+                                executor.unpause(id);
+                            }
+                        } else if ui.button("Pause").clicked() {
+                            executor.pause(id);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            executor.cancel_coroutine(id);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        });
+    });
+}
@@ -0,0 +1,342 @@
+//! Dialogue graphs, executed by coroutines. Available behind the `dialogue` feature.
+//!
+//! A [`Dialogue`] is made of nodes, each with a line, some custom commands and either the next
+//! node or a set of choices, optionally guarded by conditions. A [`DialogueRunner`] walks through
+//! that graph, and returns a [`Step`] at each point where the coroutine should await something:
+//!
+//! ```ignore
+//! let mut runner = DialogueRunner::new(dialogue)
+//!     .with_condition(|name, world| name == "has_key" && world.contains_resource::<Key>());
+//! while let Some(step) = runner.next(&s) {
+//!     match step {
+//!         Step::Line { text, .. } => show_text(&mut s, text_entity, text).await,
+//!         Step::Choices(choices) => runner.choose(pick_choice(&mut s, &choices).await),
+//!         Step::Command(command) => run_command(&mut s, &command).await,
+//!     }
+//! }
+//! ```
+//!
+//! [`run_dialogue`] does all of the above with the helpers of the [`cutscene`](crate::cutscene)
+//! module, choices being picked with the number keys.
+//!
+//! Once [`DialoguePlugin`] is added, dialogues can be loaded from `.dialogue.ron` files:
+//!
+//! ```ron
+//! (
+//!     start: "hello",
+//!     nodes: {
+//!         "hello": (
+//!             speaker: Some("Guard"),
+//!             line: "Halt!",
+//!             next: Choices([
+//!                 (text: "Show the pass", next: "pass", condition: Some("has_pass")),
+//!                 (text: "Flee", next: "flee"),
+//!             ]),
+//!         ),
+//!         "pass": (line: "Go ahead."),
+//!         "flee": (line: "Coward!", commands: ["run"]),
+//!     },
+//! )
+//! ```
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    log::warn,
+    prelude::{AddAsset, App, Entity, Input, KeyCode, Plugin, Text, World},
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashMap},
+};
+use serde::Deserialize;
+
+use crate::{
+    cutscene::{typewriter, wait_for_advance_input},
+    function_coroutine::scope::Scope,
+};
+
+/// A dialogue graph. It can be used as an asset, loaded by the [`DialoguePlugin`].
+#[derive(TypeUuid, TypePath, Deserialize, Clone, Debug, Default)]
+#[uuid = "5b1d3c1e-3f0a-4e2b-9a57-1c9e4f6d2a80"]
+pub struct Dialogue {
+    /// The node the dialogue starts from.
+    pub start: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+/// A node of a [`Dialogue`]. Only its line is required when it is deserialized.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct DialogueNode {
+    pub speaker: Option<String>,
+    pub line: String,
+    /// Custom commands, returned by the [`DialogueRunner`] before the line of this node.
+    pub commands: Vec<String>,
+    pub next: Next,
+}
+
+/// What comes after a [`DialogueNode`].
+#[derive(Deserialize, Clone, Debug, Default)]
+pub enum Next {
+    #[default]
+    End,
+    Goto(String),
+    Choices(Vec<Choice>),
+}
+
+/// A choice offered to the player.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Choice {
+    pub text: String,
+    /// The node to go to when this choice is made.
+    pub next: String,
+    /// The name of the condition this choice is guarded by, see
+    /// [`DialogueRunner::with_condition`].
+    pub condition: Option<String>,
+}
+
+impl Dialogue {
+    /// Parse a dialogue written in [RON](https://github.com/ron-rs/ron), as in the `.dialogue.ron`
+    /// files, see the [module level documentation](self).
+    pub fn from_ron(source: &str) -> Result<Self, ron::de::SpannedError> {
+        ron::from_str(source)
+    }
+}
+
+#[derive(Default)]
+struct DialogueLoader;
+
+impl AssetLoader for DialogueLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let dialogue = Dialogue::from_ron(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(dialogue));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dialogue.ron"]
+    }
+}
+
+/// Load [`Dialogue`] assets from `.dialogue.ron` files.
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Dialogue>()
+            .init_asset_loader::<DialogueLoader>();
+    }
+}
+
+/// A point of the dialogue where the coroutine running it should await something.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Step {
+    /// A line to display.
+    Line {
+        speaker: Option<String>,
+        text: String,
+    },
+    /// The texts of the choices offered to the player. The index of the one made must be given
+    /// to [`DialogueRunner::choose`].
+    Choices(Vec<String>),
+    /// A custom command.
+    Command(String),
+}
+
+type Condition = Box<dyn Fn(&str, &World) -> bool + Send + Sync>;
+
+enum Stage {
+    Commands(usize),
+    Line,
+    /// The indices of the choices offered, among all the choices of the node.
+    Choosing(Vec<usize>),
+}
+
+/// Walks through a [`Dialogue`], see the [module level documentation](self).
+pub struct DialogueRunner {
+    dialogue: Dialogue,
+    current: Option<String>,
+    stage: Stage,
+    condition: Condition,
+}
+
+impl DialogueRunner {
+    pub fn new(dialogue: Dialogue) -> Self {
+        Self {
+            current: Some(dialogue.start.clone()),
+            dialogue,
+            stage: Stage::Commands(0),
+            condition: Box::new(|_, _| true),
+        }
+    }
+
+    /// Evaluate the conditions of the choices with `condition`, which is given their name. By
+    /// default, all conditions are fulfilled.
+    pub fn with_condition(
+        mut self,
+        condition: impl Fn(&str, &World) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.condition = Box::new(condition);
+        self
+    }
+
+    /// Returns the next step of the dialogue, or `None` once it is over. Returns the same
+    /// [`Step::Choices`] again until a choice is made. When the conditions of all the choices of
+    /// a node fail, there is nothing to choose from and the dialogue ends there.
+    pub fn next(&mut self, s: &Scope) -> Option<Step> {
+        loop {
+            let node = self.dialogue.nodes.get(self.current.as_ref()?)?;
+            match &self.stage {
+                Stage::Commands(i) => match node.commands.get(*i) {
+                    Some(command) => {
+                        self.stage = Stage::Commands(i + 1);
+                        return Some(Step::Command(command.clone()));
+                    }
+                    None => {
+                        self.stage = Stage::Line;
+                        return Some(Step::Line {
+                            speaker: node.speaker.clone(),
+                            text: node.line.clone(),
+                        });
+                    }
+                },
+                Stage::Line => match &node.next {
+                    Next::End => self.current = None,
+                    Next::Goto(next) => {
+                        self.current = Some(next.clone());
+                        self.stage = Stage::Commands(0);
+                    }
+                    Next::Choices(choices) => {
+                        let world = s.world();
+                        let offered: Vec<usize> = choices
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, c)| match &c.condition {
+                                Some(name) => (self.condition)(name, &world),
+                                None => true,
+                            })
+                            .map(|(i, _)| i)
+                            .collect();
+                        if offered.is_empty() {
+                            let current = self.current.take().unwrap_or_default();
+                            warn!("No choice is available at {current:?}, the dialogue ends");
+                        } else {
+                            self.stage = Stage::Choosing(offered);
+                        }
+                    }
+                },
+                Stage::Choosing(offered) => {
+                    let Next::Choices(choices) = &node.next else {
+                        unreachable!()
+                    };
+                    let texts = offered.iter().map(|i| choices[*i].text.clone()).collect();
+                    return Some(Step::Choices(texts));
+                }
+            }
+        }
+    }
+
+    /// Make the choice at `index` among the ones returned by the last [`Step::Choices`]. Does
+    /// nothing if no choice is expected, or if `index` is out of bounds.
+    pub fn choose(&mut self, index: usize) {
+        let Stage::Choosing(offered) = &self.stage else {
+            return;
+        };
+        let Some(node) = self
+            .current
+            .as_ref()
+            .and_then(|n| self.dialogue.nodes.get(n))
+        else {
+            return;
+        };
+        let Next::Choices(choices) = &node.next else {
+            return;
+        };
+        if let Some(i) = offered.get(index) {
+            self.current = Some(choices[*i].next.clone());
+            self.stage = Stage::Commands(0);
+        }
+    }
+}
+
+const CHOICE_KEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+/// Wait until the player picks one of `count` choices with the number keys, and returns its
+/// index.
+///
+/// # Panics
+/// If `count` is 0, since no choice could ever be picked.
+pub async fn wait_for_choice(s: &mut Scope, count: usize) -> usize {
+    assert!(count > 0, "There must be at least one choice to pick from");
+    s.until(move |world| {
+        let keys = world.get_resource::<Input<KeyCode>>()?;
+        CHOICE_KEYS[..count.min(CHOICE_KEYS.len())]
+            .iter()
+            .position(|key| keys.just_pressed(*key))
+    })
+    .await
+}
+
+/// Run `runner` to its end, displaying its lines in the [`Text`] of `text` with a [`typewriter`]
+/// effect. Choices are listed in the same text, and picked with the number keys. Custom commands
+/// are given to `on_command`.
+pub async fn run_dialogue(
+    s: &mut Scope,
+    mut runner: DialogueRunner,
+    text: Entity,
+    mut on_command: impl FnMut(&mut Scope, &str),
+) {
+    while let Some(step) = runner.next(s) {
+        match step {
+            Step::Line {
+                speaker,
+                text: line,
+            } => {
+                let line = match speaker {
+                    Some(speaker) => format!("{speaker}: {line}"),
+                    None => line,
+                };
+                set_text(s, text, line);
+                s.next_tick().await;
+                if typewriter(s, text, 40.).await {
+                    // The input skipping the typewriter doesn't also skip the line
+                    s.next_tick().await;
+                    wait_for_advance_input(s).await;
+                }
+            }
+            Step::Choices(choices) => {
+                let list: Vec<_> = choices
+                    .iter()
+                    .enumerate()
+                    .map(|(i, choice)| format!("{}. {choice}", i + 1))
+                    .collect();
+                set_text(s, text, list.join("\n"));
+                let choice = wait_for_choice(s, choices.len()).await;
+                runner.choose(choice);
+            }
+            Step::Command(command) => on_command(s, &command),
+        }
+    }
+}
+
+fn set_text(s: &mut Scope, entity: Entity, text: String) {
+    s.modify(entity, move |t: &mut Text| {
+        if let Some(section) = t.sections.first_mut() {
+            section.value = text;
+        }
+    });
+}
@@ -0,0 +1,124 @@
+//! Starting a coroutine with options, see [`Executor::build`].
+
+use std::marker::PhantomData;
+
+use bevy::prelude::{Entity, World};
+
+use crate::{function_coroutine::CoroutineParamFunction, id_alloc::Id};
+
+use super::{partition::PartitionKey, tag::CoroTag, DespawnPolicy, Executor};
+
+/// The options a coroutine is started with.
+#[derive(Default)]
+pub(crate) struct CoroOptions {
+    pub owner: Option<Entity>,
+    pub name: Option<&'static str>,
+    pub despawn_policy: Option<DespawnPolicy>,
+    pub non_send: bool,
+    pub partition: Option<PartitionKey>,
+    pub priority: i32,
+    pub tags: Vec<CoroTag>,
+}
+
+/// A coroutine about to be added to an [`Executor`], returned by [`Executor::build`].
+#[must_use = "the coroutine is only added once `spawn` is called"]
+pub struct CoroBuilder<'a, Marker, T, C> {
+    executor: &'a mut Executor,
+    coroutine: C,
+    options: CoroOptions,
+    paused: bool,
+    _phantom: PhantomData<fn(Marker) -> T>,
+}
+
+impl Executor {
+    /// Prepare to add `coroutine`, with the options set on the returned [`CoroBuilder`].
+    ///
+    /// ```ignore
+    /// let id = executor
+    ///     .build(patrol)
+    ///     .name("patrol")
+    ///     .priority(2)
+    ///     .owner(guard)
+    ///     .tag("ai")
+    ///     .despawn_policy(DespawnPolicy::Resume)
+    ///     .spawn(world);
+    /// ```
+    pub fn build<Marker, T, C>(&mut self, coroutine: C) -> CoroBuilder<'_, Marker, T, C>
+    where
+        C: CoroutineParamFunction<Marker, T>,
+    {
+        CoroBuilder {
+            executor: self,
+            coroutine,
+            options: CoroOptions::default(),
+            paused: false,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<Marker: 'static, T, C> CoroBuilder<'_, Marker, T, C>
+where
+    C: CoroutineParamFunction<Marker, T>,
+    T: Send + Sync + 'static,
+{
+    /// The name of the coroutine, as shown by [`info`](super::info), the profiler and the
+    /// watchdog. Defaults to the type name of the function.
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.options.name = Some(name);
+        self
+    }
+
+    /// The entity owning the coroutine. See [`coroutine`](crate::commands::coroutine).
+    pub fn owner(mut self, owner: Entity) -> Self {
+        self.options.owner = Some(owner);
+        self
+    }
+
+    /// What happens to the coroutine if its owner is despawned, like
+    /// [`Scope::set_despawn_policy`](crate::prelude::Scope::set_despawn_policy).
+    pub fn despawn_policy(mut self, policy: DespawnPolicy) -> Self {
+        self.options.despawn_policy = Some(policy);
+        self
+    }
+
+    /// Put the coroutine in the partition `key`, see [`Executor::cancel_partition`]. The
+    /// coroutines it starts are put in the same partition.
+    pub fn partition(mut self, key: PartitionKey) -> Self {
+        self.options.partition = Some(key);
+        self
+    }
+
+    /// Among the coroutines woken by a tick, the ones with a higher priority are resumed first.
+    /// When they don't all fit in the [resume budget](Executor::set_resume_budget), the ones with
+    /// the lowest priority are left for the following ticks. Defaults to 0.
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.options.priority = priority;
+        self
+    }
+
+    /// Tag the coroutine with `tag`, to find it with [`Executor::tagged`] or cancel it with
+    /// [`Executor::cancel_tagged`]. A coroutine can have several tags.
+    pub fn tag(mut self, tag: CoroTag) -> Self {
+        self.options.tags.push(tag);
+        self
+    }
+
+    /// Add the coroutine paused, it only starts once [`Executor::unpause`] is called.
+    pub fn paused(mut self) -> Self {
+        self.paused = true;
+        self
+    }
+
+    /// Add the coroutine to the executor, and returns its id. Returns [`None`] if the parameters
+    /// of the coroutine couldn't be initialized, for instance if the owner lacks a component.
+    pub fn spawn(self, world: &World) -> Option<Id> {
+        let id = self
+            .executor
+            .build_function_coroutine(world, self.options, self.coroutine)?;
+        if self.paused {
+            self.executor.pause(id);
+        }
+        Some(id)
+    }
+}
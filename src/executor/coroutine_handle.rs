@@ -0,0 +1,87 @@
+//! Handles to the coroutines of an [`Executor`], for the code running outside of them.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use bevy::prelude::Component;
+
+use crate::id_alloc::Id;
+
+use super::Executor;
+
+#[derive(Default)]
+pub(super) struct HandleState {
+    cancel: AtomicBool,
+    finished: AtomicBool,
+}
+
+/// A handle to a coroutine, which systems can use to cancel it. Unlike a
+/// [`CoroHandle`](crate::function_coroutine::handle::CoroHandle), dropping it leaves the coroutine
+/// running. It can be stored in a component, to stop the AI loop of an enemy when it dies for
+/// instance:
+///
+/// ```ignore
+/// fn on_death(dead: Query<&CoroutineHandle, Added<Dead>>) {
+///     for ai in dead.iter() {
+///         ai.cancel();
+///     }
+/// }
+/// ```
+#[derive(Component, Clone)]
+pub struct CoroutineHandle {
+    id: Id,
+    state: Arc<HandleState>,
+}
+
+impl CoroutineHandle {
+    /// Cancel the coroutine and everything it started, on the next tick of the executor. Does
+    /// nothing if the coroutine already ended.
+    pub fn cancel(&self) {
+        self.state.cancel.store(true, Ordering::Release);
+    }
+
+    /// Returns true once the coroutine has ended, either by finishing or by being canceled.
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.load(Ordering::Acquire)
+    }
+
+    pub fn id(&self) -> Id {
+        self.id
+    }
+}
+
+impl Executor {
+    /// Returns a handle to the coroutine `id`, or `None` if it already ended.
+    pub fn handle(&mut self, id: Id) -> Option<CoroutineHandle> {
+        if !self.coroutines.contains_key(&id) {
+            return None;
+        }
+        let state = self.handles.entry(id).or_default();
+        Some(CoroutineHandle {
+            id,
+            state: Arc::clone(state),
+        })
+    }
+
+    /// Cancel the coroutines whose handle asked for it.
+    pub(super) fn cancel_requested(&mut self) {
+        let canceled: Vec<Id> = self
+            .handles
+            .iter()
+            .filter(|(_, state)| state.cancel.load(Ordering::Acquire))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in canceled {
+            self.cancel_coroutine(id);
+        }
+    }
+
+    /// Notify the handles of `id`, if any, that it ended.
+    pub(super) fn notify_handles(&mut self, id: Id) {
+        if let Some(state) = self.handles.remove(&id) {
+            state.finished.store(true, Ordering::Release);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Detection of coroutines awaiting each other, which can never be resumed.
+
+use bevy::{log::warn, utils::HashSet};
+
+use crate::id_alloc::Id;
+
+use super::Executor;
+
+impl Executor {
+    /// Returns the coroutines which are deadlocked: they await other coroutines (with
+    /// [`Scope::all`] or [`Scope::first`]) which end up awaiting them back, so none of them
+    /// will ever be resumed.
+    ///
+    /// With [diagnostics](Executor::set_diagnostics) enabled, the default in debug builds,
+    /// deadlocks are also logged as warnings at the end of each tick, with the name of each
+    /// coroutine involved and where it is suspended.
+    ///
+    /// [`Scope::all`]: crate::prelude::Scope::all
+    /// [`Scope::first`]: crate::prelude::Scope::first
+    pub fn deadlocks(&self) -> Vec<Id> {
+        // Start from every coroutine awaiting others, and remove the ones which can still make
+        // progress until nothing changes.
+        let mut stuck: HashSet<u64> = self
+            .waiting_on_all
+            .keys()
+            .chain(self.waiting_on_first.keys())
+            .map(|id| id.to_bits())
+            .collect();
+
+        loop {
+            let before = stuck.clone();
+            stuck.retain(|id| {
+                let coro_id = Id::from_bits(*id);
+                if let Some(all) = self.waiting_on_all.get(&coro_id) {
+                    all.iter().any(|id| before.contains(&id))
+                } else if let Some(first) = self.waiting_on_first.get(&coro_id) {
+                    first.iter().all(|id| before.contains(&id))
+                } else {
+                    false
+                }
+            });
+            if stuck.len() == before.len() {
+                break;
+            }
+        }
+
+        let mut deadlocks: Vec<Id> = stuck.into_iter().map(Id::from_bits).collect();
+        deadlocks.sort_by_key(|id| id.to_bits());
+        deadlocks
+    }
+
+    pub(super) fn warn_deadlocks(&mut self) {
+        if self.waiting_on_all.is_empty() && self.waiting_on_first.is_empty() {
+            return;
+        }
+
+        let deadlocks: Vec<Id> = self
+            .deadlocks()
+            .into_iter()
+            .filter(|id| !self.warned_deadlocked.contains(id.to_bits()))
+            .collect();
+        if deadlocks.is_empty() {
+            return;
+        }
+
+        let mut report = String::new();
+        for id in deadlocks {
+            self.warned_deadlocked.insert(id.to_bits());
+            let Some(coro) = self.coroutines.get_mut(&id) else {
+                continue;
+            };
+            let coro = coro.get();
+            let location = coro
+                .suspended_at()
+                .map_or(String::new(), |l| format!(" at {l}"));
+            report += &format!("\n  {}{location}", coro.meta().name);
+        }
+        warn!("These coroutines await each other, and will never be resumed:{report}");
+    }
+}
@@ -0,0 +1,225 @@
+//! A reflected snapshot of the state of the [`Executor`], so that tools such as
+//! `bevy-inspector-egui` can show which coroutines are running. It is kept up to date by the
+//! [`ExecutorInfoPlugin`](crate::plugin::ExecutorInfoPlugin).
+
+use std::fmt::{self, Display, Write};
+
+use bevy::{
+    prelude::{Entity, ReflectResource, Resource},
+    reflect::Reflect,
+};
+
+use crate::id_alloc::Id;
+
+use super::Executor;
+
+/// What a coroutine is currently waiting on.
+#[derive(Reflect, Clone, Debug, Default, PartialEq)]
+pub enum CoroutineState {
+    /// Resumed on the next tick.
+    #[default]
+    Tick,
+    /// Resumed once this many seconds have elapsed.
+    Duration { remaining: f32 },
+    /// Resumed once any of these coroutines is done.
+    First(Vec<u64>),
+    /// Resumed once all of these coroutines are done.
+    All(Vec<u64>),
+    /// Resumed once a signal (such as a change of component) is emitted.
+    Signal,
+    /// Resumed once events are sent.
+    Event,
+    /// Resumed once a channel can be received from.
+    Channel,
+}
+
+impl Display for CoroutineState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoroutineState::Tick => write!(f, "next tick"),
+            CoroutineState::Duration { remaining } => write!(f, "{remaining:.2}s left"),
+            CoroutineState::First(ids) => write!(f, "first of {ids:?}"),
+            CoroutineState::All(ids) => write!(f, "all of {ids:?}"),
+            CoroutineState::Signal => write!(f, "signal"),
+            CoroutineState::Event => write!(f, "event"),
+            CoroutineState::Channel => write!(f, "channel"),
+        }
+    }
+}
+
+/// The reflected state of a single coroutine, see [`ExecutorInfo`].
+#[derive(Reflect, Clone, Debug, Default, PartialEq)]
+pub struct CoroutineInfo {
+    /// The id of the coroutine, as returned by [`Id::to_bits`].
+    pub id: u64,
+    /// The name of the function this coroutine was created from, unless another one was given
+    /// with [`CoroBuilder::name`](super::builder::CoroBuilder::name).
+    pub name: String,
+    /// The entity the coroutine is bound to, if any.
+    pub owner: Option<Entity>,
+    /// The coroutine whose scope started this one, if any. It is canceled when its parent ends.
+    pub parent: Option<u64>,
+    /// The coroutine awaiting this one, if any.
+    pub awaited_by: Option<u64>,
+    pub state: CoroutineState,
+    /// Where the coroutine is suspended in the source code, as `file:line:column`.
+    pub location: Option<String>,
+    /// How long ago the coroutine was started, in seconds.
+    pub age: f32,
+    pub paused: bool,
+    /// The time spent resuming the coroutine during the last tick, in seconds. Only measured
+    /// when [profiling](Executor::set_profiling) is enabled.
+    pub poll_time: f32,
+}
+
+/// A snapshot of all the coroutines of the [`Executor`], sorted by id.
+#[derive(Resource, Reflect, Clone, Debug, Default)]
+#[reflect(Resource)]
+pub struct ExecutorInfo {
+    pub coroutines: Vec<CoroutineInfo>,
+}
+
+impl Executor {
+    /// Returns a snapshot of the state of every coroutine currently handled by this executor.
+    pub fn info(&mut self) -> ExecutorInfo {
+        let metas: Vec<_> = self
+            .coroutines
+            .iter_mut()
+            .map(|(id, coro)| {
+                let coro = coro.get();
+                let meta = coro.meta();
+                (*id, meta.name, meta.owner, coro.suspended_at())
+            })
+            .collect();
+
+        let mut coroutines: Vec<CoroutineInfo> = metas
+            .into_iter()
+            .map(|(id, name, owner, location)| CoroutineInfo {
+                id: id.to_bits(),
+                name: name.to_owned(),
+                owner,
+                parent: self.owned_by.get(&id).map(|p| p.to_bits()),
+                awaited_by: self.is_awaited_by.get(&id).map(|p| p.to_bits()),
+                state: self.state_of(id),
+                location: location.map(|l| l.to_string()),
+                age: self
+                    .started_at
+                    .get(&id)
+                    .map_or(0.0, |t| (self.elapsed - *t).as_secs_f32()),
+                paused: self.is_paused(id),
+                poll_time: self
+                    .poll_times
+                    .as_ref()
+                    .and_then(|times| times.get(&id))
+                    .map_or(0.0, |(_, d)| d.as_secs_f32()),
+            })
+            .collect();
+        coroutines.sort_by_key(|c| c.id);
+
+        ExecutorInfo { coroutines }
+    }
+
+    fn state_of(&self, id: Id) -> CoroutineState {
+        if let Some(deadline) = self.waiting_on_time.get(&id) {
+            let now = self.paused.get(&id).copied().unwrap_or(self.elapsed);
+            CoroutineState::Duration {
+                remaining: deadline.saturating_sub(now).as_secs_f32(),
+            }
+        } else if let Some(handles) = self.waiting_on_first.get(&id) {
+            CoroutineState::First(handles.iter().collect())
+        } else if let Some(handles) = self.waiting_on_all.get(&id) {
+            CoroutineState::All(handles.iter().collect())
+        } else if self.waiting_on_signal.contains(id) {
+            CoroutineState::Signal
+        } else if self.waiting_on_events.contains(id) {
+            CoroutineState::Event
+        } else if self.waiting_on_channel.contains(id) {
+            CoroutineState::Channel
+        } else {
+            CoroutineState::Tick
+        }
+    }
+}
+
+impl ExecutorInfo {
+    /// The coroutine under which `coro` is shown in a tree: the one awaiting it, or else the one
+    /// which started it.
+    fn tree_parent(coro: &CoroutineInfo) -> Option<u64> {
+        coro.awaited_by.or(coro.parent)
+    }
+
+    fn children(&self, of: Option<u64>) -> impl Iterator<Item = &CoroutineInfo> {
+        self.coroutines
+            .iter()
+            .filter(move |c| Self::tree_parent(c) == of)
+    }
+
+    /// Returns a textual representation of the coroutines, where each coroutine is indented
+    /// below the one awaiting it (or having started it), along with what it is waiting on.
+    pub fn tree(&self) -> String {
+        let mut out = String::new();
+        for root in self.children(None) {
+            self.write_node(&mut out, root, 0);
+        }
+        out
+    }
+
+    fn write_node(&self, out: &mut String, coro: &CoroutineInfo, depth: usize) {
+        let _ = write!(
+            out,
+            "{:indent$}#{} {}",
+            "",
+            coro.id,
+            coro.name,
+            indent = depth * 2
+        );
+        if let Some(owner) = coro.owner {
+            let _ = write!(out, " on {owner:?}");
+        }
+        let _ = write!(out, ", waiting on {}", coro.state);
+        if let Some(location) = &coro.location {
+            let _ = write!(out, " at {location}");
+        }
+        if coro.paused {
+            out.push_str(" (paused)");
+        }
+        out.push('\n');
+
+        for child in self.children(Some(coro.id)) {
+            self.write_node(out, child, depth + 1);
+        }
+    }
+
+    /// Returns the same tree as [`tree`](ExecutorInfo::tree), in the Graphviz dot format.
+    pub fn graphviz(&self) -> String {
+        let mut out = String::from("digraph coroutines {\n");
+        for coro in &self.coroutines {
+            let _ = writeln!(
+                out,
+                "    c{} [label=\"#{} {}\\n{}\"];",
+                coro.id,
+                coro.id,
+                coro.name.replace('"', "\\\""),
+                coro.state
+            );
+            if let Some(parent) = Self::tree_parent(coro) {
+                let _ = writeln!(out, "    c{} -> c{};", parent, coro.id);
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+impl Executor {
+    /// Returns a textual representation of the tree of running coroutines, see
+    /// [`ExecutorInfo::tree`].
+    pub fn dump_tree(&mut self) -> String {
+        self.info().tree()
+    }
+
+    /// Returns the tree of running coroutines in the Graphviz dot format.
+    pub fn dump_graphviz(&mut self) -> String {
+        self.info().graphviz()
+    }
+}
@@ -1,43 +1,197 @@
-use bevy::{prelude::Entity, time::Time, utils::synccell::SyncCell};
-use std::{collections::VecDeque, ops::Index};
+use bevy::{
+    log::warn,
+    prelude::Entity,
+    time::Time,
+    utils::{synccell::SyncCell, Instant},
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    ops::Index,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    time::Duration,
+};
 
 use bevy::{
     prelude::{Resource, World},
-    time::Timer,
-    utils::HashMap,
+    utils::{HashMap, HashSet},
 };
 use tinyset::{SetU64, SetUsize};
 
 use crate::{
+    coro_pool::CoroPool,
+    external_signal::{poll_external, ExternalSignal},
     function_coroutine::ResumeParam,
-    global_channel::{Channel, CommandChannel},
+    global_channel::{Channel, CleanupQueue, CommandChannel},
 };
 
-use self::msg::{CoroStatus, EmitMsg, NewCoroutine, SignalId, YieldMsg};
+use self::{
+    builder::CoroOptions,
+    coroutine_handle::CoroutineHandle,
+    msg::{CoroStatus, EmitMsg, NewCoroutine, SignalId, YieldMsg},
+    record::{Recorder, WakeReason},
+    shared_changes::ChangeReader,
+    slab::Slab,
+    tick_set::TickSet,
+    wake_index::{ChannelWaiters, EventWaiters},
+    watchdog::Watchdog,
+};
 
 use super::{
-    function_coroutine::{resume::Resume, scope::Scope, CoroutineParamFunction, FunctionCoroutine},
+    function_coroutine::{
+        await_time::IntoDuration, resume::Resume, scope::Scope, CoroutineFunction,
+        CoroutineParamFunction, FunctionCoroutine,
+    },
     id_alloc::{Id, Ids},
     Coroutine, HeapCoro,
 };
 
+pub mod builder;
+pub mod coroutine_handle;
+mod deadlock;
+pub mod info;
 pub mod msg;
+pub mod owners;
+mod parallel;
+pub mod partition;
+pub mod profile;
+pub mod record;
+pub mod save;
+mod shared_changes;
+mod slab;
+pub mod tag;
+mod tick_set;
+mod wake_index;
+pub mod watchdog;
+
+pub(crate) use parallel::validate_access;
 
 #[derive(Resource, Default)]
 pub struct Executor {
     ids: Ids,
-    coroutines: HashMap<Id, HeapCoro>,
-    waiting_on_tick: VecDeque<Id>,
-    waiting_on_time: HashMap<Id, Timer>,
+    coroutines: Slab<HeapCoro>,
+    /// The coroutines resumed on the next tick. The waits on a duration, a signal, events, or
+    /// a channel which can be probed are woken by what they wait on. Only the others (conditions
+    /// given to `until`, and `std` channels) have no wake source, and are polled here every
+    /// tick.
+    waiting_on_tick: TickSet,
+    /// The deadline of each coroutine waiting on a duration, compared to `elapsed`.
+    waiting_on_time: HashMap<Id, Duration>,
+    /// The same deadlines, sorted so that only the expired ones are looked at each tick. Entries
+    /// which no longer match `waiting_on_time` are stale, and skipped.
+    deadlines: BinaryHeap<Reverse<(Duration, Id)>>,
     waiting_on_all: HashMap<Id, SetU64>,
     waiting_on_first: HashMap<Id, SetU64>,
-    waiting_on_signal: HashMap<SignalId, SetU64>,
+    waiting_on_signal: SignalWaiters,
+    waiting_on_events: EventWaiters,
+    waiting_on_channel: ChannelWaiters,
     scope_ownership: HashMap<Id, SetU64>,
+    /// The reverse of `scope_ownership`, so finished coroutines are removed from their owner
+    /// right away instead of lingering until the owner ends.
+    owned_by: HashMap<Id, Id>,
     is_awaited_by: HashMap<Id, Id>,
     new_coro_channel: Channel<NewCoroutine>,
     signal_channel: Channel<EmitMsg>,
     commands_channel: CommandChannel,
     yield_channel: Channel<YieldMsg>,
+    external_signals: Vec<fn(&mut World) -> Vec<SignalId>>,
+    cleanup_queue: CleanupQueue,
+    pool: CoroPool,
+    /// The paused coroutines, with the time at which they were paused.
+    paused: HashMap<Id, Duration>,
+    elapsed: Duration,
+    started_at: HashMap<Id, Duration>,
+    recorder: Option<Recorder>,
+    manual_time: Option<Duration>,
+    /// The duration of each tick, for a [deterministic](Executor::deterministic) executor.
+    fixed_step: Option<Duration>,
+    despawn_policy: DespawnPolicy,
+    has_policy_overrides: bool,
+    orphaned: SetU64,
+    /// Whether to warn about unreachable waits and deadlocks, see
+    /// [`set_diagnostics`](Executor::set_diagnostics).
+    diagnostics: Option<bool>,
+    warned_unreachable: SetU64,
+    warned_deadlocked: SetU64,
+    watchdog: Option<Watchdog>,
+    poll_times: Option<HashMap<Id, (&'static str, Duration)>>,
+    /// Finished coroutines, waiting to be dropped.
+    finished: VecDeque<HeapCoro>,
+    cleanup_budget: Option<usize>,
+    resume_budget: Option<usize>,
+    fairness: Fairness,
+    /// The index of the last coroutine woken by a tick, for [`Fairness::RoundRobin`].
+    last_woken: Option<u32>,
+    parallel: bool,
+    change_reader: ChangeReader,
+    /// The signals emitted by coroutines during this tick, to publish to the other executors.
+    emitted: HashSet<SignalId>,
+    partitions: partition::Partitions,
+    /// The priority of the coroutines which don't have the default one, see
+    /// [`CoroBuilder::priority`](builder::CoroBuilder::priority).
+    priorities: HashMap<Id, i32>,
+    tags: HashMap<Id, Vec<tag::CoroTag>>,
+    /// The shared state of the [`CoroutineHandle`]s given out, by coroutine.
+    handles: HashMap<Id, Arc<coroutine_handle::HandleState>>,
+    owners: owners::Owners,
+}
+
+/// What happens to a coroutine when its owner entity is despawned, or loses one of the
+/// components the coroutine accesses. See [`Executor::set_despawn_policy`],
+/// [`Scope::set_despawn_policy`], and [`AwaitChange::on_despawn`] to choose it for a single wait.
+///
+/// [`AwaitChange::on_despawn`]: crate::function_coroutine::await_change::AwaitChange::on_despawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DespawnPolicy {
+    /// The coroutine is canceled, as if its handle was dropped. The coroutines owned by an entity
+    /// are canceled on the tick following its despawn, even those which aren't due to be resumed.
+    #[default]
+    Cancel,
+    /// Panic, with the name of the coroutine and where it was suspended.
+    Panic,
+    /// The coroutine is resumed right away, and keeps running afterward. Its parameters then
+    /// return [`Despawned`](crate::function_coroutine::coro_param::Despawned) from their `try_`
+    /// accessors.
+    Resume,
+    /// The coroutine is never resumed again, but isn't dropped either: it stays suspended until
+    /// its handle is dropped, or its parent ends.
+    Sleep,
+}
+
+/// Which coroutines are resumed first when they don't all fit in the budget set with
+/// [`Executor::set_resume_budget`]. The others are resumed on the following ticks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Fairness {
+    /// Always start with the oldest coroutines (the ones with the lowest [`Id`] index). A few
+    /// coroutines which are always ready can then prevent the others from ever being resumed.
+    #[default]
+    InOrder,
+    /// Start right after the last coroutine resumed on the previous tick, so that every
+    /// coroutine gets its turn.
+    RoundRobin,
+}
+
+/// The clock of the [`Executor`], updated at the start of each tick. It follows the [`Time`]
+/// resource, unless the executor was created with [`Executor::with_manual_time`] or
+/// [`Executor::deterministic`]. Helpers measuring time read it instead of [`Time`], so that they
+/// stay in sync with the durations awaited by coroutines.
+#[derive(Resource, Default, Clone, Copy)]
+pub(crate) struct TickTime {
+    /// The time elapsed since the previous tick, as returned by [`Scope::next_tick`].
+    pub delta: Duration,
+    /// The total time elapsed since the executor started.
+    pub elapsed: Duration,
+}
+
+impl TickTime {
+    /// The clock of the executor, or zero if it never ticked.
+    pub fn get(world: &World) -> Self {
+        world
+            .get_resource::<TickTime>()
+            .copied()
+            .unwrap_or_default()
+    }
 }
 
 // SAFETY: The [`Executor`] can only be accessed througth an exclusive
@@ -45,16 +199,225 @@ pub struct Executor {
 unsafe impl Sync for Executor {}
 
 impl Executor {
-    pub fn add_coroutine(&mut self, id: Id, coroutine: HeapCoro) {
+    pub fn add_coroutine(&mut self, id: Id, mut coroutine: HeapCoro) {
+        self.track_owner(id, &mut coroutine);
         let prev = self.coroutines.insert(id, coroutine);
-        self.waiting_on_tick.push_back(id);
+        self.started_at.insert(id, self.elapsed);
+        self.waiting_on_tick.insert(id);
         debug_assert!(prev.is_none());
     }
 
+    /// An executor whose time only advances through [`advance`](Executor::advance), instead of
+    /// following the [`Time`] resource. Duration based coroutines can then be tested
+    /// deterministically, without waiting.
+    pub fn with_manual_time() -> Self {
+        Self {
+            manual_time: Some(Duration::ZERO),
+            ..Default::default()
+        }
+    }
+
+    /// An executor whose behavior only depends on the world and on the number of ticks, so that
+    /// two simulations given the same inputs stay identical, as lockstep netcode requires. Each
+    /// tick advances its time by exactly `step`, instead of following the [`Time`] resource, and
+    /// so do the helpers measuring time such as [`Scope::cooldown`]. Coroutines are resumed one at
+    /// a time, always in the same order, and the executor never reads the wall clock:
+    /// [profiling](Executor::set_profiling) measures nothing.
+    ///
+    /// Rollback isn't supported: a suspended coroutine cannot be copied, so its progress can't be
+    /// rewound, and restoring the scheduling bookkeeping alone would point to coroutines which
+    /// already went further. [`save_state`](Executor::save_state) and
+    /// [`restore_state`](Executor::restore_state) can bring a new deterministic executor back in
+    /// sync, after loading a game or a desync, but the coroutines are started again from their
+    /// beginning: only the clock, the time left on their timers and the [`Checkpoints`] are
+    /// restored, and whatever else they did must be saved in the world.
+    ///
+    /// [`Checkpoints`]: crate::quest::Checkpoints
+    pub fn deterministic(step: impl IntoDuration) -> Self {
+        Self {
+            fixed_step: Some(step.into_duration()),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.fixed_step.is_some()
+    }
+
+    /// The time the next tick will advance by.
+    pub(crate) fn next_delta(&self, world: &World) -> Duration {
+        match (self.manual_time, self.fixed_step) {
+            (_, Some(step)) => step,
+            (Some(pending), None) => pending,
+            (None, None) => world.resource::<Time>().delta(),
+        }
+    }
+
+    /// Advance the time of an executor created with [`with_manual_time`] by `duration`. It is
+    /// taken into account on the next tick.
+    ///
+    /// [`with_manual_time`]: Executor::with_manual_time
+    pub fn advance(&mut self, duration: impl IntoDuration) {
+        let duration = duration.into_duration();
+        match &mut self.manual_time {
+            Some(pending) => *pending += duration,
+            None => panic!("Executor::advance can only be used with an executor created with `Executor::with_manual_time`"),
+        }
+    }
+
+    /// Set what happens to coroutines whose entity disappears, unless they chose otherwise with
+    /// [`Scope::set_despawn_policy`]. Defaults to [`DespawnPolicy::Cancel`].
+    pub fn set_despawn_policy(&mut self, policy: DespawnPolicy) {
+        self.despawn_policy = policy;
+    }
+
+    /// Choose whether to warn about coroutines which will never be resumed: the ones waiting on a
+    /// change of a despawned entity or removed resource, on events whose resource was removed,
+    /// and the [deadlocked](Executor::deadlocks) ones. Each coroutine is only reported once. This looks at every waiting coroutine at the
+    /// end of each tick, so it defaults to enabled in debug builds only.
+    pub fn set_diagnostics(&mut self, enabled: bool) {
+        self.diagnostics = Some(enabled);
+    }
+
+    fn diagnostics_enabled(&self) -> bool {
+        self.diagnostics.unwrap_or(cfg!(debug_assertions))
+    }
+
+    /// Drop at most `budget` finished coroutines per tick, the others are dropped on the
+    /// following ticks. This avoids a hitch when many coroutines end at once. With `None`, the
+    /// default, they are all dropped by the end of the tick they finish in.
+    pub fn set_cleanup_budget(&mut self, budget: Option<usize>) {
+        self.cleanup_budget = budget;
+    }
+
+    /// Resume at most `budget` of the coroutines waiting on the next tick, each tick. The others
+    /// wait for the following ticks, in the order chosen with [`set_fairness`]. Coroutines woken
+    /// by anything else (a timer, a signal, another coroutine ending...) are not limited. With
+    /// `None`, the default, they are all resumed.
+    ///
+    /// [`set_fairness`]: Executor::set_fairness
+    pub fn set_resume_budget(&mut self, budget: Option<usize>) {
+        self.resume_budget = budget;
+    }
+
+    /// Choose which coroutines are resumed first when they exceed the resume budget. Defaults to
+    /// [`Fairness::InOrder`].
+    pub fn set_fairness(&mut self, fairness: Fairness) {
+        self.fairness = fairness;
+    }
+
+    /// Take the coroutines waiting on the next tick which fit in the resume budget.
+    fn wake_on_tick(&mut self) -> Vec<Id> {
+        let mut woken: Vec<Id> = self.waiting_on_tick.drain().collect();
+        self.apply_partition_budgets(&mut woken);
+        let priorities = &self.priorities;
+        let priority = |id: &Id| priorities.get(id).copied().unwrap_or_default();
+
+        if let Some(budget) = self.resume_budget.filter(|budget| woken.len() > *budget) {
+            if let (Fairness::RoundRobin, Some(last)) = (self.fairness, self.last_woken) {
+                let start = woken.partition_point(|id| id.index() <= last);
+                woken.rotate_left(start);
+            }
+            // The sorts are stable, so the fairness still applies within a priority
+            if !priorities.is_empty() {
+                woken.sort_by_key(|id| Reverse(priority(id)));
+            }
+            for id in woken.drain(budget..) {
+                self.waiting_on_tick.insert(id);
+            }
+            self.last_woken = woken.last().map(|id| id.index());
+        }
+
+        // The ready coroutines are resumed starting from the end
+        if !priorities.is_empty() {
+            woken.sort_by_key(priority);
+        }
+        woken
+    }
+
+    /// Remove `coro_id` from the running coroutines. It is dropped later, in `drop_finished`.
+    fn retire(&mut self, coro_id: Id) {
+        self.leave_partition(coro_id);
+        self.priorities.remove(&coro_id);
+        self.tags.remove(&coro_id);
+        self.notify_handles(coro_id);
+        if let Some(mut coro) = self.coroutines.remove(&coro_id) {
+            if let Some(owner) = coro.get().meta().owner {
+                self.forget_owned(owner, coro_id);
+            }
+            self.finished.push_back(coro);
+        }
+    }
+
+    fn drop_finished(&mut self) {
+        let count = self.cleanup_budget.map_or(self.finished.len(), |budget| {
+            budget.min(self.finished.len())
+        });
+        self.finished.drain(..count);
+    }
+
+    /// Register the [`ExternalSignal`] `S`, so that it is polled at the beginning of each tick.
+    pub fn register_external_signal<S: ExternalSignal>(&mut self) {
+        self.external_signals.push(poll_external::<S>);
+    }
+
+    /// Cancel the coroutine `id` and everything it started, as if its handle was dropped. Does
+    /// nothing if the coroutine already ended.
+    pub fn cancel_coroutine(&mut self, id: Id) {
+        if self.coroutines.contains_key(&id) {
+            self.cancel(id);
+        }
+    }
+
+    /// Stop resuming the coroutine `id` until [`unpause`](Executor::unpause) is called. Its
+    /// timers are frozen in the meantime, and if it is woken up (by a signal for instance) it is
+    /// resumed right after being unpaused.
+    pub fn pause(&mut self, id: Id) {
+        if self.coroutines.contains_key(&id) {
+            self.paused.entry(id).or_insert(self.elapsed);
+        }
+    }
+
+    pub fn unpause(&mut self, id: Id) {
+        if let Some(paused_at) = self.paused.remove(&id) {
+            if let Some(deadline) = self.waiting_on_time.get(&id) {
+                self.wait_until(id, *deadline + (self.elapsed - paused_at));
+            }
+        }
+    }
+
+    /// Returns true if the coroutine `id` hasn't ended yet.
+    pub fn is_running(&self, id: Id) -> bool {
+        self.coroutines.contains_key(&id)
+    }
+
+    pub fn is_paused(&self, id: Id) -> bool {
+        self.paused.contains_key(&id)
+    }
+
+    /// Resume `id` once `elapsed` reaches `deadline`.
+    fn wait_until(&mut self, id: Id, deadline: Duration) {
+        self.waiting_on_time.insert(id, deadline);
+        self.deadlines.push(Reverse((deadline, id)));
+    }
+
     fn cancel(&mut self, coro_id: Id) {
         self.ids.free(coro_id);
-        self.coroutines.remove(&coro_id);
+        self.retire(coro_id);
+        self.started_at.remove(&coro_id);
+        self.paused.remove(&coro_id);
+        self.waiting_on_time.remove(&coro_id);
+        self.waiting_on_signal.forget(coro_id);
+        self.waiting_on_events.forget(coro_id);
+        self.waiting_on_channel.forget(coro_id);
+        self.orphaned.remove(coro_id.to_bits());
+        self.warned_unreachable.remove(coro_id.to_bits());
+        self.warned_deadlocked.remove(coro_id.to_bits());
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.forget(coro_id);
+        }
 
+        self.forget_owner(coro_id);
         if let Some(owned) = self.scope_ownership.remove(&coro_id) {
             for c in owned {
                 self.cancel(Id::from_bits(c))
@@ -78,60 +441,202 @@ impl Executor {
         }
     }
 
+    /// Remove `coro_id` from the coroutines owned by its parent scope, if any.
+    fn forget_owner(&mut self, coro_id: Id) {
+        let Some(parent) = self.owned_by.remove(&coro_id) else {
+            return;
+        };
+        if let Some(owned) = self.scope_ownership.get_mut(&parent) {
+            owned.remove(coro_id.to_bits());
+            if owned.is_empty() {
+                self.scope_ownership.remove(&parent);
+            }
+        }
+    }
+
     pub fn tick_until_empty(&mut self, world: &mut World) {
-        while !self.coroutines.is_empty() {
+        while !self.coroutines.is_empty() || !self.finished.is_empty() {
             self.tick(world);
         }
     }
 
     pub fn tick(&mut self, world: &mut World) {
+        self.cancel_requested();
+        self.cancel_despawned_owners(world);
         let mut root_coros = VecDeque::<Id>::new();
 
-        root_coros.append(&mut self.waiting_on_tick);
-
-        let delta_time = world.resource::<Time>().delta();
+        root_coros.extend(self.wake_on_tick());
+        let woken_by_tick = root_coros.len();
 
-        // Tick all coroutines waiting on duration
-        self.waiting_on_time.retain(|coro, timer| {
-            timer.tick(delta_time);
-            if timer.just_finished() {
-                root_coros.push_back(*coro);
-                false
-            } else {
-                true
+        if let Some(recorder) = &mut self.recorder {
+            if !recorder.start_tick() {
+                self.recorder = None;
             }
+        }
+
+        let delta_time = self.next_delta(world);
+        if let Some(pending) = &mut self.manual_time {
+            *pending = Duration::ZERO;
+        }
+        self.elapsed += delta_time;
+        world.insert_resource(TickTime {
+            delta: delta_time,
+            elapsed: self.elapsed,
         });
+        if let Some(times) = &mut self.poll_times {
+            times.clear();
+        }
+        self.reset_partition_stats();
+
+        // Wake the coroutines whose deadline is reached, without looking at the others
+        while let Some(Reverse((deadline, id))) = self.deadlines.peek().copied() {
+            if deadline > self.elapsed {
+                break;
+            }
+            self.deadlines.pop();
+
+            // Paused coroutines get a new deadline once unpaused
+            if self.waiting_on_time.get(&id) != Some(&deadline) || self.paused.contains_key(&id) {
+                continue;
+            }
+            self.waiting_on_time.remove(&id);
+            root_coros.push_back(id);
+        }
+        let woken_by_timer = root_coros.len();
+
+        // Only the coroutines with something new to read are woken up
+        root_coros.extend(self.waiting_on_events.wake(world));
+        let woken_by_event = root_coros.len();
+        root_coros.extend(self.waiting_on_channel.wake());
 
         let mut parents = ParentTable::new();
         let mut signals = HashMap::new();
 
+        for (i, c_id) in root_coros.iter().enumerate() {
+            let reason = if i < woken_by_tick {
+                WakeReason::Tick
+            } else if i < woken_by_timer {
+                WakeReason::Timer
+            } else if i < woken_by_event {
+                WakeReason::Event
+            } else {
+                WakeReason::Channel
+            };
+            record::wake(&mut self.recorder, *c_id, reason);
+        }
+
         let mut ready_coro: Vec<(Id, usize)> = root_coros
             .into_iter()
             .map(|c_id| (c_id, parents.add_root(c_id)))
             .collect();
 
+        // External signals are emitted before anything runs, so every coroutine can see them
+        for poll in &self.external_signals {
+            for id in poll(world) {
+                let by = parents.add_external();
+                signals.insert(id, by);
+                if let Some(children) = self.waiting_on_signal.remove(&id) {
+                    for c in children {
+                        let id = Id::from_bits(c);
+                        let node = parents.add_child(by, id);
+                        record::wake(&mut self.recorder, id, WakeReason::Signal);
+                        ready_coro.push((id, node));
+                    }
+                }
+            }
+        }
+
+        // Changes made by the coroutines of other executors since the last tick
+        for id in self.receive_shared_changes(world) {
+            let by = parents.add_external();
+            signals.insert(id, by);
+            if let Some(children) = self.waiting_on_signal.remove(&id) {
+                for c in children {
+                    let id = Id::from_bits(c);
+                    let node = parents.add_child(by, id);
+                    record::wake(&mut self.recorder, id, WakeReason::Signal);
+                    ready_coro.push((id, node));
+                }
+            }
+        }
+
+        // Coroutines waiting on a signal, events or a channel are never resumed if their entity
+        // is gone, unless they are woken up here
+        if self.despawn_policy != DespawnPolicy::Cancel || self.has_policy_overrides {
+            for id in self.despawned_waiters(world) {
+                self.waiting_on_signal.forget(id);
+                self.waiting_on_events.forget(id);
+                self.waiting_on_channel.forget(id);
+                ready_coro.push((id, parents.add_root(id)));
+            }
+        }
+
         while !ready_coro.is_empty() {
-            while let Some((coro_id, node)) = ready_coro.pop() {
+            if self.resumes_in_parallel() {
+                if let Err(panic) = self.resume_batch(&mut ready_coro, world) {
+                    self.recover_from_panic(
+                        &panic.coroutines,
+                        &mut ready_coro,
+                        &mut parents,
+                        &mut signals,
+                    );
+                    panic::resume_unwind(panic.payload);
+                }
+                self.process_channels(&mut ready_coro, &mut parents, &mut signals);
+                continue;
+            }
+
+            while let Some((coro_id, node)) = self.pick_ready(&mut ready_coro) {
                 if !self.ids.contains(coro_id) {
                     continue;
                 }
 
-                let coro = self.coroutines.get_mut(&coro_id).unwrap().get();
+                if self.paused.contains_key(&coro_id) {
+                    self.waiting_on_tick.insert(coro_id);
+                    continue;
+                }
 
-                if !coro.is_valid(world) {
-                    self.cancel(coro_id);
+                if !self.check_valid(coro_id, world) {
                     continue;
                 }
 
-                let status = Coroutine::resume(
-                    coro.as_mut(),
-                    world,
-                    &self.ids,
-                    node,
-                    &self.signal_channel,
-                    &self.new_coro_channel,
-                    &self.commands_channel,
-                );
+                self.record_resume(coro_id);
+                if let Some(watchdog) = &mut self.watchdog {
+                    watchdog.resumed(coro_id, self.elapsed);
+                }
+                let start =
+                    (self.poll_times.is_some() && !self.is_deterministic()).then(Instant::now);
+                let coro = self.coroutines.get_mut(&coro_id).unwrap().get();
+                let status = panic::catch_unwind(AssertUnwindSafe(|| {
+                    Coroutine::resume(
+                        coro.as_mut(),
+                        world,
+                        &self.ids,
+                        node,
+                        &self.signal_channel,
+                        &self.new_coro_channel,
+                        &self.commands_channel,
+                    )
+                }));
+                let status = match status {
+                    Ok(status) => status,
+                    Err(payload) => {
+                        self.recover_from_panic(
+                            &[coro_id],
+                            &mut ready_coro,
+                            &mut parents,
+                            &mut signals,
+                        );
+                        panic::resume_unwind(payload);
+                    }
+                };
+                self.has_policy_overrides |= coro.meta().despawn_policy().is_some();
+                let poll_time = start.map(|start| start.elapsed());
+                if let Some(poll_time) = poll_time {
+                    let name = coro.meta().name;
+                    self.add_poll_time(coro_id, name, poll_time);
+                }
+                self.count_partition_resume(coro_id, poll_time);
 
                 // TODO remove copy paste
                 // Note to self: When running on a single thread, it's faster to process each
@@ -142,9 +647,11 @@ impl Executor {
                     CoroStatus::Done => {
                         self.mark_as_done(coro_id, node, &mut ready_coro, &mut parents)
                     }
-                    CoroStatus::Tick => self.waiting_on_tick.push_back(coro_id),
+                    CoroStatus::Tick => {
+                        self.waiting_on_tick.insert(coro_id);
+                    }
                     CoroStatus::Duration(d) => {
-                        self.waiting_on_time.insert(coro_id, d);
+                        self.wait_until(coro_id, self.elapsed + d.remaining());
                     }
                     CoroStatus::First(handlers) => {
                         self.waiting_on_first.insert(coro_id, handlers.clone());
@@ -169,15 +676,19 @@ impl Executor {
                         if let Some(writer) = signals.get(&signal_id) {
                             if !parents.is_parent(*writer, node) {
                                 let node = parents.add_child(*writer, coro_id);
+                                record::wake(&mut self.recorder, coro_id, WakeReason::Signal);
                                 ready_coro.push((coro_id, node));
                                 continue;
                             }
                         }
 
-                        self.waiting_on_signal
-                            .entry(signal_id)
-                            .or_default()
-                            .insert(coro_id.to_bits());
+                        self.waiting_on_signal.insert(signal_id, coro_id);
+                    }
+                    CoroStatus::Event(wait) => {
+                        self.waiting_on_events.insert(wait, coro_id);
+                    }
+                    CoroStatus::Channel(probe) => {
+                        self.waiting_on_channel.insert(probe, coro_id);
                     }
                 };
             }
@@ -186,7 +697,172 @@ impl Executor {
         }
 
         self.ids.flush();
+        self.publish_changes(world);
         self.commands_channel.apply(world);
+        // Dropping a coroutine can queue cleanup operations, which then run this tick
+        self.drop_finished();
+        self.cleanup_queue.apply(world);
+        if self.diagnostics_enabled() {
+            self.warn_unreachable_waits(world);
+            self.warn_deadlocks();
+        }
+        self.check_stalls();
+    }
+
+    /// Called when the coroutines `panicked` panicked while being resumed, before the panic goes
+    /// on. They are canceled (along with what they started), and the coroutines which were about
+    /// to be resumed this tick, including the ones woken by the `signals` emitted so far, are
+    /// resumed on the next one instead, so that the executor is still usable if the panic is
+    /// caught.
+    fn recover_from_panic(
+        &mut self,
+        panicked: &[Id],
+        ready_coro: &mut Vec<(Id, usize)>,
+        parents: &mut ParentTable,
+        signals: &mut HashMap<SignalId, usize>,
+    ) {
+        self.process_channels(ready_coro, parents, signals);
+        for id in panicked {
+            self.cancel(*id);
+        }
+        for (id, _) in ready_coro.drain(..) {
+            if self.ids.contains(id) {
+                self.waiting_on_tick.insert(id);
+            }
+        }
+    }
+
+    /// Returns false if `coro_id` must not be resumed, because it isn't valid anymore. Its
+    /// [`DespawnPolicy`] is applied in that case.
+    fn check_valid(&mut self, coro_id: Id, world: &World) -> bool {
+        if self.orphaned.contains(coro_id.to_bits()) {
+            return true;
+        }
+
+        let coro = self.coroutines.get_mut(&coro_id).unwrap().get();
+        // The owner is checked as well, in case its `CoroutineOwner` marker was removed
+        let owner_despawned = coro
+            .meta()
+            .owner
+            .is_some_and(|owner| world.get_entity(owner).is_none());
+        if !owner_despawned && coro.is_valid(world) {
+            return true;
+        }
+
+        match coro.meta().despawn_policy().unwrap_or(self.despawn_policy) {
+            DespawnPolicy::Cancel => {
+                self.cancel(coro_id);
+                false
+            }
+            DespawnPolicy::Panic => {
+                let location = coro
+                    .suspended_at()
+                    .map_or(String::new(), |l| format!(" while suspended at {l}"));
+                panic!(
+                    "The entity of the coroutine {} was despawned{location}",
+                    coro.meta().name
+                );
+            }
+            DespawnPolicy::Resume => {
+                self.orphaned.insert(coro_id.to_bits());
+                true
+            }
+            // Not waiting on anything anymore, so it is never woken up
+            DespawnPolicy::Sleep => false,
+        }
+    }
+
+    /// Returns the coroutines waiting on a signal, events or a channel, which are no longer
+    /// valid.
+    fn despawned_waiters(&mut self, world: &World) -> Vec<Id> {
+        let mut waiters: Vec<Id> = self
+            .waiting_on_signal
+            .coroutines()
+            .chain(self.waiting_on_events.iter().map(|(id, _)| id))
+            .chain(self.waiting_on_channel.coroutines())
+            .filter(|id| !self.orphaned.contains(id.to_bits()))
+            .collect();
+        waiters.sort_unstable_by_key(|id| id.index());
+        waiters.retain(|id| {
+            self.coroutines
+                .get_mut(id)
+                .is_some_and(|c| !c.get().is_valid(world))
+        });
+        waiters
+    }
+
+    /// Returns the coroutines waiting on a signal which can never be emitted anymore, because
+    /// the entity (or resource) emitting it is gone, or on events whose [`Events`] resource was
+    /// removed, and which weren't warned about yet. Each comes with a description of its wait.
+    ///
+    /// [`Events`]: bevy::ecs::event::Events
+    fn new_unreachable_waits(&self, world: &World) -> Vec<(u64, String)> {
+        let signals = self
+            .waiting_on_signal
+            .iter()
+            .filter(|(signal, _)| match signal.owner {
+                Some(entity) => world.get_entity(entity).is_none(),
+                None => world.get_resource_by_id(signal.signal_type).is_none(),
+            })
+            .flat_map(|(signal, waiters)| waiters.iter().map(|w| (w, *signal)))
+            .filter(|(w, _)| !self.warned_unreachable.contains(*w))
+            .map(|(w, signal)| {
+                let source = match signal.owner {
+                    Some(entity) => {
+                        format!("a change of the entity {entity:?}, which was despawned")
+                    }
+                    None if signal.external => {
+                        "an external signal, whose resource was removed".to_owned()
+                    }
+                    None => "a change of a resource which was removed".to_owned(),
+                };
+                (w, source)
+            });
+        let events = self
+            .waiting_on_events
+            .iter()
+            .filter(|(id, events)| {
+                !self.warned_unreachable.contains(id.to_bits())
+                    && world.get_resource_by_id(*events).is_none()
+            })
+            .map(|(id, events)| {
+                let name = world
+                    .components()
+                    .get_info(events)
+                    .map_or("events", |info| info.name());
+                (
+                    id.to_bits(),
+                    format!("`{name}`, whose resource was removed"),
+                )
+            });
+        signals.chain(events).collect()
+    }
+
+    /// Warn once about each coroutine returned by
+    /// [`new_unreachable_waits`](Executor::new_unreachable_waits).
+    fn warn_unreachable_waits(&mut self, world: &World) {
+        for (waiter, source) in self.new_unreachable_waits(world) {
+            self.warned_unreachable.insert(waiter);
+            let Some(coro) = self.coroutines.get_mut(&Id::from_bits(waiter)) else {
+                continue;
+            };
+            let coro = coro.get();
+            let location = coro
+                .suspended_at()
+                .map_or(String::new(), |l| format!(" at {l}"));
+            warn!(
+                "The coroutine {}{location} waits on {source}. It will never be resumed.",
+                coro.meta().name
+            );
+        }
+    }
+
+    /// Take the next coroutine to resume out of `ready_coro`.
+    fn pick_ready(&self, ready_coro: &mut Vec<(Id, usize)>) -> Option<(Id, usize)> {
+        match &self.recorder {
+            Some(recorder) => recorder.pick(ready_coro),
+            None => ready_coro.pop(),
+        }
     }
 
     /// Mark a coroutine as done, and properly handles cleanup.
@@ -197,8 +873,21 @@ impl Executor {
         ready_coro: &mut Vec<(Id, usize)>,
         parents: &mut ParentTable,
     ) {
-        self.coroutines.remove(&coro_id);
+        self.retire(coro_id);
+        self.started_at.remove(&coro_id);
+        self.paused.remove(&coro_id);
+        self.waiting_on_time.remove(&coro_id);
+        self.waiting_on_signal.forget(coro_id);
+        self.waiting_on_events.forget(coro_id);
+        self.waiting_on_channel.forget(coro_id);
+        self.orphaned.remove(coro_id.to_bits());
+        self.warned_unreachable.remove(coro_id.to_bits());
+        self.warned_deadlocked.remove(coro_id.to_bits());
+        if let Some(watchdog) = &mut self.watchdog {
+            watchdog.forget(coro_id);
+        }
 
+        self.forget_owner(coro_id);
         if let Some(owned) = self.scope_ownership.remove(&coro_id) {
             for c in owned {
                 self.cancel(Id::from_bits(c))
@@ -209,6 +898,9 @@ impl Executor {
             if let Some(mut others) = self.waiting_on_first.remove(&parent) {
                 others.remove(coro_id.to_bits());
                 // coro is the "winner", all the others are cancelled
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.first_resolved(parent, coro_id);
+                }
                 for o in others {
                     let id = Id::from_bits(o);
                     self.is_awaited_by.remove(&id);
@@ -216,6 +908,7 @@ impl Executor {
                 }
 
                 let node = parents.add_child(coro_node, parent);
+                record::wake(&mut self.recorder, parent, WakeReason::Awaited);
                 ready_coro.push((parent, node));
             }
 
@@ -225,6 +918,7 @@ impl Executor {
                 let node = parents.add_child(coro_node, parent);
 
                 if others.is_empty() {
+                    record::wake(&mut self.recorder, parent, WakeReason::Awaited);
                     ready_coro.push((parent, node));
                     self.waiting_on_all.remove(&parent);
                 }
@@ -232,31 +926,120 @@ impl Executor {
         }
     }
 
+    /// Add `coroutine`, owned by `owner` if any, and returns a handle to cancel it. Returns
+    /// `None` if the parameters of the coroutine couldn't be initialized.
     pub fn add_function_coroutine<Marker: 'static, T, C>(
         &mut self,
         owner: Option<Entity>,
         world: &World,
         coroutine: C,
-    ) where
+    ) -> Option<CoroutineHandle>
+    where
         C: CoroutineParamFunction<Marker, T>,
         T: Sync + Send + 'static,
+    {
+        let options = CoroOptions {
+            owner,
+            ..Default::default()
+        };
+        let id = self.build_function_coroutine(world, options, coroutine)?;
+        self.handle(id)
+    }
+
+    /// Add many coroutines at once, each with its owner if any. Space for all of them is reserved
+    /// up front, which is cheaper than adding them one by one when starting a script for every
+    /// entity of a wave for instance.
+    pub fn add_batch<Marker: 'static, T, C, I>(&mut self, world: &World, coroutines: I)
+    where
+        C: CoroutineParamFunction<Marker, T>,
+        T: Sync + Send + 'static,
+        I: IntoIterator<Item = (Option<Entity>, C)>,
+    {
+        let coroutines = coroutines.into_iter();
+        let (additional, _) = coroutines.size_hint();
+        self.coroutines.reserve(additional);
+        self.started_at.reserve(additional);
+
+        for (owner, coroutine) in coroutines {
+            let options = CoroOptions {
+                owner,
+                ..Default::default()
+            };
+            self.build_function_coroutine(world, options, coroutine);
+        }
+    }
+
+    /// Like [`add_function_coroutine`](Executor::add_function_coroutine), but `coroutine` doesn't
+    /// need to be [`Send`]. It is bound to the current thread, and the executor must always be
+    /// ticked from it.
+    pub fn add_non_send_coroutine<Marker: 'static, T, C>(
+        &mut self,
+        owner: Option<Entity>,
+        world: &World,
+        coroutine: C,
+    ) where
+        C: CoroutineFunction<Marker, T>,
+        T: Sync + Send + 'static,
+    {
+        let options = CoroOptions {
+            owner,
+            non_send: true,
+            ..Default::default()
+        };
+        self.build_function_coroutine(world, options, coroutine);
+    }
+
+    fn build_function_coroutine<Marker: 'static, T, C>(
+        &mut self,
+        world: &World,
+        options: CoroOptions,
+        coroutine: C,
+    ) -> Option<Id>
+    where
+        C: CoroutineFunction<Marker, T>,
+        T: Sync + Send + 'static,
     {
         let resume_param = Resume::new(ResumeParam::new());
 
         let id = self.ids.allocate_id();
 
-        let new_scope = Scope::new(id, owner, resume_param.clone());
+        let new_scope = Scope::new(
+            id,
+            options.owner,
+            resume_param.clone(),
+            self.cleanup_queue.clone(),
+            self.pool.clone(),
+        );
 
-        if let Some(c) = FunctionCoroutine::new(
+        let mut c = FunctionCoroutine::new(
             new_scope,
             world.as_unsafe_world_cell_readonly(),
             resume_param,
             id,
             None,
             coroutine,
-        ) {
-            self.add_coroutine(id, SyncCell::new(Box::pin(c)));
-        };
+        )?;
+        if options.non_send {
+            c = c.bind_to_current_thread();
+        }
+        if let Some(name) = options.name {
+            c = c.with_name(name);
+        }
+        if let Some(policy) = options.despawn_policy {
+            c = c.with_despawn_policy(policy);
+            self.has_policy_overrides = true;
+        }
+        self.add_coroutine(id, SyncCell::new(self.pool.alloc(c)));
+        if let Some(key) = options.partition {
+            self.set_partition(id, key);
+        }
+        if options.priority != 0 {
+            self.priorities.insert(id, options.priority);
+        }
+        if !options.tags.is_empty() {
+            self.tags.insert(id, options.tags);
+        }
+        Some(id)
     }
 
     fn process_channels(
@@ -268,22 +1051,28 @@ impl Executor {
         for NewCoroutine {
             id,
             ran_after,
-            coroutine,
+            mut coroutine,
             is_owned_by,
+            started_by,
             should_start_now,
-        } in self.new_coro_channel.receive()
+        } in self.new_coro_channel.receive().collect::<Vec<_>>()
         {
+            self.track_owner(id, &mut coroutine);
             self.coroutines.insert(id, coroutine);
+            self.started_at.insert(id, self.elapsed);
+            self.inherit_partition(id, started_by);
 
             if let Some(parent) = is_owned_by {
                 self.scope_ownership
                     .entry(parent)
                     .or_default()
                     .insert(id.to_bits());
+                self.owned_by.insert(id, parent);
             }
 
             if should_start_now {
                 let next_node = parents.add_child(ran_after, id);
+                record::wake(&mut self.recorder, id, WakeReason::Started);
                 ready_coro.push((id, next_node));
             }
         }
@@ -291,14 +1080,17 @@ impl Executor {
         let mut just_done: Vec<(Id, usize)> = Vec::new();
         let mut just_canceled: Vec<Id> = Vec::new();
 
-        for YieldMsg { id, node, status } in self.yield_channel.receive() {
+        let yields: Vec<YieldMsg> = self.yield_channel.receive().collect();
+        for YieldMsg { id, node, status } in yields {
             match status {
                 CoroStatus::Done => {
                     just_done.push((id, node));
                 }
-                CoroStatus::Tick => self.waiting_on_tick.push_back(id),
+                CoroStatus::Tick => {
+                    self.waiting_on_tick.insert(id);
+                }
                 CoroStatus::Duration(d) => {
-                    self.waiting_on_time.insert(id, d);
+                    self.wait_until(id, self.elapsed + d.remaining());
                 }
                 CoroStatus::First(handlers) => {
                     self.waiting_on_first.insert(id, handlers.clone());
@@ -323,15 +1115,19 @@ impl Executor {
                     if let Some(writer) = signal_table.get(&signal_id) {
                         if !parents.is_parent(*writer, node) {
                             let node = parents.add_child(*writer, id);
+                            record::wake(&mut self.recorder, id, WakeReason::Signal);
                             ready_coro.push((id, node));
                             continue;
                         }
                     }
 
-                    self.waiting_on_signal
-                        .entry(signal_id)
-                        .or_default()
-                        .insert(id.to_bits());
+                    self.waiting_on_signal.insert(signal_id, id);
+                }
+                CoroStatus::Event(wait) => {
+                    self.waiting_on_events.insert(wait, id);
+                }
+                CoroStatus::Channel(probe) => {
+                    self.waiting_on_channel.insert(probe, id);
                 }
             };
         }
@@ -346,10 +1142,12 @@ impl Executor {
 
         for EmitMsg { id, by } in self.signal_channel.receive() {
             signal_table.insert(id, by);
+            self.emitted.insert(id);
             if let Some(children) = self.waiting_on_signal.remove(&id) {
                 for c in children {
                     let id = Id::from_bits(c);
                     let node = parents.add_child(by, id);
+                    record::wake(&mut self.recorder, id, WakeReason::Signal);
                     ready_coro.push((id, node));
                 }
             }
@@ -396,6 +1194,13 @@ impl ParentTable {
         node
     }
 
+    /// Add a root node which does not belong to any coroutine, such as the emitter of an
+    /// [`ExternalSignal`].
+    fn add_external(&mut self) -> usize {
+        self.table.push(SetUsize::new());
+        self.table.len() - 1
+    }
+
     /// Return true if (and only if) `parent` is a parent of `child`.
     /// It is useful to know if a write performed by coroutine A can
     /// be observed by coroutine B, which is the case if this returns
@@ -404,3 +1209,259 @@ impl ParentTable {
         self.table.get(child).unwrap().contains(parent)
     }
 }
+
+/// The coroutines waiting on each signal, indexed both ways so that waking the waiters of a
+/// signal only costs as much as the number of waiters, and forgetting a coroutine doesn't require
+/// looking at every signal.
+#[derive(Default)]
+struct SignalWaiters {
+    by_signal: HashMap<SignalId, SetU64>,
+    by_coroutine: HashMap<Id, SignalId>,
+}
+
+impl SignalWaiters {
+    fn insert(&mut self, signal: SignalId, id: Id) {
+        self.forget(id);
+        self.by_signal
+            .entry(signal)
+            .or_default()
+            .insert(id.to_bits());
+        self.by_coroutine.insert(id, signal);
+    }
+
+    /// Remove and return all the coroutines waiting on `signal`.
+    fn remove(&mut self, signal: &SignalId) -> Option<SetU64> {
+        let waiters = self.by_signal.remove(signal)?;
+        for w in waiters.iter() {
+            self.by_coroutine.remove(&Id::from_bits(w));
+        }
+        Some(waiters)
+    }
+
+    /// Stop `id` from waiting on its signal, if any.
+    fn forget(&mut self, id: Id) {
+        if let Some(signal) = self.by_coroutine.remove(&id) {
+            if let Some(waiters) = self.by_signal.get_mut(&signal) {
+                waiters.remove(id.to_bits());
+                if waiters.is_empty() {
+                    self.by_signal.remove(&signal);
+                }
+            }
+        }
+    }
+
+    fn contains(&self, id: Id) -> bool {
+        self.by_coroutine.contains_key(&id)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&SignalId, &SetU64)> {
+        self.by_signal.iter()
+    }
+
+    fn coroutines(&self) -> impl Iterator<Item = Id> + '_ {
+        self.by_coroutine.keys().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use bevy::prelude::{Event, Events, Resource, World};
+
+    use crate::{
+        external_signal::{ExternalSignal, Triggered},
+        prelude::Scope,
+    };
+
+    use super::*;
+
+    #[derive(Resource)]
+    struct Trigger;
+
+    impl ExternalSignal for Trigger {
+        fn poll(&mut self) -> Option<Triggered> {
+            None
+        }
+    }
+
+    /// Start a coroutine waiting on `Trigger`, and returns it once it is suspended.
+    fn waiting_on_trigger(world: &mut World, executor: &mut Executor, owner: Option<Entity>) -> Id {
+        executor.add_function_coroutine(owner, world, |mut s: Scope| async move {
+            s.on_external::<Trigger>().await;
+        });
+        executor.tick(world);
+        let waiters: Vec<Id> = executor.waiting_on_signal.coroutines().collect();
+        assert_eq!(waiters.len(), 1);
+        waiters[0]
+    }
+
+    fn assert_no_waiters(executor: &Executor) {
+        assert!(executor.waiting_on_signal.by_signal.is_empty());
+        assert!(executor.waiting_on_signal.by_coroutine.is_empty());
+    }
+
+    #[test]
+    fn forgetting_canceled_waiters() {
+        let mut world = World::new();
+        world.insert_resource(Trigger);
+        let mut executor = Executor::with_manual_time();
+
+        let id = waiting_on_trigger(&mut world, &mut executor, None);
+        executor.cancel_coroutine(id);
+        executor.tick(&mut world);
+        assert_no_waiters(&executor);
+    }
+
+    #[test]
+    fn forgetting_despawned_waiters() {
+        let mut world = World::new();
+        world.insert_resource(Trigger);
+        let mut executor = Executor::with_manual_time();
+
+        let owner = world.spawn_empty().id();
+        waiting_on_trigger(&mut world, &mut executor, Some(owner));
+        world.despawn(owner);
+        executor.tick(&mut world);
+        assert_no_waiters(&executor);
+    }
+
+    #[test]
+    fn separating_external_signals() {
+        let mut world = World::new();
+        world.insert_resource(Trigger);
+        let mut executor = Executor::with_manual_time();
+
+        let id = waiting_on_trigger(&mut world, &mut executor, None);
+        let signal_type = world.components().resource_id::<Trigger>().unwrap();
+        // The emitted signals are only looked at once a coroutine was resumed
+        executor.add_function_coroutine(None, &world, |_: Scope| async move {});
+        // A change of the resource holding the external signal is not the signal
+        executor.signal_channel.send(EmitMsg {
+            id: SignalId {
+                signal_type,
+                owner: None,
+                external: false,
+                key: None,
+            },
+            by: 0,
+        });
+        executor.tick(&mut world);
+        assert!(executor.waiting_on_signal.contains(id));
+    }
+
+    #[test]
+    fn warning_once_about_unreachable_waits() {
+        let mut world = World::new();
+        world.insert_resource(Trigger);
+        let mut executor = Executor::with_manual_time();
+        executor.set_diagnostics(true);
+
+        let waiter = waiting_on_trigger(&mut world, &mut executor, None);
+        assert!(executor.new_unreachable_waits(&world).is_empty());
+        world.remove_resource::<Trigger>();
+        assert_eq!(executor.new_unreachable_waits(&world).len(), 1);
+        executor.tick(&mut world);
+        assert!(executor.warned_unreachable.contains(waiter.to_bits()));
+        assert!(executor.new_unreachable_waits(&world).is_empty());
+    }
+
+    #[test]
+    fn warning_about_removed_events() {
+        #[derive(Event)]
+        struct Hit;
+
+        let mut world = World::new();
+        world.init_resource::<Events<Hit>>();
+        let mut executor = Executor::with_manual_time();
+        executor.set_diagnostics(true);
+
+        let waiter = executor
+            .add_function_coroutine(None, &world, |mut s: Scope| async move {
+                s.next_event::<Hit>().await;
+            })
+            .unwrap()
+            .id();
+        executor.tick(&mut world);
+        assert!(executor.new_unreachable_waits(&world).is_empty());
+        world.remove_resource::<Events<Hit>>();
+        let unreachable = executor.new_unreachable_waits(&world);
+        assert_eq!(unreachable.len(), 1);
+        assert!(unreachable[0].1.contains("Events"));
+        executor.tick(&mut world);
+        assert!(executor.warned_unreachable.contains(waiter.to_bits()));
+        assert!(executor.new_unreachable_waits(&world).is_empty());
+    }
+
+    #[test]
+    fn disabling_diagnostics() {
+        let mut world = World::new();
+        world.insert_resource(Trigger);
+        let mut executor = Executor::with_manual_time();
+        executor.set_diagnostics(false);
+
+        waiting_on_trigger(&mut world, &mut executor, None);
+        world.remove_resource::<Trigger>();
+        executor.tick(&mut world);
+        assert!(executor.warned_unreachable.is_empty());
+        assert_eq!(executor.new_unreachable_waits(&world).len(), 1);
+    }
+    #[test]
+    fn resuming_event_waiters_only_on_new_events() {
+        #[derive(Event)]
+        struct Hit;
+
+        let mut world = World::new();
+        world.init_resource::<Events<Hit>>();
+        let mut executor = Executor::with_manual_time();
+
+        let resumes = Arc::new(AtomicU32::new(0));
+        let r = Arc::clone(&resumes);
+        executor.add_function_coroutine(None, &world, move |mut s: Scope| async move {
+            loop {
+                r.fetch_add(1, Ordering::Relaxed);
+                s.next_event::<Hit>().await;
+            }
+        });
+        for _ in 0..3 {
+            executor.tick(&mut world);
+        }
+        assert_eq!(resumes.load(Ordering::Relaxed), 1);
+        assert_eq!(executor.waiting_on_events.iter().count(), 1);
+
+        world.send_event(Hit);
+        executor.tick(&mut world);
+        executor.tick(&mut world);
+        assert_eq!(resumes.load(Ordering::Relaxed), 2);
+    }
+
+    #[cfg(feature = "crossbeam-channel")]
+    #[test]
+    fn resuming_channel_waiters_only_on_new_values() {
+        let mut world = World::new();
+        let mut executor = Executor::with_manual_time();
+
+        let (tx, mut rx) = crossbeam_channel::unbounded::<u32>();
+        let resumes = Arc::new(AtomicU32::new(0));
+        let r = Arc::clone(&resumes);
+        executor.add_function_coroutine(None, &world, move |mut s: Scope| async move {
+            loop {
+                r.fetch_add(1, Ordering::Relaxed);
+                if s.recv(&mut rx).await.is_none() {
+                    break;
+                }
+            }
+        });
+        for _ in 0..3 {
+            executor.tick(&mut world);
+        }
+        assert_eq!(resumes.load(Ordering::Relaxed), 1);
+
+        tx.send(1).unwrap();
+        executor.tick(&mut world);
+        assert_eq!(resumes.load(Ordering::Relaxed), 2);
+        drop(tx);
+        executor.tick(&mut world);
+        assert!(executor.waiting_on_channel.coroutines().next().is_none());
+    }
+}
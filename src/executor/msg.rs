@@ -1,8 +1,9 @@
-use bevy::prelude::Entity;
+use bevy::ecs::event::{Event, Events};
+use bevy::prelude::{Entity, World};
 use bevy::{ecs::component::ComponentId, time::Timer};
 use tinyset::SetU64;
 
-use crate::{id_alloc::Id, HeapCoro};
+use crate::{function_coroutine::await_recv::ChannelProbe, id_alloc::Id, HeapCoro};
 
 /// A newly spawned [`Coroutine`] and how it should be handled by the [`Executor`](executor).
 pub struct NewCoroutine {
@@ -10,6 +11,8 @@ pub struct NewCoroutine {
     pub ran_after: usize,
     pub coroutine: HeapCoro,
     pub is_owned_by: Option<Id>,
+    /// The coroutine whose scope started this one.
+    pub started_by: Id,
     pub should_start_now: bool,
 }
 
@@ -32,12 +35,46 @@ pub enum CoroStatus {
     All(SetU64),
     /// Get resumed once the signal is triggered
     Signal(SignalId),
+    /// Get resumed once events were sent
+    Event(EventWait),
+    /// Get resumed once the probe reports that the channel can be received from
+    Channel(ChannelProbe),
     /// Has finished execution
     Done,
     /// Never get resumed, and gets cleanup instead
     Cancel,
 }
 
+/// A wait on the events of a type, see [`Scope::next_event`].
+///
+/// [`Scope::next_event`]: crate::prelude::Scope::next_event
+pub struct EventWait {
+    /// The resource id of the [`Events`].
+    pub events: ComponentId,
+    /// The number of events the coroutine has read (or skipped).
+    pub seen: usize,
+    /// Returns the number of events sent so far, or `None` if the [`Events`] resource is missing.
+    pub sent: fn(&World) -> Option<usize>,
+}
+
+impl EventWait {
+    /// A wait on the events `E`, by a coroutine which has `unread` events left. Returns `None`
+    /// if the [`Events<E>`] resource is missing.
+    pub fn new<E: Event>(world: &World, unread: usize) -> Option<Self> {
+        Some(Self {
+            events: world.components().resource_id::<Events<E>>()?,
+            seen: sent_events::<E>(world)?.saturating_sub(unread),
+            sent: sent_events::<E>,
+        })
+    }
+}
+
+fn sent_events<E: Event>(world: &World) -> Option<usize> {
+    let events = world.get_resource::<Events<E>>()?;
+    // The events are numbered continuously, starting from the oldest one still stored
+    Some(events.oldest_event_count() + events.len())
+}
+
 /// The msg notifying that a [`Signal`] was emitted.
 #[derive(Clone, Copy)]
 pub struct EmitMsg {
@@ -52,4 +89,13 @@ pub struct EmitMsg {
 pub struct SignalId {
     pub signal_type: ComponentId,
     pub owner: Option<Entity>,
+    /// Whether the signal is triggered by an
+    /// [`ExternalSignal`](crate::external_signal::ExternalSignal), and not by a change of
+    /// `signal_type`. The resource of an external signal can then change without waking the
+    /// coroutines awaiting it.
+    pub external: bool,
+    /// The key of an external signal, which only wakes the coroutines awaiting that key, see
+    /// [`Triggered::Key`](crate::external_signal::Triggered::Key). Always `None` for the other
+    /// signals.
+    pub key: Option<u64>,
 }
@@ -0,0 +1,106 @@
+//! Canceling the coroutines of an entity as soon as it is despawned, even those which aren't due
+//! to be resumed. Owner entities are given a [`CoroutineOwner`] marker, so that only the removals
+//! of that marker are looked at each tick, instead of every owner. Every owner is only looked at
+//! when some removals were missed, because the executor didn't tick for a few frames.
+
+use bevy::{
+    ecs::{event::ManualEventReader, removal_detection::RemovedComponentEntity},
+    prelude::{Component, Entity, World},
+    utils::HashMap,
+};
+
+use crate::{id_alloc::Id, HeapCoro};
+
+use super::{DespawnPolicy, Executor};
+
+/// Marks the entities owning coroutines, so that the [`Executor`] notices when they are
+/// despawned. It is inserted on the tick following the start of their first coroutine, which
+/// moves the entity to another archetype, and stays there afterward.
+#[derive(Component, Default)]
+pub struct CoroutineOwner;
+
+/// The coroutines owned by each entity.
+#[derive(Default)]
+pub(super) struct Owners {
+    by_entity: HashMap<Entity, Vec<Id>>,
+    /// The owners which may not have a [`CoroutineOwner`] marker yet.
+    unmarked: Vec<Entity>,
+    removed: ManualEventReader<RemovedComponentEntity>,
+}
+
+impl Executor {
+    pub(super) fn track_owner(&mut self, id: Id, coroutine: &mut HeapCoro) {
+        let Some(owner) = coroutine.get().meta().owner else {
+            return;
+        };
+        let owned = self.owners.by_entity.entry(owner).or_default();
+        if owned.is_empty() {
+            self.owners.unmarked.push(owner);
+        }
+        owned.push(id);
+    }
+
+    pub(super) fn forget_owned(&mut self, owner: Entity, id: Id) {
+        if let Some(owned) = self.owners.by_entity.get_mut(&owner) {
+            owned.retain(|owned| *owned != id);
+            if owned.is_empty() {
+                self.owners.by_entity.remove(&owner);
+            }
+        }
+    }
+
+    /// Cancel the coroutines whose owner entity was despawned since the last tick. Coroutines
+    /// with another [`DespawnPolicy`] are left to `check_valid`.
+    pub(super) fn cancel_despawned_owners(&mut self, world: &mut World) {
+        let marker = world.init_component::<CoroutineOwner>();
+
+        let mut despawned = Vec::new();
+        for owner in std::mem::take(&mut self.owners.unmarked) {
+            match world.get_entity_mut(owner) {
+                Some(mut entity) if !entity.contains::<CoroutineOwner>() => {
+                    entity.insert(CoroutineOwner);
+                }
+                Some(_) => {}
+                None => despawned.push(owner),
+            }
+        }
+        if let Some(removed) = world.removed_components().get(marker) {
+            // The removals are only kept for two frames. When the executor doesn't tick that often
+            // (in `FixedUpdate`, or ticked by hand), some may be gone, so every owner is checked.
+            if self.owners.removed.missed_events(removed) > 0 {
+                despawned.extend(
+                    self.owners
+                        .by_entity
+                        .keys()
+                        .copied()
+                        .filter(|entity| world.get_entity(*entity).is_none()),
+                );
+            }
+            despawned.extend(
+                self.owners
+                    .removed
+                    .iter(removed)
+                    .map(|entity| Entity::from(entity.clone()))
+                    .filter(|entity| world.get_entity(*entity).is_none()),
+            );
+        }
+        if despawned.is_empty() {
+            return;
+        }
+
+        // Sorted so that the coroutines are canceled in the same order every run
+        despawned.sort();
+        despawned.dedup();
+        for entity in despawned {
+            for id in self.owners.by_entity.remove(&entity).unwrap_or_default() {
+                let Some(coro) = self.coroutines.get_mut(&id) else {
+                    continue;
+                };
+                let policy = coro.get().meta().despawn_policy();
+                if policy.unwrap_or(self.despawn_policy) == DespawnPolicy::Cancel {
+                    self.cancel_coroutine(id);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Resuming coroutines with non conflicting accesses at the same time, see
+//! [`Executor::enable_parallel_resume`].
+
+use std::{
+    any::Any,
+    cell::Cell,
+    panic::{self, AssertUnwindSafe},
+    ptr::null,
+    sync::Mutex,
+};
+
+use bevy::{
+    prelude::World,
+    tasks::{ComputeTaskPool, TaskPool},
+    utils::HashMap,
+};
+
+use crate::{id_alloc::Id, CoroAccess};
+
+/// The coroutines of a batch which panicked, along with the first panic payload, to resume
+/// unwinding once the executor recovered.
+pub(super) struct BatchPanic {
+    pub coroutines: Vec<Id>,
+    pub payload: Box<dyn Any + Send>,
+}
+
+impl BatchPanic {
+    fn add(panic: &mut Option<BatchPanic>, id: Id, payload: Box<dyn Any + Send>) {
+        match panic {
+            Some(panic) => panic.coroutines.push(id),
+            None => {
+                *panic = Some(BatchPanic {
+                    coroutines: vec![id],
+                    payload,
+                })
+            }
+        }
+    }
+}
+
+/// The accesses of the coroutines of the batch being resumed, including the ones they make
+/// outside of their parameters. Helpers going through the [`World`] directly report their
+/// accesses with [`validate_access`], which panics if they conflict with another coroutine of the
+/// batch. This is checked in release builds too: a conflicting access would alias a reference
+/// held by another thread.
+struct BatchValidator {
+    accesses: Mutex<HashMap<Id, (&'static str, CoroAccess)>>,
+}
+
+thread_local! {
+    static VALIDATOR: Cell<*const BatchValidator> = const { Cell::new(null()) };
+}
+
+/// Sets the validator of the current thread, until dropped.
+struct Validating;
+
+impl Validating {
+    fn start(validator: &BatchValidator) -> Self {
+        VALIDATOR.with(|v| v.set(validator));
+        Validating
+    }
+}
+
+impl Drop for Validating {
+    fn drop(&mut self) {
+        VALIDATOR.with(|v| v.set(null()));
+    }
+}
+
+/// Report an access made by the coroutine `id` outside of its parameters, described by `access`.
+/// Does nothing unless the coroutine is resumed in a parallel batch.
+pub(crate) fn validate_access(id: Id, access: impl FnOnce(&mut CoroAccess)) {
+    let validator = VALIDATOR.with(Cell::get);
+    if validator.is_null() {
+        return;
+    }
+    // Safety: The validator outlives the resume of the batch, during which the pointer is set.
+    let mut accesses = unsafe { &*validator }.accesses.lock().unwrap();
+
+    let mut new_access = CoroAccess::default();
+    access(&mut new_access);
+    for (other, (name, other_access)) in accesses.iter() {
+        if *other != id && !new_access.is_compatible(other_access) {
+            let this_name = accesses.get(&id).map_or("?", |(name, _)| name);
+            panic!(
+                "The coroutine `{this_name}` accessed the world in a way that conflicts with \
+                `{name}`, which is resumed at the same time. Coroutines resumed in parallel must \
+                only access the world through their parameters."
+            );
+        }
+    }
+    if let Some((_, own)) = accesses.get_mut(&id) {
+        own.extend(&new_access);
+    }
+}
+
+use super::Executor;
+
+impl Executor {
+    /// Resume the ready coroutines in parallel batches, using the [`ComputeTaskPool`]. Coroutines
+    /// end up in the same batch when their [`CoroAccess`] are compatible, for instance when they
+    /// write to the same component but on different entities.
+    ///
+    /// Parallel resumes are skipped while recording or replaying, and on wasm32, where there is a
+    /// single thread. They aren't measured by [`set_profiling`](Executor::set_profiling).
+    /// Non-[`Send`] coroutines are still resumed on the thread ticking the executor.
+    ///
+    /// The helpers of [`Scope`](crate::prelude::Scope) which access the world outside of the
+    /// parameters of the coroutine report it, and panic when another coroutine of the same batch
+    /// writes to what they access, in release builds too. Reading the whole world, as
+    /// [`Scope::until`] does, conflicts with any write, so waiting on a condition only works in
+    /// batches where the other coroutines don't write.
+    ///
+    /// # Safety
+    /// The caller must ensure that the custom
+    /// [`CoroParam`](crate::function_coroutine::coro_param::CoroParam)s and
+    /// [`Coroutine`](crate::Coroutine)s resumed by the executor declare all their accesses in
+    /// their [`CoroAccess`], and that coroutines don't reach the [`World`] through other means
+    /// than this crate.
+    ///
+    /// [`Scope::until`]: crate::prelude::Scope::until
+    pub unsafe fn enable_parallel_resume(&mut self) {
+        self.parallel = true;
+    }
+
+    /// Go back to resuming coroutines one at a time.
+    pub fn disable_parallel_resume(&mut self) {
+        self.parallel = false;
+    }
+
+    pub(super) fn resumes_in_parallel(&self) -> bool {
+        self.parallel
+            && self.recorder.is_none()
+            && !self.is_deterministic()
+            && cfg!(not(target_arch = "wasm32"))
+    }
+
+    /// Resume a batch of ready coroutines, whose accesses don't conflict with each other. The ones
+    /// that didn't fit in the batch are left in `ready_coro`. The status of each resumed coroutine
+    /// is sent on the yield channel.
+    ///
+    /// If coroutines panic, the others still run (or are put back in `ready_coro` for a non-Send
+    /// one), and the panic is returned.
+    pub(super) fn resume_batch(
+        &mut self,
+        ready_coro: &mut Vec<(Id, usize)>,
+        world: &mut World,
+    ) -> Result<(), BatchPanic> {
+        let mut batch_access = CoroAccess::default();
+        let mut batch = HashMap::<Id, usize>::default();
+        let mut deferred = Vec::new();
+
+        while let Some((coro_id, node)) = self.pick_ready(ready_coro) {
+            if !self.ids.contains(coro_id) {
+                continue;
+            }
+
+            if self.paused.contains_key(&coro_id) {
+                self.waiting_on_tick.insert(coro_id);
+                continue;
+            }
+
+            if batch.contains_key(&coro_id) {
+                deferred.push((coro_id, node));
+                continue;
+            }
+
+            if !self.check_valid(coro_id, world) {
+                continue;
+            }
+
+            let coro = self.coroutines.get_mut(&coro_id).unwrap().get();
+            if coro.meta().is_non_send() {
+                // Resumed right away on this thread, before the batch runs on the task pool
+                if let Some(watchdog) = &mut self.watchdog {
+                    watchdog.resumed(coro_id, self.elapsed);
+                }
+                // Safety: Nothing else accesses the world at the same time.
+                let resumed = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+                    coro.as_mut().resume_unsafe(
+                        world.as_unsafe_world_cell(),
+                        &self.ids,
+                        node,
+                        &self.signal_channel,
+                        &self.new_coro_channel,
+                        &self.commands_channel,
+                        &self.yield_channel,
+                    );
+                }));
+                if let Err(payload) = resumed {
+                    ready_coro.extend(deferred);
+                    ready_coro.extend(batch);
+                    return Err(BatchPanic {
+                        coroutines: vec![coro_id],
+                        payload,
+                    });
+                }
+                continue;
+            }
+
+            let access = coro.meta().access();
+            if !access.is_compatible(&batch_access) {
+                deferred.push((coro_id, node));
+                continue;
+            }
+            batch_access.extend(access);
+
+            if let Some(watchdog) = &mut self.watchdog {
+                watchdog.resumed(coro_id, self.elapsed);
+            }
+            batch.insert(coro_id, node);
+        }
+
+        deferred.reverse();
+        ready_coro.extend(deferred);
+
+        let mut validator = BatchValidator {
+            accesses: Mutex::default(),
+        };
+        let accesses = validator.accesses.get_mut().unwrap();
+        for (id, coro) in self.coroutines.iter_mut() {
+            if batch.contains_key(id) {
+                let meta = coro.get().meta();
+                accesses.insert(*id, (meta.name, meta.access().clone()));
+            }
+        }
+        let validator = &validator;
+        let panicked = Mutex::new(None);
+        let panicked_ref = &panicked;
+
+        let world = world.as_unsafe_world_cell();
+        let ids = &self.ids;
+        let signal_channel = &self.signal_channel;
+        let new_coro_channel = &self.new_coro_channel;
+        let commands_channel = &self.commands_channel;
+        let yield_channel = &self.yield_channel;
+
+        ComputeTaskPool::init(TaskPool::default).scope(|scope| {
+            for (id, coro) in self.coroutines.iter_mut() {
+                let Some(&node) = batch.get(id) else {
+                    continue;
+                };
+                let coro = coro.get();
+
+                let id = *id;
+                scope.spawn(async move {
+                    let _validating = Validating::start(validator);
+                    // Safety: The coroutines of a batch have compatible accesses, they only use
+                    // the world through the cell, and the accesses outside of their parameters are
+                    // validated. The caller of `enable_parallel_resume` ensures that nothing
+                    // bypasses this crate.
+                    let resumed = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+                        coro.as_mut().resume_unsafe(
+                            world,
+                            ids,
+                            node,
+                            signal_channel,
+                            new_coro_channel,
+                            commands_channel,
+                            yield_channel,
+                        );
+                    }));
+                    if let Err(payload) = resumed {
+                        BatchPanic::add(&mut panicked_ref.lock().unwrap(), id, payload);
+                    }
+                });
+            }
+        });
+
+        for (id, coro) in self.coroutines.iter_mut() {
+            if batch.contains_key(id) {
+                self.has_policy_overrides |= coro.get().meta().despawn_policy().is_some();
+            }
+        }
+
+        match panicked.into_inner().unwrap() {
+            Some(panic) => Err(panic),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::component::ComponentId, prelude::Entity};
+
+    use crate::SourceId;
+
+    use super::*;
+
+    fn write(entity: u32) -> impl FnOnce(&mut CoroAccess) {
+        move |access: &mut CoroAccess| {
+            access.add_write(
+                SourceId::Entity(Entity::from_raw(entity)),
+                ComponentId::new(0),
+            );
+        }
+    }
+
+    fn validator() -> BatchValidator {
+        let mut accesses = HashMap::default();
+        for i in 0..2 {
+            let mut access = CoroAccess::default();
+            write(i)(&mut access);
+            accesses.insert(Id::from_bits(i as u64), ("example", access));
+        }
+        BatchValidator {
+            accesses: Mutex::new(accesses),
+        }
+    }
+
+    #[test]
+    fn validating_compatible_access() {
+        let validator = validator();
+        let _validating = Validating::start(&validator);
+        validate_access(Id::from_bits(0), write(0));
+        validate_access(Id::from_bits(0), write(2));
+        validate_access(Id::from_bits(1), write(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicts with `example`")]
+    fn validating_conflicting_access() {
+        let validator = validator();
+        let _validating = Validating::start(&validator);
+        validate_access(Id::from_bits(0), write(1));
+    }
+}
@@ -0,0 +1,127 @@
+//! Partitioning coroutines by a key, such as the client of a server, to cancel, budget and
+//! account for them together. See [`CoroBuilder::partition`](super::builder::CoroBuilder::partition).
+
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+
+use crate::id_alloc::Id;
+
+use super::Executor;
+
+/// The key of a partition, chosen by the caller (the id of a client for instance).
+pub type PartitionKey = u64;
+
+/// The coroutines of each partition, along with their accounting.
+#[derive(Default)]
+pub(super) struct Partitions {
+    of: HashMap<Id, PartitionKey>,
+    budgets: HashMap<PartitionKey, usize>,
+    stats: HashMap<PartitionKey, PartitionStats>,
+}
+
+/// Statistics about the coroutines of a partition.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PartitionStats {
+    /// The number of coroutines currently in the partition.
+    pub coroutines: usize,
+    /// The number of times coroutines of the partition were resumed during the last tick.
+    pub resumes: usize,
+    /// The time spent resuming them during the last tick. Only measured when
+    /// [profiling](Executor::set_profiling) is enabled.
+    pub poll_time: Duration,
+}
+
+impl Executor {
+    /// Returns the partition of the coroutine `id`, if it is in one.
+    pub fn partition(&self, id: Id) -> Option<PartitionKey> {
+        self.partitions.of.get(&id).copied()
+    }
+
+    /// Cancel all the coroutines of the partition `key`, when a client disconnects for instance.
+    pub fn cancel_partition(&mut self, key: PartitionKey) {
+        let ids: Vec<Id> = self
+            .partitions
+            .of
+            .iter()
+            .filter(|(_, k)| **k == key)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            self.cancel_coroutine(id);
+        }
+    }
+
+    /// Resume at most `budget` coroutines of the partition `key` waiting on the next tick, each
+    /// tick, like [`set_resume_budget`](Executor::set_resume_budget) does for the whole executor.
+    /// With `None`, the default, they are all resumed.
+    pub fn set_partition_budget(&mut self, key: PartitionKey, budget: Option<usize>) {
+        match budget {
+            Some(budget) => self.partitions.budgets.insert(key, budget),
+            None => self.partitions.budgets.remove(&key),
+        };
+    }
+
+    /// Returns the statistics of the partition `key`.
+    pub fn partition_stats(&self, key: PartitionKey) -> PartitionStats {
+        let coroutines = self.partitions.of.values().filter(|k| **k == key).count();
+        PartitionStats {
+            coroutines,
+            ..self.partitions.stats.get(&key).copied().unwrap_or_default()
+        }
+    }
+
+    /// Put `id` in the partition of the coroutine which started it, if any.
+    pub(super) fn inherit_partition(&mut self, id: Id, started_by: Id) {
+        if let Some(key) = self.partition(started_by) {
+            self.partitions.of.insert(id, key);
+        }
+    }
+
+    pub(super) fn set_partition(&mut self, id: Id, key: PartitionKey) {
+        self.partitions.of.insert(id, key);
+    }
+
+    pub(super) fn leave_partition(&mut self, id: Id) {
+        self.partitions.of.remove(&id);
+    }
+
+    /// Keep the coroutines woken by a tick which exceed the budget of their partition for the
+    /// following ticks.
+    pub(super) fn apply_partition_budgets(&mut self, woken: &mut Vec<Id>) {
+        if self.partitions.budgets.is_empty() {
+            return;
+        }
+
+        let mut used: HashMap<PartitionKey, usize> = HashMap::new();
+        let partitions = &self.partitions;
+        let waiting_on_tick = &mut self.waiting_on_tick;
+        woken.retain(|id| {
+            let Some(key) = partitions.of.get(id) else {
+                return true;
+            };
+            let Some(budget) = partitions.budgets.get(key) else {
+                return true;
+            };
+            let used = used.entry(*key).or_default();
+            *used += 1;
+            if *used > *budget {
+                waiting_on_tick.insert(*id);
+                return false;
+            }
+            true
+        });
+    }
+
+    pub(super) fn reset_partition_stats(&mut self) {
+        self.partitions.stats.clear();
+    }
+
+    pub(super) fn count_partition_resume(&mut self, id: Id, poll_time: Option<Duration>) {
+        if let Some(key) = self.partitions.of.get(&id) {
+            let stats = self.partitions.stats.entry(*key).or_default();
+            stats.resumes += 1;
+            stats.poll_time += poll_time.unwrap_or_default();
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Measurement of the time spent resuming each coroutine, see [`Executor::set_profiling`].
+
+use std::time::Duration;
+
+use bevy::utils::HashMap;
+
+use crate::id_alloc::Id;
+
+use super::Executor;
+
+/// The time spent resuming a coroutine during the last tick.
+#[derive(Clone, Copy, Debug)]
+pub struct PollTime {
+    pub id: Id,
+    pub name: &'static str,
+    pub duration: Duration,
+}
+
+impl Executor {
+    /// Start or stop measuring how long each coroutine takes to be resumed. The measures are
+    /// reset at the beginning of each tick.
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.poll_times = enabled.then(HashMap::new);
+    }
+
+    /// Returns the time spent resuming each coroutine during the last tick, if profiling is
+    /// enabled. Coroutines which weren't resumed are not part of it.
+    pub fn poll_times(&self) -> impl Iterator<Item = PollTime> + '_ {
+        self.poll_times
+            .iter()
+            .flatten()
+            .map(|(id, (name, duration))| PollTime {
+                id: *id,
+                name,
+                duration: *duration,
+            })
+    }
+
+    /// Returns the `n` coroutines which took the longest to be resumed during the last tick,
+    /// from the slowest to the fastest.
+    pub fn top_poll_times(&self, n: usize) -> Vec<PollTime> {
+        let mut times: Vec<PollTime> = self.poll_times().collect();
+        times.sort_unstable_by_key(|t| std::cmp::Reverse(t.duration));
+        times.truncate(n);
+        times
+    }
+
+    pub(super) fn add_poll_time(&mut self, id: Id, name: &'static str, duration: Duration) {
+        if let Some(times) = &mut self.poll_times {
+            times.entry(id).or_insert((name, Duration::ZERO)).1 += duration;
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Recording and replay of the scheduling decisions of the [`Executor`].
+//!
+//! Coroutines which are ready during the same tick are resumed one after the other, and the order
+//! matters as soon as they touch the same data (or race in a [`Scope::first`]). A [`Recording`]
+//! keeps track of this order, so that a bug caused by a specific ordering can be reproduced by
+//! [replaying](Executor::replay) it.
+//!
+//! [`Scope::first`]: crate::function_coroutine::scope::Scope::first
+
+use bevy::{log::warn, utils::HashMap};
+
+use crate::id_alloc::Id;
+
+use super::Executor;
+
+/// Why a coroutine was resumed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WakeReason {
+    /// It was just started.
+    Started,
+    /// It was waiting on the next tick.
+    Tick,
+    /// Its timer finished.
+    Timer,
+    /// A signal it was waiting on was emitted.
+    Signal,
+    /// Events it was waiting on were sent.
+    Event,
+    /// The channel it was waiting on can be received from.
+    Channel,
+    /// The coroutines it awaits (with `first` or `all`) are done.
+    Awaited,
+}
+
+/// A scheduling decision taken by the [`Executor`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScheduleEvent {
+    /// The coroutine `id` was resumed.
+    Resume { id: u64, reason: WakeReason },
+    /// The coroutine `awaiting` was waiting on the first of several coroutines, and `winner` was
+    /// the one to end. The others were canceled.
+    FirstResolved { awaiting: u64, winner: u64 },
+}
+
+/// Every scheduling decision taken by the [`Executor`], tick by tick. Coroutines are identified
+/// by [`Id::to_bits`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recording {
+    pub ticks: Vec<Vec<ScheduleEvent>>,
+}
+
+pub(super) enum Recorder {
+    Recording {
+        recording: Recording,
+        wakes: HashMap<Id, WakeReason>,
+    },
+    Replaying {
+        recording: Recording,
+        tick: usize,
+        position: usize,
+    },
+}
+
+impl Recorder {
+    /// Returns false once the whole recording was replayed.
+    pub(super) fn start_tick(&mut self) -> bool {
+        match self {
+            Recorder::Recording { recording, .. } => {
+                recording.ticks.push(Vec::new());
+                true
+            }
+            Recorder::Replaying {
+                recording,
+                tick,
+                position,
+            } => {
+                *tick += 1;
+                *position = 0;
+                *tick <= recording.ticks.len()
+            }
+        }
+    }
+
+    pub(super) fn wake(&mut self, id: Id, reason: WakeReason) {
+        if let Recorder::Recording { wakes, .. } = self {
+            wakes.insert(id, reason);
+        }
+    }
+
+    pub(super) fn first_resolved(&mut self, awaiting: Id, winner: Id) {
+        if let Recorder::Recording { recording, .. } = self {
+            if let Some(tick) = recording.ticks.last_mut() {
+                tick.push(ScheduleEvent::FirstResolved {
+                    awaiting: awaiting.to_bits(),
+                    winner: winner.to_bits(),
+                });
+            }
+        }
+    }
+
+    /// Record that `id` is resumed. When replaying, returns `false` if it doesn't match the
+    /// recording.
+    pub(super) fn resume(&mut self, id: Id) -> bool {
+        match self {
+            Recorder::Recording { recording, wakes } => {
+                let reason = wakes.remove(&id).unwrap_or(WakeReason::Tick);
+                if let Some(tick) = recording.ticks.last_mut() {
+                    tick.push(ScheduleEvent::Resume {
+                        id: id.to_bits(),
+                        reason,
+                    });
+                }
+                true
+            }
+            Recorder::Replaying { .. } => {
+                let expected = self.expected();
+                if let Recorder::Replaying { position, .. } = self {
+                    *position += 1;
+                }
+                expected.map_or(true, |e| e == id.to_bits())
+            }
+        }
+    }
+
+    /// The id of the next coroutine to resume according to the replayed recording.
+    fn expected(&self) -> Option<u64> {
+        let Recorder::Replaying {
+            recording,
+            tick,
+            position,
+        } = self
+        else {
+            return None;
+        };
+        // The tick counter is incremented before the first tick
+        recording
+            .ticks
+            .get(tick.wrapping_sub(1))?
+            .iter()
+            .filter_map(|e| match e {
+                ScheduleEvent::Resume { id, .. } => Some(*id),
+                ScheduleEvent::FirstResolved { .. } => None,
+            })
+            .nth(*position)
+    }
+
+    /// Take the next coroutine to resume out of `ready`. When replaying, the recorded one is
+    /// picked if it is ready.
+    pub(super) fn pick<T>(&self, ready: &mut Vec<(Id, T)>) -> Option<(Id, T)> {
+        if let Some(expected) = self.expected() {
+            if let Some(i) = ready.iter().rposition(|(id, _)| id.to_bits() == expected) {
+                return Some(ready.remove(i));
+            }
+        }
+        ready.pop()
+    }
+}
+
+impl Executor {
+    /// Start recording every scheduling decision, until [`stop_recording`] is called. Stops any
+    /// ongoing replay.
+    ///
+    /// [`stop_recording`]: Executor::stop_recording
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder::Recording {
+            recording: Recording::default(),
+            wakes: HashMap::new(),
+        });
+    }
+
+    /// Stop recording, and returns what was recorded since [`start_recording`] was called.
+    ///
+    /// [`start_recording`]: Executor::start_recording
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        match self.recorder.take() {
+            Some(Recorder::Recording { recording, .. }) => Some(recording),
+            other => {
+                self.recorder = other;
+                None
+            }
+        }
+    }
+
+    /// Starting with the next tick, resume the coroutines in the order given by `recording`. The
+    /// executor should be in the same state as when the recording started, otherwise the
+    /// replay stops with a warning as soon as it diverges.
+    pub fn replay(&mut self, recording: Recording) {
+        self.recorder = Some(Recorder::Replaying {
+            recording,
+            tick: 0,
+            position: 0,
+        });
+    }
+
+    /// Returns true while a recording is being replayed.
+    pub fn is_replaying(&self) -> bool {
+        matches!(self.recorder, Some(Recorder::Replaying { .. }))
+    }
+
+    /// Called right before `id` is resumed.
+    pub(super) fn record_resume(&mut self, id: Id) {
+        if let Some(recorder) = &mut self.recorder {
+            if !recorder.resume(id) {
+                warn!("The replayed recording diverged, coroutines are now resumed in the default order");
+                self.recorder = None;
+            }
+        }
+    }
+}
+
+/// Notify the recorder, if any, that `id` was woken up.
+pub(super) fn wake(recorder: &mut Option<Recorder>, id: Id, reason: WakeReason) {
+    if let Some(recorder) = recorder {
+        recorder.wake(id, reason);
+    }
+}
@@ -0,0 +1,173 @@
+//! Saving which coroutines are running, to start them again when a game is loaded. A suspended
+//! coroutine cannot be serialized, so only a structured subset of its state is saved: the key it
+//! was started with, its owner, the time left on its timer, and the [`Checkpoints`] of the
+//! quests. On load, each coroutine is started again by the factory registered under its key.
+//!
+//! ```ignore
+//! let factories = CoroFactories::new()
+//!     .register("patrol", |_| boxed_coroutine(patrol))
+//!     .register("bomb", |saved| {
+//!         let fuse = saved.remaining.unwrap_or(Duration::from_secs(5));
+//!         boxed_coroutine(move |mut s: Scope| async move {
+//!             s.duration(fuse).await;
+//!             explode(&mut s);
+//!         })
+//!     });
+//!
+//! executor.build(bomb).name("bomb").owner(bomb_entity).spawn(world);
+//!
+//! let state = executor.save_state(world, &factories);
+//! // ...
+//! executor.restore_state(world, &state, &factories);
+//! ```
+
+use std::time::Duration;
+
+use bevy::{
+    prelude::{Entity, World},
+    reflect::Reflect,
+    utils::HashMap,
+};
+
+use crate::{function_coroutine::BoxedCoroutine, id_alloc::Id, quest::Checkpoints};
+
+use super::Executor;
+
+type Factory = Box<dyn Fn(&SavedCoroutine) -> BoxedCoroutine + Send + Sync>;
+
+/// The coroutines which can be saved, each identified by the key (the name) it was started with.
+#[derive(Default)]
+pub struct CoroFactories {
+    factories: HashMap<&'static str, Factory>,
+}
+
+impl CoroFactories {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Save the coroutines started with the name `key`, and start them again on load with the
+    /// coroutine returned by `factory`.
+    pub fn register(
+        mut self,
+        key: &'static str,
+        factory: impl Fn(&SavedCoroutine) -> BoxedCoroutine + Send + Sync + 'static,
+    ) -> Self {
+        self.factories.insert(key, Box::new(factory));
+        self
+    }
+}
+
+/// What is saved of a coroutine.
+#[derive(Reflect, Clone, Debug, PartialEq)]
+pub struct SavedCoroutine {
+    pub key: String,
+    /// The entity owning the coroutine. When loading a scene, it must be mapped to the new
+    /// entity before restoring the coroutine.
+    pub owner: Option<Entity>,
+    /// The time left before the coroutine is woken up, if it was waiting on a duration.
+    pub remaining: Option<Duration>,
+    pub paused: bool,
+}
+
+/// The orchestration state of an [`Executor`], see [`Executor::save_state`].
+#[derive(Reflect, Clone, Debug, Default, PartialEq)]
+pub struct ExecutorState {
+    pub coroutines: Vec<SavedCoroutine>,
+    pub checkpoints: Checkpoints,
+    /// The clock of the executor, which the deadlines of the coroutines and the [`Cooldowns`] are
+    /// measured against.
+    ///
+    /// [`Cooldowns`]: crate::ability::Cooldowns
+    pub elapsed: Duration,
+}
+
+impl Executor {
+    /// Save the coroutines whose name is registered in `factories`, in the order they were
+    /// started. Coroutines started by another coroutine are not saved, since their parent starts
+    /// them again.
+    pub fn save_state(&mut self, world: &World, factories: &CoroFactories) -> ExecutorState {
+        let keyed: Vec<_> = self
+            .coroutines
+            .iter_mut()
+            .map(|(id, coro)| {
+                let meta = coro.get().meta();
+                (*id, meta.name, meta.owner)
+            })
+            .collect();
+
+        let mut coroutines: Vec<_> = keyed
+            .into_iter()
+            .filter(|(id, key, _)| {
+                factories.factories.contains_key(key) && !self.owned_by.contains_key(id)
+            })
+            .map(|(id, key, owner)| {
+                let saved = SavedCoroutine {
+                    key: key.to_owned(),
+                    owner,
+                    remaining: self.remaining(id),
+                    paused: self.is_paused(id),
+                };
+                (self.started_at.get(&id).copied(), id.to_bits(), saved)
+            })
+            .collect();
+        coroutines.sort_by_key(|(started_at, id, _)| (*started_at, *id));
+
+        ExecutorState {
+            coroutines: coroutines.into_iter().map(|(_, _, saved)| saved).collect(),
+            checkpoints: world
+                .get_resource::<Checkpoints>()
+                .cloned()
+                .unwrap_or_default(),
+            elapsed: self.elapsed,
+        }
+    }
+
+    /// Start again the coroutines of `state`, with the factories registered under their key, and
+    /// restore the [`Checkpoints`] resource and the clock of the executor. Coroutines whose key
+    /// isn't registered, or whose owner doesn't exist, are skipped. Returns the ids of the
+    /// coroutines started.
+    ///
+    /// The coroutines start from their beginning, this is not a rollback of their progress. Since
+    /// the clock is set back, the state should be restored into a new executor: the deadlines of
+    /// the coroutines already running would be shifted.
+    pub fn restore_state(
+        &mut self,
+        world: &mut World,
+        state: &ExecutorState,
+        factories: &CoroFactories,
+    ) -> Vec<Id> {
+        world.insert_resource(state.checkpoints.clone());
+        self.elapsed = state.elapsed;
+
+        let mut started = Vec::new();
+        for saved in &state.coroutines {
+            let Some((key, factory)) = factories.factories.get_key_value(saved.key.as_str()) else {
+                continue;
+            };
+            if saved
+                .owner
+                .is_some_and(|owner| world.get_entity(owner).is_none())
+            {
+                continue;
+            }
+
+            let mut builder = self.build(factory(saved)).name(key);
+            if let Some(owner) = saved.owner {
+                builder = builder.owner(owner);
+            }
+            if saved.paused {
+                builder = builder.paused();
+            }
+            started.extend(builder.spawn(world));
+        }
+        started
+    }
+
+    /// The time left before `id` is woken up, if it waits on a duration.
+    fn remaining(&self, id: Id) -> Option<Duration> {
+        let deadline = self.waiting_on_time.get(&id)?;
+        let now = self.paused.get(&id).copied().unwrap_or(self.elapsed);
+        Some(deadline.saturating_sub(now))
+    }
+}
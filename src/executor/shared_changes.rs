@@ -0,0 +1,125 @@
+//! Changes made by the coroutines of an executor, seen by the other executors of the same world.
+//!
+//! A mutation through a coroutine parameter only wakes the coroutines of the executor it was made
+//! from. When several executors run in different schedules (one in `Update` and one in
+//! `PostUpdate` for instance), each of them also publishes its changes in a log stored in the
+//! world, and the others read it on their next tick, from where they stopped the previous time.
+//! Executors never consume changes meant for another one, and don't miss the changes made
+//! between two of their ticks.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+};
+
+use bevy::{
+    prelude::{Resource, World},
+    utils::HashSet,
+};
+
+use super::{msg::SignalId, Executor};
+
+static NEXT_KEY: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Resource, Default)]
+pub(crate) struct SharedChanges {
+    /// The position of the first entry of `log`.
+    start: u64,
+    /// Each change, with the key of the executor it comes from.
+    log: VecDeque<(usize, SignalId)>,
+    readers: Vec<Reader>,
+}
+
+struct Reader {
+    key: usize,
+    /// Dead once the executor is dropped, so it stops holding the log back.
+    alive: Weak<()>,
+    /// The position in the log up to which this executor has read.
+    cursor: u64,
+}
+
+/// How an [`Executor`] identifies itself in [`SharedChanges`].
+pub(super) struct ChangeReader {
+    key: usize,
+    alive: Arc<()>,
+}
+
+impl Default for ChangeReader {
+    fn default() -> Self {
+        Self {
+            key: NEXT_KEY.fetch_add(1, Ordering::Relaxed),
+            alive: Arc::new(()),
+        }
+    }
+}
+
+impl SharedChanges {
+    fn end(&self) -> u64 {
+        self.start + self.log.len() as u64
+    }
+
+    /// Drop the entries every executor has read.
+    fn trim(&mut self) {
+        self.readers.retain(|r| r.alive.strong_count() > 0);
+        let read = self.readers.iter().map(|r| r.cursor).min();
+        let read = read.unwrap_or(self.end());
+        while self.start < read {
+            self.log.pop_front();
+            self.start += 1;
+        }
+    }
+}
+
+impl Executor {
+    /// Returns the changes made by other executors since the last tick of this one, in the order
+    /// they were published. Each signal is only returned once, even if it was emitted several
+    /// times.
+    pub(super) fn receive_shared_changes(&mut self, world: &mut World) -> Vec<SignalId> {
+        let reader = &self.change_reader;
+        let mut shared = world.get_resource_or_insert_with(SharedChanges::default);
+        let end = shared.end();
+
+        let Some(index) = shared.readers.iter().position(|r| r.key == reader.key) else {
+            // Changes made before this executor existed are not its concern
+            shared.readers.push(Reader {
+                key: reader.key,
+                alive: Arc::downgrade(&reader.alive),
+                cursor: end,
+            });
+            return Vec::new();
+        };
+
+        let cursor = std::mem::replace(&mut shared.readers[index].cursor, end);
+        let mut seen = HashSet::new();
+        let changes = shared
+            .log
+            .range((cursor - shared.start) as usize..)
+            .filter(|(key, signal)| *key != reader.key && seen.insert(*signal))
+            .map(|(_, signal)| *signal)
+            .collect();
+        shared.trim();
+        changes
+    }
+
+    /// Publish the changes made during this tick, if there are other executors to see them.
+    pub(super) fn publish_changes(&mut self, world: &mut World) {
+        let key = self.change_reader.key;
+        let emitted = self.emitted.drain();
+        let Some(mut shared) = world.get_resource_mut::<SharedChanges>() else {
+            return;
+        };
+        if shared.readers.len() < 2 {
+            return;
+        }
+
+        shared.log.extend(emitted.map(|signal| (key, signal)));
+        // This executor has seen its own changes
+        let end = shared.end();
+        if let Some(reader) = shared.readers.iter_mut().find(|r| r.key == key) {
+            reader.cursor = end;
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::id_alloc::Id;
+
+/// A storage indexed by generational [`Id`]s. Since ids are recycled by the [`Ids`] allocator,
+/// the storage stays dense, and finding a coroutine doesn't require hashing its id.
+///
+/// [`Ids`]: crate::id_alloc::Ids
+pub(crate) struct Slab<T> {
+    entries: Vec<Option<(Id, T)>>,
+    len: usize,
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Slab<T> {
+    /// Insert `value` at `id`, and returns the previous value stored for this exact id.
+    pub fn insert(&mut self, id: Id, value: T) -> Option<T> {
+        let index = id.index() as usize;
+        if index >= self.entries.len() {
+            self.entries.resize_with(index + 1, || None);
+        }
+
+        match self.entries[index].replace((id, value)) {
+            Some((prev_id, prev)) if prev_id == id => Some(prev),
+            // A stale value from a previous generation
+            Some(_) => None,
+            None => {
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// Make room for `additional` more values.
+    pub fn reserve(&mut self, additional: usize) {
+        let free = self.entries.len() - self.len;
+        self.entries.reserve(additional.saturating_sub(free));
+    }
+
+    pub fn get_mut(&mut self, id: &Id) -> Option<&mut T> {
+        match self.entries.get_mut(id.index() as usize)? {
+            Some((entry_id, value)) if entry_id == id => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains_key(&self, id: &Id) -> bool {
+        matches!(
+            self.entries.get(id.index() as usize),
+            Some(Some((entry_id, _))) if entry_id == id
+        )
+    }
+
+    pub fn remove(&mut self, id: &Id) -> Option<T> {
+        let entry = self.entries.get_mut(id.index() as usize)?;
+        match entry {
+            Some((entry_id, _)) if entry_id == id => {
+                self.len -= 1;
+                entry.take().map(|(_, value)| value)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Id, &mut T)> {
+        self.entries
+            .iter_mut()
+            .filter_map(|entry| entry.as_mut().map(|(id, value)| (&*id, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generations_are_checked() {
+        let mut slab = Slab::default();
+        let old = Id::from_bits(3);
+        let new = Id::from_bits(1 << 32 | 3);
+
+        assert_eq!(slab.insert(old, 'a'), None);
+        assert_eq!(slab.get_mut(&new), None);
+        assert_eq!(slab.remove(&new), None);
+        assert!(slab.contains_key(&old));
+
+        assert_eq!(slab.remove(&old), Some('a'));
+        assert!(slab.is_empty());
+        assert_eq!(slab.insert(new, 'b'), None);
+        assert!(!slab.contains_key(&old));
+        assert_eq!(slab.iter_mut().count(), 1);
+    }
+}
@@ -0,0 +1,32 @@
+//! Tagging coroutines, to find and cancel them together. See
+//! [`CoroBuilder::tag`](super::builder::CoroBuilder::tag).
+
+use crate::id_alloc::Id;
+
+use super::Executor;
+
+/// A tag given to coroutines, such as `"ai"` or `"ui"`.
+pub type CoroTag = &'static str;
+
+impl Executor {
+    /// Returns the tags of the coroutine `id`.
+    pub fn tags(&self, id: Id) -> &[CoroTag] {
+        self.tags.get(&id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the coroutines tagged with `tag`, in no particular order.
+    pub fn tagged(&self, tag: CoroTag) -> impl Iterator<Item = Id> + '_ {
+        self.tags
+            .iter()
+            .filter(move |(_, tags)| tags.contains(&tag))
+            .map(|(id, _)| *id)
+    }
+
+    /// Cancel all the coroutines tagged with `tag`, and everything they started.
+    pub fn cancel_tagged(&mut self, tag: CoroTag) {
+        let ids: Vec<Id> = self.tagged(tag).collect();
+        for id in ids {
+            self.cancel_coroutine(id);
+        }
+    }
+}
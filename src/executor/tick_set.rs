@@ -0,0 +1,77 @@
+use crate::id_alloc::Id;
+
+/// The coroutines waiting on the next tick, as a bitset over the index of their [`Id`]. Waking
+/// them all at the start of a tick skips over 64 indices at a time, and checking if a coroutine
+/// is already waiting doesn't require a search.
+#[derive(Default)]
+pub(crate) struct TickSet {
+    words: Vec<u64>,
+    /// The full id stored at each set index, to tell generations apart.
+    ids: Vec<u64>,
+}
+
+impl TickSet {
+    /// Add `id` to the set. Returns false if it was already present.
+    pub fn insert(&mut self, id: Id) -> bool {
+        let index = id.index() as usize;
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+            self.ids.resize(self.words.len() * 64, 0);
+        }
+
+        let present = self.words[word] & bit != 0 && self.ids[index] == id.to_bits();
+        self.words[word] |= bit;
+        self.ids[index] = id.to_bits();
+        !present
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, id: Id) -> bool {
+        let index = id.index() as usize;
+        match self.words.get(index / 64) {
+            Some(word) => word & (1 << (index % 64)) != 0 && self.ids[index] == id.to_bits(),
+            None => false,
+        }
+    }
+
+    /// Remove all the ids from the set, in the order of their index.
+    pub fn drain(&mut self) -> impl Iterator<Item = Id> + '_ {
+        let ids = &self.ids;
+        self.words
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, word)| **word != 0)
+            .flat_map(move |(w, word)| {
+                let mut bits = std::mem::take(word);
+                std::iter::from_fn(move || {
+                    if bits == 0 {
+                        return None;
+                    }
+                    let bit = bits.trailing_zeros() as usize;
+                    bits &= bits - 1;
+                    Some(Id::from_bits(ids[w * 64 + bit]))
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draining_in_index_order() {
+        let mut set = TickSet::default();
+        let a = Id::from_bits(130);
+        let b = Id::from_bits(1 << 32 | 2);
+
+        assert!(set.insert(a));
+        assert!(set.insert(b));
+        assert!(!set.insert(a));
+        assert!(!set.contains(Id::from_bits(2)));
+
+        assert_eq!(set.drain().collect::<Vec<_>>(), vec![b, a]);
+        assert!(!set.contains(a));
+    }
+}
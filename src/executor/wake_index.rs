@@ -0,0 +1,125 @@
+//! The coroutines waiting on events or on a channel, so that they are only resumed once there is
+//! something for them to read, instead of checking again on every tick.
+
+use bevy::{ecs::component::ComponentId, prelude::World, utils::HashMap};
+
+use crate::{function_coroutine::await_recv::ChannelProbe, id_alloc::Id};
+
+use super::msg::EventWait;
+
+/// The waiters of a single type of events.
+struct EventType {
+    /// Returns the number of events sent so far.
+    sent: fn(&World) -> Option<usize>,
+    /// The number of events each waiter has already read.
+    waiters: HashMap<Id, usize>,
+}
+
+/// The coroutines waiting on events, by type of events. Each tick, the number of events sent is
+/// read once per type, and only the coroutines which haven't read all of them yet are woken up.
+#[derive(Default)]
+pub(super) struct EventWaiters {
+    by_type: HashMap<ComponentId, EventType>,
+    by_coroutine: HashMap<Id, ComponentId>,
+}
+
+impl EventWaiters {
+    pub fn insert(&mut self, wait: EventWait, id: Id) {
+        self.forget(id);
+        self.by_type
+            .entry(wait.events)
+            .or_insert_with(|| EventType {
+                sent: wait.sent,
+                waiters: HashMap::default(),
+            })
+            .waiters
+            .insert(id, wait.seen);
+        self.by_coroutine.insert(id, wait.events);
+    }
+
+    /// Remove and return the coroutines for which events were sent since they last read them.
+    pub fn wake(&mut self, world: &World) -> Vec<Id> {
+        let mut woken = Vec::new();
+        self.by_type.retain(|_, events| {
+            // The resource was removed, the waiters are kept in case it comes back
+            let Some(sent) = (events.sent)(world) else {
+                return true;
+            };
+            events.waiters.retain(|id, seen| {
+                let ready = *seen < sent;
+                if ready {
+                    woken.push(*id);
+                }
+                !ready
+            });
+            !events.waiters.is_empty()
+        });
+        for id in &woken {
+            self.by_coroutine.remove(id);
+        }
+        // In the order of their index, like the coroutines woken by a tick
+        woken.sort_unstable_by_key(|id| id.index());
+        woken
+    }
+
+    /// Stop `id` from waiting on events, if it was.
+    pub fn forget(&mut self, id: Id) {
+        if let Some(events) = self.by_coroutine.remove(&id) {
+            if let Some(waiters) = self.by_type.get_mut(&events) {
+                waiters.waiters.remove(&id);
+                if waiters.waiters.is_empty() {
+                    self.by_type.remove(&events);
+                }
+            }
+        }
+    }
+
+    pub fn contains(&self, id: Id) -> bool {
+        self.by_coroutine.contains_key(&id)
+    }
+
+    /// The coroutines waiting on events, along with the resource id of their [`Events`].
+    ///
+    /// [`Events`]: bevy::ecs::event::Events
+    pub fn iter(&self) -> impl Iterator<Item = (Id, ComponentId)> + '_ {
+        self.by_coroutine.iter().map(|(id, events)| (*id, *events))
+    }
+}
+
+/// The coroutines waiting to receive from a channel, along with a probe telling whether they can.
+#[derive(Default)]
+pub(super) struct ChannelWaiters {
+    probes: HashMap<Id, ChannelProbe>,
+}
+
+impl ChannelWaiters {
+    pub fn insert(&mut self, probe: ChannelProbe, id: Id) {
+        self.probes.insert(id, probe);
+    }
+
+    /// Remove and return the coroutines whose channel has a value, or was disconnected.
+    pub fn wake(&mut self) -> Vec<Id> {
+        let mut woken = Vec::new();
+        self.probes.retain(|id, probe| {
+            let ready = probe();
+            if ready {
+                woken.push(*id);
+            }
+            !ready
+        });
+        woken.sort_unstable_by_key(|id| id.index());
+        woken
+    }
+
+    pub fn forget(&mut self, id: Id) {
+        self.probes.remove(&id);
+    }
+
+    pub fn contains(&self, id: Id) -> bool {
+        self.probes.contains_key(&id)
+    }
+
+    pub fn coroutines(&self) -> impl Iterator<Item = Id> + '_ {
+        self.probes.keys().copied()
+    }
+}
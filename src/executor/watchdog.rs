@@ -0,0 +1,148 @@
+//! An optional watchdog reporting coroutines which haven't been resumed for a long time, see
+//! [`Executor::set_watchdog`].
+
+use std::{panic::Location, time::Duration};
+
+use bevy::{log::warn, utils::HashMap};
+use tinyset::SetU64;
+
+use crate::id_alloc::Id;
+
+use super::Executor;
+
+/// After how long a coroutine which wasn't resumed is considered stalled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StallThreshold {
+    Ticks(u64),
+    Duration(Duration),
+}
+
+/// A coroutine reported by the watchdog.
+#[derive(Clone, Debug)]
+pub struct Stall {
+    pub id: Id,
+    pub name: &'static str,
+    /// Where the coroutine is suspended, if known.
+    pub location: Option<&'static Location<'static>>,
+    /// The time elapsed since the coroutine was last resumed.
+    pub duration: Duration,
+    /// The number of ticks since the coroutine was last resumed.
+    pub ticks: u64,
+}
+
+type StallHook = Box<dyn FnMut(&Stall) + Send + Sync>;
+
+pub(super) struct Watchdog {
+    threshold: StallThreshold,
+    hook: Option<StallHook>,
+    ticks: u64,
+    last_resumed: HashMap<Id, (u64, Duration)>,
+    reported: SetU64,
+}
+
+impl Watchdog {
+    /// Called each time `id` is resumed.
+    pub(super) fn resumed(&mut self, id: Id, now: Duration) {
+        self.last_resumed.insert(id, (self.ticks, now));
+        self.reported.remove(id.to_bits());
+    }
+
+    /// Called when `id` is done or canceled.
+    pub(super) fn forget(&mut self, id: Id) {
+        self.last_resumed.remove(&id);
+        self.reported.remove(id.to_bits());
+    }
+}
+
+impl Executor {
+    /// Log a warning each time a coroutine waiting on a signal hasn't been resumed for longer
+    /// than `threshold`. Coroutines waiting on a duration, or on other coroutines, are not
+    /// reported.
+    pub fn set_watchdog(&mut self, threshold: StallThreshold) {
+        self.enable_watchdog(threshold, None);
+    }
+
+    /// Like [`set_watchdog`](Executor::set_watchdog), but calls `hook` instead of logging a
+    /// warning.
+    pub fn set_watchdog_with(
+        &mut self,
+        threshold: StallThreshold,
+        hook: impl FnMut(&Stall) + Send + Sync + 'static,
+    ) {
+        self.enable_watchdog(threshold, Some(Box::new(hook)));
+    }
+
+    pub fn disable_watchdog(&mut self) {
+        self.watchdog = None;
+    }
+
+    fn enable_watchdog(&mut self, threshold: StallThreshold, hook: Option<StallHook>) {
+        self.watchdog = Some(Watchdog {
+            threshold,
+            hook,
+            ticks: 0,
+            last_resumed: HashMap::new(),
+            reported: SetU64::new(),
+        });
+    }
+
+    /// Report the coroutines which stalled since the last check.
+    pub(super) fn check_stalls(&mut self) {
+        let Some(watchdog) = &mut self.watchdog else {
+            return;
+        };
+        watchdog.ticks += 1;
+
+        let waiters = self
+            .waiting_on_signal
+            .coroutines()
+            .chain(self.waiting_on_events.iter().map(|(id, _)| id))
+            .chain(self.waiting_on_channel.coroutines());
+        for id in waiters {
+            let waiter = id.to_bits();
+            if watchdog.reported.contains(waiter) {
+                continue;
+            }
+            let Some(coro) = self.coroutines.get_mut(&id) else {
+                continue;
+            };
+
+            // Coroutines are only tracked once the watchdog is enabled
+            let (tick, time) = *watchdog
+                .last_resumed
+                .entry(id)
+                .or_insert((watchdog.ticks, self.elapsed));
+            let ticks = watchdog.ticks - tick;
+            let duration = self.elapsed - time;
+            let stalled = match watchdog.threshold {
+                StallThreshold::Ticks(max) => ticks > max,
+                StallThreshold::Duration(max) => duration > max,
+            };
+            if !stalled {
+                continue;
+            }
+
+            watchdog.reported.insert(waiter);
+            let coro = coro.get();
+            let stall = Stall {
+                id,
+                name: coro.meta().name,
+                location: coro.suspended_at(),
+                duration,
+                ticks,
+            };
+            match &mut watchdog.hook {
+                Some(hook) => hook(&stall),
+                None => warn!(
+                    "The coroutine {} wasn't resumed for {:.1}s ({} ticks){}",
+                    stall.name,
+                    stall.duration.as_secs_f32(),
+                    stall.ticks,
+                    stall
+                        .location
+                        .map_or(String::new(), |l| format!(", it is suspended at {l}"))
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::{DetectChangesMut, Resource, World};
+
+use crate::executor::msg::SignalId;
+
+/// A source of wake ups living outside of the ECS, such as websockets, OS events or editors.
+///
+/// An external signal is stored as a [`Resource`], and must be registered with
+/// [`Executor::register_external_signal`](crate::executor::Executor::register_external_signal).
+/// It is polled at the beginning of each tick, before any coroutine is resumed. Each time it is
+/// [`Triggered::All`], all the coroutines awaiting it (via
+/// [`Scope::on_external`](crate::prelude::Scope::on_external)) are resumed, and each time it is
+/// [`Triggered::Key`], only the ones awaiting that key (via
+/// [`Scope::on_external_key`](crate::prelude::Scope::on_external_key)) are.
+///
+/// ```ignore
+/// #[derive(Resource)]
+/// struct OnMessage(Arc<AtomicBool>);
+///
+/// impl ExternalSignal for OnMessage {
+///     fn poll(&mut self) -> Option<Triggered> {
+///         self.0.swap(false, Ordering::Relaxed).then_some(Triggered::All)
+///     }
+/// }
+///
+/// /// The responses to requests, each awaited by the coroutine which sent it.
+/// #[derive(Resource)]
+/// struct OnResponse(Receiver<u64>);
+///
+/// impl ExternalSignal for OnResponse {
+///     fn poll(&mut self) -> Option<Triggered> {
+///         self.0.try_recv().ok().map(Triggered::Key)
+///     }
+/// }
+/// ```
+pub trait ExternalSignal: Resource {
+    /// Returns how the signal was triggered since the last time it was polled. It is polled
+    /// again until it returns `None`, so that several keys can be triggered on the same tick.
+    fn poll(&mut self) -> Option<Triggered>;
+}
+
+/// How an [`ExternalSignal`] was triggered, see [`ExternalSignal::poll`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Triggered {
+    /// Wake the coroutines awaiting the signal itself.
+    All,
+    /// Wake the coroutines awaiting this key of the signal.
+    Key(u64),
+}
+
+/// Poll the external signal `S`, and returns the ids of the signals it triggered.
+pub(crate) fn poll_external<S: ExternalSignal>(world: &mut World) -> Vec<SignalId> {
+    let Some(signal_type) = world.components().resource_id::<S>() else {
+        return Vec::new();
+    };
+    let Some(mut signal) = world.get_resource_mut::<S>() else {
+        return Vec::new();
+    };
+
+    let signal = signal.bypass_change_detection();
+    std::iter::from_fn(|| signal.poll())
+        .map(|triggered| SignalId {
+            signal_type,
+            owner: None,
+            external: true,
+            key: match triggered {
+                Triggered::All => None,
+                Triggered::Key(key) => Some(key),
+            },
+        })
+        .collect()
+}
@@ -0,0 +1,92 @@
+//! Fade in and out of sprites, UI nodes and texts.
+
+use bevy::prelude::{BackgroundColor, Component, Entity, Sprite, Text, TextureAtlasSprite};
+
+use crate::{
+    function_coroutine::{await_time::IntoDuration, scope::Scope},
+    tween::Ease,
+};
+
+/// A component with an alpha channel, which can be faded with [`Scope::fade_to`].
+pub trait Fade: Component {
+    /// Returns the current alpha.
+    fn alpha(&self) -> f32;
+
+    /// Set the alpha.
+    fn set_alpha(&mut self, alpha: f32);
+}
+
+impl Fade for Sprite {
+    fn alpha(&self) -> f32 {
+        self.color.a()
+    }
+
+    fn set_alpha(&mut self, alpha: f32) {
+        self.color.set_a(alpha);
+    }
+}
+
+impl Fade for TextureAtlasSprite {
+    fn alpha(&self) -> f32 {
+        self.color.a()
+    }
+
+    fn set_alpha(&mut self, alpha: f32) {
+        self.color.set_a(alpha);
+    }
+}
+
+impl Fade for BackgroundColor {
+    fn alpha(&self) -> f32 {
+        self.0.a()
+    }
+
+    fn set_alpha(&mut self, alpha: f32) {
+        self.0.set_a(alpha);
+    }
+}
+
+/// The alpha of a [`Text`] is the one of its first section, but all sections are faded.
+impl Fade for Text {
+    fn alpha(&self) -> f32 {
+        self.sections.first().map_or(1.0, |s| s.style.color.a())
+    }
+
+    fn set_alpha(&mut self, alpha: f32) {
+        for section in &mut self.sections {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+impl Scope {
+    /// Fade the component `C` of `entity` from its current alpha to `alpha` over `duration`.
+    /// Returns `false` if the entity or its component was removed before the end.
+    pub async fn fade_to<C: Fade>(
+        &mut self,
+        entity: Entity,
+        alpha: f32,
+        duration: impl IntoDuration,
+        ease: Ease,
+    ) -> bool {
+        let duration = duration.into_duration();
+        let Some(start) = self.world().get::<C>(entity).map(C::alpha) else {
+            return false;
+        };
+
+        self.tween(entity, duration, ease, |c: &mut C, k| {
+            c.set_alpha(start + (alpha - start) * k)
+        })
+        .await
+    }
+
+    /// Fade the component `C` of `entity` to an alpha of 1 over `duration`.
+    pub async fn fade_in<C: Fade>(&mut self, entity: Entity, duration: impl IntoDuration) -> bool {
+        self.fade_to::<C>(entity, 1.0, duration, Ease::Linear).await
+    }
+
+    /// Fade the component `C` of `entity` to an alpha of 0 over `duration`.
+    pub async fn fade_out<C: Fade>(&mut self, entity: Entity, duration: impl IntoDuration) -> bool {
+        self.fade_to::<C>(entity, 0.0, duration, Ease::Linear).await
+    }
+}
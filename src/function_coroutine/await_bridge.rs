@@ -0,0 +1,47 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project::pin_project;
+
+use crate::MaybeSend;
+
+use super::{CoroStatus, Scope};
+
+/// A future which is not part of this library, driven by the [`Executor`]. Created with
+/// [`Scope::bridge`].
+///
+/// The inner future is polled once per tick: since the executor does not rely on wakers, a wake
+/// up simply means the future gets polled again on the next tick.
+///
+/// [`Executor`]: crate::executor::Executor
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+pub struct Bridge<'a, F> {
+    scope: &'a mut Scope,
+    #[pin]
+    future: F,
+}
+
+impl<'a, F: Future + MaybeSend> Bridge<'a, F> {
+    pub(crate) fn new(scope: &'a mut Scope, future: F) -> Self {
+        Self { scope, future }
+    }
+}
+
+impl<F: Future + MaybeSend> Future for Bridge<'_, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.project();
+        match this.future.poll(cx) {
+            Poll::Ready(value) => Poll::Ready(value),
+            Poll::Pending => {
+                this.scope.yield_(CoroStatus::Tick);
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -4,7 +4,10 @@ use std::{
     task::{Context, Poll},
 };
 
-use crate::executor::msg::{CoroStatus, SignalId};
+use crate::executor::{
+    msg::{CoroStatus, SignalId},
+    DespawnPolicy,
+};
 
 use super::{scope::Scope, CoroState};
 
@@ -13,6 +16,7 @@ pub struct AwaitChange<'a> {
     scope: &'a mut Scope,
     id: SignalId,
     state: CoroState,
+    despawn_policy: Option<DespawnPolicy>,
 }
 
 impl<'a> AwaitChange<'a> {
@@ -21,8 +25,21 @@ impl<'a> AwaitChange<'a> {
             scope,
             id,
             state: CoroState::Running,
+            despawn_policy: None,
         }
     }
+
+    /// Choose what happens if the entity is despawned during this wait only, instead of
+    /// following the policy of the coroutine:
+    ///
+    /// ```ignore
+    /// // Wake up to play the death animation
+    /// on_hp_change.observe(&mut s).on_despawn(DespawnPolicy::Resume).await;
+    /// ```
+    pub fn on_despawn(mut self, policy: DespawnPolicy) -> Self {
+        self.despawn_policy = Some(policy);
+        self
+    }
 }
 
 impl<'a> Future for AwaitChange<'a> {
@@ -35,6 +52,9 @@ impl<'a> Future for AwaitChange<'a> {
                 self.state = CoroState::Halted;
 
                 let id = self.id;
+                if let Some(policy) = self.despawn_policy {
+                    self.scope.set_wait_despawn_policy(policy);
+                }
                 self.scope.yield_(CoroStatus::Signal(id));
                 Poll::Pending
             }
@@ -0,0 +1,48 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bevy::prelude::World;
+use pin_project::pin_project;
+
+use super::{CoroStatus, Scope};
+
+/// A future that checks a condition on the [`World`] each time the coroutine is resumed, and
+/// resolve as soon as it returns `Some`. Created with [`Scope::until`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+pub struct AwaitCondition<'a, F> {
+    scope: &'a mut Scope,
+    condition: F,
+}
+
+impl<'a, F, T> AwaitCondition<'a, F>
+where
+    F: FnMut(&World) -> Option<T>,
+{
+    pub(crate) fn new(scope: &'a mut Scope, condition: F) -> Self {
+        Self { scope, condition }
+    }
+}
+
+impl<F, T> Future for AwaitCondition<'_, F>
+where
+    F: FnMut(&World) -> Option<T>,
+{
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let result = (this.condition)(&this.scope.world());
+        match result {
+            Some(value) => Poll::Ready(value),
+            None => {
+                this.scope.yield_(CoroStatus::Tick);
+                Poll::Pending
+            }
+        }
+    }
+}
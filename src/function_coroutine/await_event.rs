@@ -0,0 +1,99 @@
+use std::{
+    any::{Any, TypeId},
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bevy::{
+    ecs::event::{Event, Events, ManualEventReader},
+    utils::HashMap,
+};
+use pin_project::pin_project;
+
+use crate::{executor::msg::EventWait, expect_resource};
+
+use super::{CoroStatus, Scope};
+
+/// The event cursors of a [`Scope`], one per event type. They are kept between two waits, so
+/// that the events sent in the meantime aren't missed.
+#[derive(Default)]
+pub(crate) struct EventCursors {
+    cursors: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl EventCursors {
+    fn take<E: Event>(&mut self) -> Option<ManualEventReader<E>> {
+        let cursor = self.cursors.remove(&TypeId::of::<E>())?;
+        cursor.downcast().ok().map(|cursor| *cursor)
+    }
+
+    fn put<E: Event>(&mut self, cursor: ManualEventReader<E>) {
+        self.cursors.insert(TypeId::of::<E>(), Box::new(cursor));
+    }
+}
+
+/// How a [`NextEvent`] reads the events `E`, returning `T` once it found what it waits for.
+pub trait ReadEvents<E: Event, T>:
+    FnMut(&mut ManualEventReader<E>, &Events<E>) -> Option<T>
+{
+}
+
+impl<E: Event, T, F> ReadEvents<E, T> for F where
+    F: FnMut(&mut ManualEventReader<E>, &Events<E>) -> Option<T>
+{
+}
+
+/// A future that resolve once events `E` were sent, with what `read` returns from them. Created
+/// with [`Scope::next_event`], [`Scope::next_events`], and the helpers waiting on a specific
+/// event. The coroutine is only resumed once new events `E` were sent, the executor counts them
+/// for all the coroutines waiting on `E` at once.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+#[pin_project]
+pub struct NextEvent<'a, E: Event, F> {
+    scope: &'a mut Scope,
+    read: F,
+    _phantom: PhantomData<E>,
+}
+
+impl<'a, E: Event, F> NextEvent<'a, E, F> {
+    pub(crate) fn new(scope: &'a mut Scope, read: F) -> Self {
+        Self {
+            scope,
+            read,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<E: Event, T, F> Future for NextEvent<'_, E, F>
+where
+    F: FnMut(&mut ManualEventReader<E>, &Events<E>) -> Option<T>,
+{
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.project();
+
+        let cursor = this.scope.events.take::<E>();
+        let (read, cursor, wait) = {
+            let world = this.scope.world();
+            let events = expect_resource::<Events<E>>(&world);
+            let mut cursor = cursor.unwrap_or_else(|| events.get_reader_current());
+            let read = (this.read)(&mut cursor, events);
+            let wait = EventWait::new::<E>(&world, cursor.len(events));
+            (read, cursor, wait)
+        };
+        this.scope.events.put(cursor);
+
+        match read {
+            Some(value) => Poll::Ready(value),
+            None => {
+                let status = wait.map_or(CoroStatus::Tick, CoroStatus::Event);
+                this.scope.yield_(status);
+                Poll::Pending
+            }
+        }
+    }
+}
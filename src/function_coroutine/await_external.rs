@@ -0,0 +1,43 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use oneshot::TryRecvError;
+
+/// The result of a future running on an external runtime (such as tokio or async-std). It must be
+/// awaited via [`Scope::bridge`](super::scope::Scope::bridge), which is what the `spawn_*`
+/// methods of [`Scope`](super::scope::Scope) do.
+///
+/// Resolves to `None` if the task was dropped by the runtime before completion.
+pub struct ExternalTask<T> {
+    receiver: oneshot::Receiver<T>,
+}
+
+impl<T> Future for ExternalTask<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.receiver.try_recv() {
+            Ok(value) => Poll::Ready(Some(value)),
+            Err(TryRecvError::Empty) => Poll::Pending,
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+        }
+    }
+}
+
+/// Split `future` into a task that can be spawned on an external runtime, and an
+/// [`ExternalTask`] receiving its result.
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub(crate) fn external_task<F>(future: F) -> (impl Future<Output = ()>, ExternalTask<F::Output>)
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    let task = async move {
+        let _ = sender.send(future.await);
+    };
+    (task, ExternalTask { receiver })
+}
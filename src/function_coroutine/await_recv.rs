@@ -0,0 +1,145 @@
+use std::{
+    future::Future,
+    panic::Location,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bevy::log::warn;
+
+use super::{CoroStatus, Scope};
+
+/// Returns true once a value can be received from a channel, or once it is disconnected, without
+/// receiving anything. See [`TryRecv::probe`].
+pub type ChannelProbe = Box<dyn Fn() -> bool + Send + Sync>;
+
+/// A channel receiver that can be awaited from within a coroutine via [`Scope::recv`].
+pub trait TryRecv {
+    type Item;
+
+    /// Try to receive a value without blocking. Returns `Ok(None)` if the channel is empty, and
+    /// `Err(())` if it is disconnected.
+    #[allow(clippy::result_unit_err)]
+    fn try_recv_item(&self) -> Result<Option<Self::Item>, ()>;
+
+    /// Returns a [`ChannelProbe`] for this receiver, which the executor checks on each tick to
+    /// only resume the coroutine waiting on it once it can receive. Receivers which can't be
+    /// checked without receiving, such as the ones of [`std::sync::mpsc`], return `None`: the
+    /// coroutine is then resumed on each tick to try again.
+    fn probe(&self) -> Option<ChannelProbe> {
+        None
+    }
+}
+
+impl<T> TryRecv for std::sync::mpsc::Receiver<T> {
+    type Item = T;
+
+    fn try_recv_item(&self) -> Result<Option<T>, ()> {
+        match self.try_recv() {
+            Ok(value) => Ok(Some(value)),
+            Err(std::sync::mpsc::TryRecvError::Empty) => Ok(None),
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err(()),
+        }
+    }
+}
+
+#[cfg(feature = "crossbeam-channel")]
+impl<T: Send + 'static> TryRecv for crossbeam_channel::Receiver<T> {
+    type Item = T;
+
+    fn try_recv_item(&self) -> Result<Option<T>, ()> {
+        match self.try_recv() {
+            Ok(value) => Ok(Some(value)),
+            Err(crossbeam_channel::TryRecvError::Empty) => Ok(None),
+            Err(crossbeam_channel::TryRecvError::Disconnected) => Err(()),
+        }
+    }
+
+    fn probe(&self) -> Option<ChannelProbe> {
+        let receiver = self.clone();
+        // A receive is ready when it doesn't block, which includes a disconnected channel
+        Some(Box::new(move || {
+            let mut select = crossbeam_channel::Select::new();
+            select.recv(&receiver);
+            select.try_ready().is_ok()
+        }))
+    }
+}
+
+#[cfg(feature = "flume")]
+impl<T: Send + 'static> TryRecv for flume::Receiver<T> {
+    type Item = T;
+
+    fn try_recv_item(&self) -> Result<Option<T>, ()> {
+        match self.try_recv() {
+            Ok(value) => Ok(Some(value)),
+            Err(flume::TryRecvError::Empty) => Ok(None),
+            Err(flume::TryRecvError::Disconnected) => Err(()),
+        }
+    }
+
+    fn probe(&self) -> Option<ChannelProbe> {
+        let receiver = self.clone();
+        Some(Box::new(move || {
+            !receiver.is_empty() || receiver.is_disconnected()
+        }))
+    }
+}
+
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Recv<'a, R> {
+    scope: &'a mut Scope,
+    receiver: &'a mut R,
+    location: &'static Location<'static>,
+}
+
+impl<'a, R: TryRecv> Recv<'a, R> {
+    pub(crate) fn new(
+        scope: &'a mut Scope,
+        receiver: &'a mut R,
+        location: &'static Location<'static>,
+    ) -> Self {
+        Self {
+            scope,
+            receiver,
+            location,
+        }
+    }
+
+    /// Warn the first time the coroutine awaits a receiver it already found disconnected.
+    fn disconnected(&mut self) {
+        let receiver = &*self.receiver as *const R as usize;
+        if self.scope.disconnected != Some(receiver) {
+            self.scope.disconnected = Some(receiver);
+        } else if !self.scope.warned_disconnected {
+            self.scope.warned_disconnected = true;
+            warn!(
+                "A coroutine awaits at {} a channel which is disconnected. It will never receive \
+                anything.",
+                self.location
+            );
+        }
+    }
+}
+
+impl<R: TryRecv> Future for Recv<'_, R> {
+    type Output = Option<R::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        match self.receiver.try_recv_item() {
+            Ok(Some(value)) => Poll::Ready(Some(value)),
+            Ok(None) => {
+                let status = match self.receiver.probe() {
+                    Some(probe) => CoroStatus::Channel(probe),
+                    None => CoroStatus::Tick,
+                };
+                self.scope.yield_(status);
+                Poll::Pending
+            }
+            Err(()) => {
+                self.disconnected();
+                Poll::Ready(None)
+            }
+        }
+    }
+}
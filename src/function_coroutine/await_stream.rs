@@ -0,0 +1,67 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+
+use crate::id_alloc::Id;
+
+use super::{CoroStatus, Scope};
+
+/// An external [`Stream`] that can be consumed from within a coroutine. Created with
+/// [`Scope::stream`].
+///
+/// The stream is never awaited in a blocking way: it is polled each time the coroutine is resumed
+/// by the [`Executor`](crate::executor::Executor), and if no item is available the coroutine
+/// yields until the next tick.
+pub struct CoroStream<S> {
+    scope_id: Id,
+    stream: S,
+}
+
+impl<S: Stream + Unpin> CoroStream<S> {
+    pub(crate) fn new(scope_id: Id, stream: S) -> Self {
+        Self { scope_id, stream }
+    }
+
+    /// Returns a future that resolve once the next item of the stream is available, or `None` if
+    /// the stream is exhausted.
+    #[track_caller]
+    pub fn next<'a>(&'a mut self, scope: &'a mut Scope) -> NextItem<'a, S> {
+        scope.check_ownership(self.scope_id);
+        scope.set_suspension_site(std::panic::Location::caller());
+        NextItem {
+            scope,
+            stream: &mut self.stream,
+        }
+    }
+
+    /// Consume this adapter, and returns the underlying stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct NextItem<'a, S> {
+    scope: &'a mut Scope,
+    stream: &'a mut S,
+}
+
+impl<S: Stream + Unpin> Future for NextItem<'_, S> {
+    type Output = Option<S::Item>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        // The waker given by the executor does nothing, so the stream is simply polled again on
+        // the next tick.
+        match Pin::new(&mut *self.stream).poll_next(cx) {
+            Poll::Ready(item) => Poll::Ready(item),
+            Poll::Pending => {
+                self.scope.yield_(CoroStatus::Tick);
+                Poll::Pending
+            }
+        }
+    }
+}
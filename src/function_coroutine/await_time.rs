@@ -1,4 +1,3 @@
-use bevy::time::Time;
 use bevy::time::Timer;
 use bevy::time::TimerMode;
 use std::future::Future;
@@ -7,10 +6,41 @@ use std::task::Context;
 use std::task::Poll;
 use std::time::Duration;
 
+use crate::executor::TickTime;
+
 use super::CoroState;
 use super::CoroStatus;
 use super::Scope;
 
+/// Anything accepted as a duration: a [`Duration`], or a number of seconds as a `f32` or `f64`.
+/// Negative numbers of seconds are treated as zero.
+///
+/// ```ignore
+/// s.duration(1.5).await;
+/// s.duration(Duration::from_millis(200)).await;
+/// ```
+pub trait IntoDuration {
+    fn into_duration(self) -> Duration;
+}
+
+impl IntoDuration for Duration {
+    fn into_duration(self) -> Duration {
+        self
+    }
+}
+
+impl IntoDuration for f32 {
+    fn into_duration(self) -> Duration {
+        Duration::from_secs_f32(self.max(0.0))
+    }
+}
+
+impl IntoDuration for f64 {
+    fn into_duration(self) -> Duration {
+        Duration::from_secs_f64(self.max(0.0))
+    }
+}
+
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct NextTick<'a> {
     scope: &'a mut Scope,
@@ -38,9 +68,9 @@ impl Future for NextTick<'_> {
                 // SAFETY: See [`Executor`]
                 let dt = unsafe {
                     (self.scope.world_cell())
-                        .get_resource::<Time>()
+                        .get_resource::<TickTime>()
                         .unwrap()
-                        .delta()
+                        .delta
                 };
                 Poll::Ready(dt)
             }
@@ -0,0 +1,143 @@
+//! A record of the references to the world handed to a coroutine while it is resumed, checked in
+//! debug builds.
+//!
+//! Each reference is wrapped in a [`Tracked`] guard, registered in the borrow tracker of the
+//! coroutine until the guard is dropped. A reference which would alias a mutable one panics when
+//! taken, and so does any reference still alive once the coroutine yields. Lifetimes already rule
+//! this out for a [`Scope`](super::scope::Scope), but not for the unsafe parts of the crate, or a
+//! [`SubScope`](super::scope::SubScope) used across an await of its scope. The checks only use
+//! safe interior mutability, so they also run under Miri.
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    ops::{Deref, DerefMut},
+    panic::Location,
+    ptr::NonNull,
+};
+
+use bevy::{ecs::component::ComponentId, prelude::Entity};
+
+/// Whether borrows are tracked, see the [module level documentation](self).
+pub(crate) const TRACK_BORROWS: bool = cfg!(debug_assertions);
+
+/// What a [`Tracked`] reference gives access to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Borrow {
+    /// The whole world, readonly.
+    World,
+    Read(Entity, ComponentId),
+    Write(Entity, ComponentId),
+}
+
+impl Borrow {
+    fn conflicts_with(self, other: Borrow) -> bool {
+        match (self, other) {
+            (Borrow::World, Borrow::Write(..)) | (Borrow::Write(..), Borrow::World) => true,
+            (Borrow::Write(e, c), Borrow::Read(o, d) | Borrow::Write(o, d))
+            | (Borrow::Read(e, c), Borrow::Write(o, d)) => e == o && c == d,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Borrow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Borrow::World => write!(f, "the world"),
+            Borrow::Read(entity, id) => write!(f, "component {id:?} of {entity:?}"),
+            Borrow::Write(entity, id) => write!(f, "component {id:?} of {entity:?} (mutably)"),
+        }
+    }
+}
+
+/// The references currently handed to a coroutine, shared between its [`Scope`] and the
+/// [`FunctionCoroutine`] resuming it.
+///
+/// [`Scope`]: super::scope::Scope
+/// [`FunctionCoroutine`]: super::FunctionCoroutine
+#[derive(Default)]
+pub(crate) struct BorrowTracker {
+    borrows: RefCell<Vec<(u64, Borrow, &'static Location<'static>)>>,
+    next: Cell<u64>,
+}
+
+impl BorrowTracker {
+    /// Record `borrow`, taken at `location`. Panics if it conflicts with a borrow still alive.
+    fn register(&self, borrow: Borrow, location: &'static Location<'static>) -> u64 {
+        let mut borrows = self.borrows.borrow_mut();
+        if let Some((_, other, at)) = borrows.iter().find(|(_, b, _)| b.conflicts_with(borrow)) {
+            panic!(
+                "A coroutine borrowed {borrow} at {location}, while it still borrows {other} \
+                since {at}"
+            );
+        }
+
+        let key = self.next.get();
+        self.next.set(key + 1);
+        borrows.push((key, borrow, location));
+        key
+    }
+
+    fn release(&self, key: u64) {
+        self.borrows.borrow_mut().retain(|(k, _, _)| *k != key);
+    }
+
+    /// Panics if a reference is still alive, once the coroutine yielded or finished.
+    pub(crate) fn assert_released(&self, coroutine: &str) {
+        if let Some((_, borrow, at)) = self.borrows.borrow().first() {
+            panic!(
+                "The coroutine `{coroutine}` still borrows {borrow} (since {at}) after being \
+                resumed. References to the world must not be held across an await."
+            );
+        }
+    }
+}
+
+/// A reference to the world, or to one of its components, which is only valid while the
+/// coroutine is resumed. It dereferences to the value, and can't be held across an await. In debug
+/// builds, conflicting references panic, see the [module level documentation](self).
+pub struct Tracked<R> {
+    value: R,
+    borrow: Option<(NonNull<BorrowTracker>, u64)>,
+}
+
+// Safety: The tracker is only accessed by the thread resuming the coroutine, while it is resumed,
+// which is the only time a `Tracked` exists.
+unsafe impl<R: Send> Send for Tracked<R> {}
+
+impl<R> Tracked<R> {
+    #[track_caller]
+    pub(crate) fn new(value: R, tracker: &BorrowTracker, borrow: Borrow) -> Self {
+        Self {
+            value,
+            borrow: TRACK_BORROWS.then(|| {
+                let key = tracker.register(borrow, Location::caller());
+                (NonNull::from(tracker), key)
+            }),
+        }
+    }
+}
+
+impl<R> Drop for Tracked<R> {
+    fn drop(&mut self) {
+        if let Some((tracker, key)) = self.borrow {
+            // Safety: The tracker outlives the scope, which outlives this reference
+            unsafe { tracker.as_ref() }.release(key);
+        }
+    }
+}
+
+impl<R: Deref> Deref for Tracked<R> {
+    type Target = R::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<R: DerefMut> DerefMut for Tracked<R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
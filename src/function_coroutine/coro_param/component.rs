@@ -1,14 +1,89 @@
+//! Access to the components of the entity owning a coroutine.
+//!
+//! The references given by [`Rd::get`] and [`Wr::get_mut`] borrow the [`Scope`], while awaiting
+//! borrows it mutably. They can't be held across an await, so none of them outlive the resume
+//! of the coroutine:
+//!
+//! ```compile_fail
+//! # use bevy::prelude::Component;
+//! # use corentin::prelude::*;
+//! # #[derive(Component)]
+//! # struct Hp(u32);
+//! async fn holding_across_await(mut s: Scope, hp: Rd<Hp>) {
+//!     let hp = hp.get(&s);
+//!     s.next_tick().await;
+//!     println!("{}", hp.0);
+//! }
+//! ```
+//!
+//! A [`Wr`] is borrowed mutably to access its component, so the component can't be aliased
+//! either:
+//!
+//! ```compile_fail
+//! # use bevy::prelude::Component;
+//! # use corentin::prelude::*;
+//! # #[derive(Component)]
+//! # struct Hp(u32);
+//! async fn aliasing(s: Scope, mut hp: Wr<Hp>) {
+//!     let a = hp.get_mut(&s);
+//!     let b = hp.get_mut(&s);
+//!     println!("{} {}", a.0, b.0);
+//! }
+//! ```
+//!
+//! Two parameters accessing the same component, where one of them writes to it, are rejected at
+//! compile time by [`coroutine_fn`]:
+//!
+//! ```compile_fail
+//! # use bevy::prelude::Component;
+//! # use corentin::prelude::*;
+//! # #[derive(Component)]
+//! # struct Hp(u32);
+//! #[coroutine_fn]
+//! async fn conflicting(s: Scope, #[param] a: Wr<Hp>, #[param] b: Rd<Hp>) {}
+//! ```
+//!
+//! Closures can't be checked this way, since two generic types can't be required to differ, so
+//! such a coroutine is not started, and an error is logged instead. The remaining ways to break
+//! these rules go through the unsafe parts of the crate, which are checked at runtime by the
+//! `strict-checks` feature. In debug builds, the references are also [`Tracked`], so that one
+//! outliving the resume or aliasing a mutable one panics.
+//!
+//! [`coroutine_fn`]: macro@crate::prelude::coroutine_fn
+
 use std::marker::PhantomData;
 
 use crate::{
-    executor::msg::SignalId, function_coroutine::scope::Scope, id_alloc::Id, CoroMeta, SourceId,
+    executor::msg::SignalId,
+    function_coroutine::{
+        borrow_tracker::{Borrow, Tracked},
+        scope::Scope,
+    },
+    id_alloc::Id,
+    CoroMeta, SourceId,
 };
 use bevy::{
     ecs::{component::ComponentId, world::unsafe_world_cell::UnsafeWorldCell},
+    log::error,
     prelude::{Component, Entity, Mut},
 };
 
-use super::{on_change::ChangeTracker, CoroParam};
+use super::{on_change::ChangeTracker, CoroParam, Despawned};
+
+/// Parameters such as `(Wr<T>, Rd<T>)` would alias the same component. Only [`coroutine_fn`] can
+/// reject them at compile time, since two generic types can't be required to differ by the trait
+/// bounds of closures, so the coroutine is not started, and the error is logged instead of
+/// silently ignoring it.
+///
+/// [`coroutine_fn`]: macro@crate::prelude::coroutine_fn
+fn conflicting_access<T: Component>(coro_meta: &CoroMeta) {
+    error!(
+        "The coroutine `{}` has conflicting parameters, and was not started: `{}` is written by \
+        one of them, and accessed by another",
+        coro_meta.name,
+        std::any::type_name::<T>()
+    );
+}
 
 /// A readonly reference to a [`Component`] from the owning [`Entity`].
 ///
@@ -17,6 +92,7 @@ use super::{on_change::ChangeTracker, CoroParam};
 pub struct Rd<T: Component> {
     scope_id: Id,
     owner: Entity,
+    id: ComponentId,
     _phantom: PhantomData<T>,
 }
 
@@ -26,12 +102,14 @@ impl<T: Component> CoroParam for Rd<T> {
         let owner = coro_meta.owner?;
 
         if !coro_meta.access.add_read(SourceId::Entity(owner), id) {
+            conflicting_access::<T>(coro_meta);
             return None;
         }
 
         Some(Self {
             scope_id: coro_meta.id,
             owner,
+            id,
             _phantom: PhantomData,
         })
     }
@@ -48,25 +126,37 @@ impl<T: Component> CoroParam for Rd<T> {
 }
 
 impl<T: Component> Rd<T> {
-    /// Return the current value of the [`Component`]. The result ([`InGuard`]) cannot be held
+    /// Return the current value of the [`Component`]. The result ([`Tracked`]) cannot be held
     /// accros any await.
-    pub fn get<'a>(&'a self, scope: &'a Scope) -> &'a T {
+    #[track_caller]
+    pub fn get<'a>(&'a self, scope: &'a Scope) -> Tracked<&'a T> {
+        self.try_get(scope).unwrap()
+    }
+
+    /// Like [`get`](Rd::get), but returns an error instead of panicking if the entity no longer
+    /// has the component.
+    #[track_caller]
+    pub fn try_get<'a>(&'a self, scope: &'a Scope) -> Result<Tracked<&'a T>, Despawned> {
         scope.check_ownership(self.scope_id);
-        unsafe {
+        let component = unsafe {
             scope
                 .world_cell()
                 .get_entity(self.owner)
-                .unwrap()
-                .get::<T>()
-                .unwrap()
-        }
+                .and_then(|e| e.get::<T>())
+                .ok_or(Despawned)?
+        };
+        Ok(scope.track(component, Borrow::Read(self.owner, self.id)))
     }
 }
 
 /// A read-write exclusive reference to a [`Component`] from the owning [`Entity`].
 ///
 /// Note that a Coroutine with such parameter will be canceled if the entity does not have the
-/// relevent component.
+/// relevent component. It also can't take any other [`Rd`] or [`Wr`] of the same component: such
+/// a coroutine is rejected at compile time by [`coroutine_fn`], and otherwise is not started, with
+/// an error logged.
+///
+/// [`coroutine_fn`]: macro@crate::prelude::coroutine_fn
 pub struct Wr<T: Component> {
     owner: Entity,
     id: ComponentId,
@@ -80,6 +170,7 @@ impl<T: Component> CoroParam for Wr<T> {
         let owner = coro_meta.owner?;
 
         if !coro_meta.access.add_write(SourceId::Entity(owner), id) {
+            conflicting_access::<T>(coro_meta);
             return None;
         }
 
@@ -103,35 +194,56 @@ impl<T: Component> CoroParam for Wr<T> {
 }
 
 impl<T: Component> Wr<T> {
-    pub fn get<'a>(&'a mut self, scope: &'a Scope) -> &'a T {
+    #[track_caller]
+    pub fn get<'a>(&'a mut self, scope: &'a Scope) -> Tracked<&'a T> {
+        self.try_get(scope).unwrap()
+    }
+
+    /// Like [`get`](Wr::get), but returns an error instead of panicking if the entity no longer
+    /// has the component.
+    #[track_caller]
+    pub fn try_get<'a>(&'a mut self, scope: &'a Scope) -> Result<Tracked<&'a T>, Despawned> {
         scope.check_ownership(self.scope_id);
-        let value = unsafe {
+        let component = unsafe {
             scope
                 .world_cell()
                 .get_entity(self.owner)
-                .unwrap()
-                .get::<T>()
-                .unwrap()
+                .and_then(|e| e.get::<T>())
+                .ok_or(Despawned)?
         };
+        Ok(scope.track(component, Borrow::Read(self.owner, self.id)))
+    }
 
-        value
+    #[track_caller]
+    pub fn get_mut<'a>(&'a mut self, scope: &'a Scope) -> Tracked<Mut<'a, T>> {
+        self.try_get_mut(scope).unwrap()
     }
 
-    pub fn get_mut<'a>(&'a mut self, scope: &'a Scope) -> Mut<'a, T> {
+    /// Like [`get_mut`](Wr::get_mut), but returns an error instead of panicking if the entity no
+    /// longer has the component.
+    #[track_caller]
+    pub fn try_get_mut<'a>(
+        &'a mut self,
+        scope: &'a Scope,
+    ) -> Result<Tracked<Mut<'a, T>>, Despawned> {
         scope.check_ownership(self.scope_id);
 
-        unsafe {
+        let component = unsafe {
             let cell = scope.world_cell();
-            let entity = cell.get_entity(self.owner).unwrap();
+            let entity = cell.get_entity(self.owner).ok_or(Despawned)?;
+            let component = entity.get_mut::<T>().ok_or(Despawned)?;
 
             if entity.contains::<ChangeTracker<T>>() {
                 scope.emit_signal(SignalId {
                     signal_type: self.id,
                     owner: Some(self.owner),
+                    external: false,
+                    key: None,
                 });
             }
 
-            cell.get_entity(self.owner).unwrap().get_mut::<T>().unwrap()
-        }
+            component
+        };
+        Ok(scope.track(component, Borrow::Write(self.owner, self.id)))
     }
 }
@@ -1,4 +1,4 @@
-use bevy::{ecs::world::unsafe_world_cell::UnsafeWorldCell, utils::all_tuples};
+use bevy::{ecs::world::unsafe_world_cell::UnsafeWorldCell, prelude::Entity, utils::all_tuples};
 
 use super::CoroMeta;
 
@@ -11,8 +11,17 @@ pub mod prelude {
 
     #[doc(hidden)]
     pub use super::on_change::{ChangeTracker, OnChange};
+
+    #[doc(hidden)]
+    pub use super::Despawned;
 }
 
+/// Returned when accessing a parameter whose entity was despawned (or lost the component), which
+/// can only happen with the [`DespawnPolicy::Resume`](crate::executor::DespawnPolicy::Resume)
+/// policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Despawned;
+
 /// A function taking a scope and 0 or many [`CoroParam`]
 /// can be trurned into a [`Coroutine`](super::Coroutine).
 pub trait CoroParam: Sized {
@@ -25,6 +34,18 @@ pub trait CoroParam: Sized {
     fn is_valid(world: UnsafeWorldCell<'_>, coro_meta: &CoroMeta) -> bool;
 }
 
+/// The entity owning the coroutine, so that a script started for an entity doesn't need to
+/// capture it. Coroutines without an owner can't take this parameter, and are not started.
+impl CoroParam for Entity {
+    fn init(_world: UnsafeWorldCell<'_>, coro_meta: &mut CoroMeta) -> Option<Self> {
+        coro_meta.owner
+    }
+
+    fn is_valid(_world: UnsafeWorldCell<'_>, _coro_meta: &CoroMeta) -> bool {
+        true
+    }
+}
+
 macro_rules! impl_coro_param {
     ($($param: ident),*) => {
         #[allow(non_snake_case, unused_parens, unused_variables)]
@@ -29,13 +29,26 @@ impl<T: Component> ChangeTracker<T> {
     }
 }
 
+/// Awaits a change of the component `T` of the owning entity, see [`OnChange::observe`].
+///
+/// Changes are not found by comparing the change ticks of the component: each mutation through
+/// [`Wr`](super::component::Wr) (or the helpers of the [`Scope`]) directly wakes the coroutines
+/// observing it, as long as the entity has a [`ChangeTracker<T>`]. A coroutine can observe a
+/// component for hours without being affected by the change ticks of the [`World`] wrapping
+/// around, and never misses a change made while it was suspended.
+///
+/// Mutations made by regular systems are not seen, since they don't go through the executor.
+///
+/// [`World`]: bevy::prelude::World
 pub struct OnChange<T: Component> {
     id: SignalId,
     _phantom: PhantomData<T>,
 }
 
 impl<T: Component> OnChange<T> {
+    #[track_caller]
     pub fn observe<'a>(&self, scope: &'a mut Scope) -> AwaitChange<'a> {
+        scope.set_suspension_site(std::panic::Location::caller());
         AwaitChange::new(scope, self.id)
     }
 }
@@ -49,6 +62,8 @@ impl<T: Component> CoroParam for OnChange<T> {
             id: SignalId {
                 signal_type: id,
                 owner: Some(owner),
+                external: false,
+                key: None,
             },
             _phantom: PhantomData,
         })
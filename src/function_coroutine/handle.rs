@@ -1,3 +1,5 @@
+use std::any::Any;
+
 use bevy::utils::all_tuples;
 use oneshot::TryRecvError;
 use tinyset::SetU64;
@@ -8,6 +10,11 @@ use super::once_channel::OnceRec;
 
 /// Value representing an ongoing coroutine. Can be used to await it's result, or cancel the
 /// underlying coroutine by dropping it.
+///
+/// The handle is typed by the output of the coroutine, so awaiting it with
+/// [`Scope::on`](super::scope::Scope::on) or [`Scope::all`](super::scope::Scope::all) gives back
+/// that output directly. Handles of coroutines with different outputs can be stored together
+/// after turning them into an [`AnyHandle`].
 pub enum CoroHandle<T> {
     Waiting { id: Id, receiver: OnceRec<T> },
     Done(T),
@@ -15,6 +22,54 @@ pub enum CoroHandle<T> {
     Finish,
 }
 
+impl<T: Send + 'static> CoroHandle<T> {
+    /// Forget the output type of this handle, so that it can be stored along other handles. The
+    /// coroutine is still canceled once the [`AnyHandle`] is dropped.
+    pub fn into_any(self) -> AnyHandle {
+        AnyHandle(Box::new(self))
+    }
+}
+
+/// A [`CoroHandle`] whose output type has been erased, see [`CoroHandle::into_any`]. Awaiting it
+/// only tells when the coroutine is done, [`AnyHandle::downcast`] gives back the typed handle.
+pub struct AnyHandle(Box<dyn ErasedHandle>);
+
+impl AnyHandle {
+    /// Returns the typed handle, or `self` if the output of the coroutine isn't a `T`.
+    pub fn downcast<T: Send + 'static>(self) -> Result<CoroHandle<T>, AnyHandle> {
+        if self.0.as_any().is::<CoroHandle<T>>() {
+            Ok(*self.0.into_any().downcast().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+trait ErasedHandle: Send {
+    fn update_status(&mut self) -> Status;
+    fn try_finish(&mut self) -> Option<()>;
+    fn as_any(&self) -> &dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+}
+
+impl<T: Send + 'static> ErasedHandle for CoroHandle<T> {
+    fn update_status(&mut self) -> Status {
+        HandleTuple::update_status(self)
+    }
+
+    fn try_finish(&mut self) -> Option<()> {
+        self.try_fetch().map(|_| ())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
 /// Trait so that we can have function generic over a tuple of handles, like await all.
 pub trait HandleTuple {
     type Output;
@@ -95,6 +150,68 @@ impl<T> HandleTuple for CoroHandle<T> {
     }
 }
 
+impl<T> HandleTuple for Vec<CoroHandle<T>> {
+    type Output = Vec<T>;
+
+    fn update_status(&mut self) -> Status {
+        self.iter_mut().fold(Status::Done, |status, h| {
+            status.combine(|| h.update_status())
+        })
+    }
+
+    fn try_fetch(&mut self) -> Option<Self::Output> {
+        self.iter_mut().map(|h| h.try_fetch()).collect()
+    }
+}
+
+impl HandleTuple for AnyHandle {
+    type Output = ();
+
+    fn update_status(&mut self) -> Status {
+        self.0.update_status()
+    }
+
+    fn try_fetch(&mut self) -> Option<Self::Output> {
+        self.0.try_finish()
+    }
+}
+
+impl HandleTuple for Vec<AnyHandle> {
+    type Output = ();
+
+    fn update_status(&mut self) -> Status {
+        self.iter_mut().fold(Status::Done, |status, h| {
+            status.combine(|| h.update_status())
+        })
+    }
+
+    fn try_fetch(&mut self) -> Option<Self::Output> {
+        self.iter_mut().try_for_each(|h| h.try_fetch())
+    }
+}
+
+/// Like the implementation for [`Vec`], but the handles and the results are stored inline, which
+/// avoids allocating when awaiting a few coroutines at once.
+impl<T, const N: usize> HandleTuple for [CoroHandle<T>; N] {
+    type Output = [T; N];
+
+    fn update_status(&mut self) -> Status {
+        self.iter_mut().fold(Status::Done, |status, h| {
+            status.combine(|| h.update_status())
+        })
+    }
+
+    fn try_fetch(&mut self) -> Option<Self::Output> {
+        let mut handles = self.iter_mut();
+        let values: [Option<T>; N] =
+            std::array::from_fn(|_| handles.next().and_then(|h| h.try_fetch()));
+        if values.iter().any(Option::is_none) {
+            return None;
+        }
+        Some(values.map(Option::unwrap))
+    }
+}
+
 macro_rules! impl_handler_tuple {
     ($first: ident, $($param: ident),*) => {
         #[allow(non_snake_case)]
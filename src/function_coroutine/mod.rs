@@ -1,24 +1,29 @@
 use bevy::ecs::world::World;
+use bevy::log::error;
 
 use bevy::ecs::world::unsafe_world_cell::UnsafeWorldCell;
 use bevy::utils::all_tuples;
 use std::future::Future;
+use std::mem::ManuallyDrop;
 
+use std::panic::Location;
 use std::pin::Pin;
 
 use std::ptr::null;
-use std::ptr::null_mut;
 use std::task::Context;
 use std::task::Poll;
+use std::thread::ThreadId;
 
 use pin_project::pin_project;
 
 use crate::executor::msg::EmitMsg;
 use crate::executor::msg::NewCoroutine;
 use crate::executor::msg::YieldMsg;
+use crate::executor::DespawnPolicy;
 use crate::global_channel::Channel;
 use crate::global_channel::CommandChannel;
 
+use self::borrow_tracker::{BorrowTracker, TRACK_BORROWS};
 use self::coro_param::CoroParam;
 use self::once_channel::OnceSender;
 use self::resume::Resume;
@@ -32,63 +37,191 @@ use super::executor::msg::CoroStatus;
 use super::id_alloc::Id;
 use super::id_alloc::Ids;
 use super::Coroutine;
+use super::MaybeSend;
 
 pub mod await_all;
+pub mod await_bridge;
 pub mod await_change;
+pub mod await_condition;
+pub mod await_event;
+pub mod await_external;
 pub mod await_first;
+pub mod await_recv;
 pub mod await_signal;
+pub mod await_stream;
 pub mod await_time;
+pub mod borrow_tracker;
 pub mod coro_param;
 pub mod handle;
 pub mod once_channel;
 pub mod resume;
 pub mod scope;
+pub mod with_args;
 
 pub mod prelude {
     #[doc(hidden)]
-    pub use super::scope::Scope;
+    pub use super::scope::{Scope, SubScope};
 
     #[doc(hidden)]
-    pub use super::handle::CoroHandle;
+    pub use super::handle::{AnyHandle, CoroHandle};
 
     #[doc(hidden)]
     pub use super::coro_param::prelude::*;
+
+    #[doc(hidden)]
+    pub use super::with_args::IntoCoroutine;
+
+    #[doc(hidden)]
+    pub use super::await_time::IntoDuration;
+
+    #[doc(hidden)]
+    pub use super::{boxed_coroutine, BoxedCoroutine};
+}
+
+/// The future of a coroutine defined with the
+/// [`coroutine_fn`](macro@crate::prelude::coroutine_fn) attribute.
+#[cfg(not(target_arch = "wasm32"))]
+pub type BoxedFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+/// The future of a coroutine defined with the
+/// [`coroutine_fn`](macro@crate::prelude::coroutine_fn) attribute.
+#[cfg(target_arch = "wasm32")]
+pub type BoxedFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// A coroutine whose type is erased, so that different ones can be stored together and chosen at
+/// runtime, in a table of recipes for instance. It is started like any other coroutine, see
+/// [`boxed_coroutine`].
+#[cfg(not(target_arch = "wasm32"))]
+pub type BoxedCoroutine<T = ()> = Box<dyn FnOnce(Scope) -> BoxedFuture<T> + Send>;
+/// A coroutine whose type is erased, so that different ones can be stored together and chosen at
+/// runtime, in a table of recipes for instance. It is started like any other coroutine, see
+/// [`boxed_coroutine`].
+#[cfg(target_arch = "wasm32")]
+pub type BoxedCoroutine<T = ()> = Box<dyn FnOnce(Scope) -> BoxedFuture<T>>;
+
+/// Erase the type of `coroutine`, along with the type of the future it returns.
+///
+/// ```ignore
+/// let recipes: Vec<BoxedCoroutine> = vec![
+///     boxed_coroutine(|mut s: Scope| async move { s.duration(1.0).await }),
+///     boxed_coroutine(patrol),
+/// ];
+/// ```
+pub fn boxed_coroutine<T, F, Fut>(coroutine: F) -> BoxedCoroutine<T>
+where
+    F: FnOnce(Scope) -> Fut + MaybeSend + 'static,
+    Fut: Future<Output = T> + MaybeSend + 'static,
+{
+    Box::new(move |s| Box::pin(coroutine(s)))
 }
 
 #[pin_project]
 pub struct FunctionCoroutine<Marker, F, T>
 where
-    F: CoroutineParamFunction<Marker, T>,
+    F: CoroutineFunction<Marker, T>,
 {
     #[pin]
-    future: F::Future,
+    future: CoroFuture<F::Future>,
     id: Id,
     resume_param: Resume<ResumeParam>,
+    borrows: Resume<BorrowTracker>,
     meta: CoroMeta,
     result_sender: Option<OnceSender<T>>,
+    suspended_at: Option<&'static Location<'static>>,
+}
+
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a coroutine",
+    label = "expected an `async fn` or a closure returning a future",
+    note = "the first parameter of a coroutine must be a `Scope`, and the others must implement `CoroParam`",
+    note = "closures need their parameter types to be annotated, as in `|mut s: Scope| async move {{ ... }}`"
+)]
+pub trait CoroutineParamFunction<Marker, T>: CoroutineFunction<Marker, T> + MaybeSend {}
+
+impl<Marker, T, F> CoroutineParamFunction<Marker, T> for F
+where
+    F: CoroutineFunction<Marker, T> + MaybeSend,
+    F::Future: MaybeSend,
+{
 }
 
-pub trait CoroutineParamFunction<Marker, T>: Send + 'static {
-    type Future: Future<Output = T> + Send + 'static;
+/// A function which can be turned into a coroutine, whether it is [`Send`] or not. Only
+/// [`CoroutineParamFunction`]s can be resumed from any thread, the others are bound to the thread
+/// they were started on (see [`Scope::start_non_send`]).
+pub trait CoroutineFunction<Marker, T>: 'static {
+    type Future: Future<Output = T> + 'static;
     type Params: CoroParam;
 
     fn init(self, scope: Scope, params: Self::Params) -> Self::Future;
 }
 
-// Safety: ???
+// Safety: The future is the only part of the coroutine which may not be `Send`. Coroutines which
+// aren't `CoroutineParamFunction` are bound to a thread (see `CoroMeta::thread`): they panic if
+// resumed from any other, and their future is never dropped from any other (see `BoundFuture`).
+// Note that on wasm32, the future may not be `Send`, but there is only a single thread.
 unsafe impl<Marker, F, T> Send for FunctionCoroutine<Marker, F, T> where
-    F: CoroutineParamFunction<Marker, T>
+    F: CoroutineFunction<Marker, T>
 {
 }
 
+/// The future of a coroutine, boxed if it is bound to a thread.
+#[pin_project(project = CoroFutureProj)]
+enum CoroFuture<Fut> {
+    Send(#[pin] Fut),
+    Bound(BoundFuture<Fut>),
+}
+
+impl<Fut: Future> Future for CoroFuture<Fut> {
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.project() {
+            CoroFutureProj::Send(future) => future.poll(cx),
+            CoroFutureProj::Bound(bound) => bound.future.as_mut().poll(cx),
+        }
+    }
+}
+
+/// The future of a non-[`Send`] coroutine. It may share values with the thread it started on (an
+/// `Rc` for instance), so it is only dropped on that thread. When dropped from another one (along
+/// with the executor or the world), it is leaked instead.
+struct BoundFuture<Fut> {
+    future: ManuallyDrop<Pin<Box<Fut>>>,
+    thread: ThreadId,
+}
+
+impl<Fut> Drop for BoundFuture<Fut> {
+    fn drop(&mut self) {
+        if self.thread == std::thread::current().id() {
+            // Safety: The future is never used again
+            unsafe { ManuallyDrop::drop(&mut self.future) };
+        } else {
+            error!(
+                "A non-Send coroutine was dropped from another thread than the one it started \
+                on, its future is leaked"
+            );
+        }
+    }
+}
+
+fn check_thread(meta: &CoroMeta) {
+    if let Some(thread) = meta.thread {
+        assert!(
+            thread == std::thread::current().id(),
+            "The non-Send coroutine {} was resumed from another thread than the one it started on",
+            meta.name
+        );
+    }
+}
+
 const ERR_WRONGAWAIT: &str = "A coroutine yielded without notifying the executor
 the reason. That is most likely because it awaits a
-future which is not part of this library.";
+future which is not part of this library. Such futures
+must be awaited through `Scope::bridge`.";
 
 impl<Marker: 'static, F, T> Coroutine for FunctionCoroutine<Marker, F, T>
 where
     T: Send + Sync + 'static,
-    F: CoroutineParamFunction<Marker, T>,
+    F: CoroutineFunction<Marker, T>,
 {
     fn resume(
         self: Pin<&mut Self>,
@@ -105,9 +238,11 @@ where
         let mut cx = Context::from_waker(&waker);
 
         let this = self.project();
+        check_thread(this.meta);
 
-        // Safety: Idk
-        let world = world as *mut _;
+        // Safety: The world is borrowed mutably for the whole resume, so the cell is the only
+        // access to it.
+        let world = unsafe { erase_world_lifetime(world.as_unsafe_world_cell()) };
         let ids = ids as *const _;
         let emit_channel = emit_channel as *const _;
         let new_coro_channel = new_coro_channel as *const _;
@@ -118,16 +253,26 @@ where
         // the swap while the future is getting polled, only before and after.
         unsafe {
             this.resume_param.set(ResumeParam {
-                world,
+                world: Some(world),
                 ids,
                 curr_node,
                 yield_sender: None,
                 emit_channel,
                 new_coro_channel,
                 commands_channel,
+                suspended_at: None,
+                despawn_policy: None,
+                wait_despawn_policy: None,
             });
 
             let res = this.future.poll(&mut cx);
+            if TRACK_BORROWS {
+                this.borrows.get().assert_released(this.meta.name);
+            }
+            if let Some(policy) = this.resume_param.get_mut().despawn_policy.take() {
+                this.meta.despawn_policy = Some(policy);
+            }
+            this.meta.wait_despawn_policy = this.resume_param.get_mut().wait_despawn_policy.take();
 
             match res {
                 Poll::Ready(t) => {
@@ -145,6 +290,7 @@ where
                         .yield_sender
                         .take()
                         .expect(ERR_WRONGAWAIT);
+                    *this.suspended_at = this.resume_param.get().suspended_at;
                     this.resume_param.set(ResumeParam::new());
                     status
                 }
@@ -167,9 +313,11 @@ where
         let mut cx = Context::from_waker(&waker);
 
         let this = self.project();
+        check_thread(this.meta);
 
-        // Safety: Idk
-        let world = world.world_mut() as *mut _;
+        // Safety: The cell is never turned into a `&mut World`: the parameters and the helpers of
+        // the scope only access what they declare, see `Scope::world_cell`.
+        let world = unsafe { erase_world_lifetime(world) };
         let ids = ids as *const _;
         let emit_channel = emit_channel as *const _;
         let new_coro_channel = new_coro_channel as *const _;
@@ -180,16 +328,26 @@ where
         // the swap while the future is getting polled, only before and after.
         unsafe {
             this.resume_param.set(ResumeParam {
-                world,
+                world: Some(world),
                 ids,
                 curr_node,
                 yield_sender: None,
                 emit_channel,
                 new_coro_channel,
                 commands_channel,
+                suspended_at: None,
+                despawn_policy: None,
+                wait_despawn_policy: None,
             });
 
             let res = this.future.poll(&mut cx);
+            if TRACK_BORROWS {
+                this.borrows.get().assert_released(this.meta.name);
+            }
+            if let Some(policy) = this.resume_param.get_mut().despawn_policy.take() {
+                this.meta.despawn_policy = Some(policy);
+            }
+            this.meta.wait_despawn_policy = this.resume_param.get_mut().wait_despawn_policy.take();
 
             match res {
                 Poll::Ready(t) => {
@@ -209,6 +367,7 @@ where
                         .yield_sender
                         .take()
                         .expect(ERR_WRONGAWAIT);
+                    *this.suspended_at = this.resume_param.get().suspended_at;
                     this.resume_param.set(ResumeParam::new());
                     yield_channel.send(YieldMsg {
                         id: *this.id,
@@ -233,11 +392,22 @@ where
     fn meta(&self) -> &CoroMeta {
         &self.meta
     }
+
+    fn suspended_at(&self) -> Option<&'static Location<'static>> {
+        self.suspended_at
+    }
 }
 
+/// The waker given to the coroutines. Coroutines are woken up by the [`Executor`] based on the
+/// status they yield, so the waker itself does nothing. It doesn't point to any state either:
+/// creating or cloning it never allocates nor touches a reference count, so there is nothing to
+/// cache or pool per coroutine.
+///
+/// [`Executor`]: crate::executor::Executor
 mod waker {
     use std::task::{RawWaker, RawWakerVTable, Waker};
 
+    #[inline]
     pub fn create() -> Waker {
         // Safety: The waker points to a vtable with functions that do nothing. Doing
         // nothing is memory-safe.
@@ -256,7 +426,7 @@ mod waker {
 impl<Marker: 'static, F, T> FunctionCoroutine<Marker, F, T>
 where
     T: Send + Sync + 'static,
-    F: CoroutineParamFunction<Marker, T>,
+    F: CoroutineFunction<Marker, T>,
 {
     pub(crate) fn new(
         scope: Scope,
@@ -268,30 +438,60 @@ where
     ) -> Option<Self> {
         let mut meta = CoroMeta {
             owner: scope.owner(),
+            name: std::any::type_name::<F>(),
+            despawn_policy: None,
+            wait_despawn_policy: None,
             access: CoroAccess::default(),
+            thread: None,
             id,
         };
 
         let params = F::Params::init(world_cell, &mut meta)?;
+        let borrows = scope.borrows.clone();
         let future = f.init(scope, params);
 
         Some(Self {
-            future,
+            future: CoroFuture::Send(future),
             resume_param,
+            borrows,
             meta,
             id,
             result_sender,
+            suspended_at: None,
         })
     }
+
+    /// Only allow this coroutine to be resumed from the current thread.
+    pub(crate) fn bind_to_current_thread(mut self) -> Self {
+        let thread = std::thread::current().id();
+        self.meta.thread = Some(thread);
+        if let CoroFuture::Send(future) = self.future {
+            self.future = CoroFuture::Bound(BoundFuture {
+                future: ManuallyDrop::new(Box::pin(future)),
+                thread,
+            });
+        }
+        self
+    }
+
+    pub(crate) fn with_name(mut self, name: &'static str) -> Self {
+        self.meta.name = name;
+        self
+    }
+
+    pub(crate) fn with_despawn_policy(mut self, policy: DespawnPolicy) -> Self {
+        self.meta.despawn_policy = Some(policy);
+        self
+    }
 }
 
 macro_rules! impl_coro_function {
     ($($param: ident),*) => {
         #[allow(non_snake_case, unused_mut, unused_variables, unused_parens)]
-        impl<Func, T, Fut, $($param: CoroParam),*> CoroutineParamFunction<fn($($param,)*) -> Fut, T> for Func
+        impl<Func, T, Fut, $($param: CoroParam),*> CoroutineFunction<fn($($param,)*) -> Fut, T> for Func
         where
-            Func: FnOnce(Scope, $($param),*) -> Fut + Send + 'static,
-            Fut: Future<Output = T> + Send + 'static,
+            Func: FnOnce(Scope, $($param),*) -> Fut + 'static,
+            Fut: Future<Output = T> + 'static,
             T: Send + Sync + 'static,
         {
             type Future = Fut;
@@ -314,13 +514,18 @@ enum CoroState {
 }
 
 pub(crate) struct ResumeParam {
-    world: *mut World,
+    /// The world the coroutine is resumed in. It is shared with the other coroutines of a
+    /// parallel batch, so it is only ever accessed through the cell, never as a `&mut World`.
+    world: Option<UnsafeWorldCell<'static>>,
     ids: *const Ids,
     curr_node: usize,
     yield_sender: Option<CoroStatus>,
     emit_channel: *const Channel<EmitMsg>,
     new_coro_channel: *const Channel<NewCoroutine>,
     commands_channel: *const CommandChannel,
+    suspended_at: Option<&'static Location<'static>>,
+    despawn_policy: Option<DespawnPolicy>,
+    wait_despawn_policy: Option<DespawnPolicy>,
 }
 
 impl Default for ResumeParam {
@@ -329,6 +534,14 @@ impl Default for ResumeParam {
     }
 }
 
+/// Erase the lifetime of `world`, to store it in a [`ResumeParam`] for the duration of a resume.
+///
+/// # Safety
+/// The cell must not be used once the resume is over.
+unsafe fn erase_world_lifetime(world: UnsafeWorldCell<'_>) -> UnsafeWorldCell<'static> {
+    std::mem::transmute(world)
+}
+
 /// General safety comment: I don't exaclty know if all that is safe...
 /// but normally [`ResumeParam`] can only be accessed via the scope
 /// when the future is polled. Meaning that all the pointers
@@ -336,13 +549,16 @@ impl Default for ResumeParam {
 impl ResumeParam {
     pub fn new() -> Self {
         Self {
-            world: null_mut(),
+            world: None,
             ids: null(),
             curr_node: 0,
             yield_sender: None,
             emit_channel: null(),
             new_coro_channel: null(),
             commands_channel: null(),
+            suspended_at: None,
+            despawn_policy: None,
+            wait_despawn_policy: None,
         }
     }
 }
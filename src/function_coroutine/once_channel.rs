@@ -1,4 +1,9 @@
-use std::{mem::MaybeUninit, ptr::NonNull};
+use std::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr::NonNull,
+    sync::atomic::{AtomicU8, Ordering},
+};
 
 use oneshot::TryRecvError;
 use sync_states::*;
@@ -25,27 +30,31 @@ pub struct OnceRec<T> {
 
 impl<T> OnceSender<T> {
     pub fn send(self, message: T) {
-        let mut channel_ptr = self.channel_ptr;
+        let channel_ptr = self.channel_ptr;
 
         // Don't run our Drop implementation if send was called, any cleanup now happens here
         std::mem::forget(self);
 
-        // SAFETY: The channel exists on the heap for the entire duration of this method and we
-        // only ever acquire shared references to it. Note that if the receiver disconnects it
-        // does not free the channel.
-        let channel = unsafe { channel_ptr.as_mut() };
+        // SAFETY: The channel exists on the heap for the entire duration of this method, and we
+        // only ever acquire shared references to it. Only the sender writes the message, and the
+        // receiver only reads it once the state is `DONE`.
+        let channel = unsafe { channel_ptr.as_ref() };
+        unsafe { (*channel.message.get()).write(message) };
 
-        match channel.state {
-            INIT => {
-                channel.state = DONE;
-                channel.message.write(message);
-            }
-            DROP_REC => {
+        match channel
+            .state
+            .compare_exchange(INIT, DONE, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => {}
+            Err(DROP_REC) => {
                 // SAFETY: The receiver has been dropped, we can therefore safely drop
-                // the channel.
-                unsafe { drop(Box::from_raw(channel_ptr.as_ptr())) }
+                // the channel, and the message nobody will read.
+                unsafe {
+                    let mut channel = Box::from_raw(channel_ptr.as_ptr());
+                    channel.message.get_mut().assume_init_drop();
+                }
             }
-            _ => unreachable!(),
+            Err(_) => unreachable!(),
         }
     }
 
@@ -56,26 +65,29 @@ impl<T> OnceSender<T> {
         // does not free the channel.
         let channel = unsafe { self.channel_ptr.as_ref() };
 
-        channel.state != DROP_REC
+        channel.state.load(Ordering::Acquire) != DROP_REC
     }
 }
 
 impl<T> OnceRec<T> {
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
-        let mut channel_ptr = self.channel_ptr;
+        let channel_ptr = self.channel_ptr;
 
         // SAFETY: The channel exists on the heap for the entire duration of this method and we
         // only ever acquire shared references to it. Note that if the receiver disconnects it
         // does not free the channel.
-        let channel = unsafe { channel_ptr.as_mut() };
+        let channel = unsafe { channel_ptr.as_ref() };
 
-        match channel.state {
+        match channel.state.load(Ordering::Acquire) {
             INIT => Err(TryRecvError::Empty),
             DROP_SND => Err(TryRecvError::Disconnected),
             DONE => {
-                let res = std::mem::replace(&mut channel.message, MaybeUninit::uninit());
-                // Safety: We are in the done state, the message must be initialized
-                unsafe { Ok(res.assume_init()) }
+                // Safety: We are in the done state, the message must be initialized, and the
+                // sender is gone. The receiver isn't `Sync`, so nobody else reads it.
+                let message = unsafe { (*channel.message.get()).assume_init_read() };
+                // The message is taken, the channel is now only disconnected
+                channel.state.store(DROP_SND, Ordering::Release);
+                Ok(message)
             }
             _ => unreachable!(),
         }
@@ -84,42 +96,51 @@ impl<T> OnceRec<T> {
 
 impl<T> Drop for OnceRec<T> {
     fn drop(&mut self) {
-        let mut channel_ptr = self.channel_ptr;
+        let channel_ptr = self.channel_ptr;
         // SAFETY: The channel exists on the heap for the entire duration of this method and we
         // only ever acquire shared references to it. Note that if the receiver disconnects it
         // does not free the channel.
-        let channel = unsafe { channel_ptr.as_mut() };
-        match channel.state {
-            DROP_SND | DONE => {
-                // SAFETY: The receiver has been dropped, we can therefore safely drop
-                // the channel.
+        let channel = unsafe { channel_ptr.as_ref() };
+        match channel
+            .state
+            .compare_exchange(INIT, DROP_REC, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => {}
+            Err(DROP_SND) => {
+                // SAFETY: The sender is done with the channel, we can therefore safely drop it.
                 unsafe { drop(Box::from_raw(channel_ptr.as_ptr())) }
             }
-            INIT => {
-                channel.state = DROP_REC;
+            Err(DONE) => {
+                // SAFETY: The sender is done with the channel, we can therefore safely drop it,
+                // along with the message nobody read.
+                unsafe {
+                    let mut channel = Box::from_raw(channel_ptr.as_ptr());
+                    channel.message.get_mut().assume_init_drop();
+                }
             }
-            _ => unreachable!(),
+            Err(_) => unreachable!(),
         }
     }
 }
 
 impl<T> Drop for OnceSender<T> {
     fn drop(&mut self) {
-        let mut channel_ptr = self.channel_ptr;
+        let channel_ptr = self.channel_ptr;
         // SAFETY: The channel exists on the heap for the entire duration of this method and we
         // only ever acquire shared references to it. Note that if the receiver disconnects it
         // does not free the channel.
-        let channel = unsafe { channel_ptr.as_mut() };
-        match channel.state {
-            DROP_REC => {
+        let channel = unsafe { channel_ptr.as_ref() };
+        match channel
+            .state
+            .compare_exchange(INIT, DROP_SND, Ordering::AcqRel, Ordering::Acquire)
+        {
+            Ok(_) => {}
+            Err(DROP_REC) => {
                 // SAFETY: The receiver has been dropped, we can therefore safely drop
                 // the channel.
                 unsafe { drop(Box::from_raw(channel_ptr.as_ptr())) }
             }
-            INIT => {
-                channel.state = DROP_SND;
-            }
-            _ => unreachable!(),
+            Err(_) => unreachable!(),
         }
     }
 }
@@ -127,16 +148,19 @@ impl<T> Drop for OnceSender<T> {
 unsafe impl<T: Send> Send for OnceSender<T> {}
 unsafe impl<T: Send> Send for OnceRec<T> {}
 
+/// The state is atomic, so that both ends can live on different threads (for instance when
+/// coroutines are resumed in parallel) without any lock. The message is written by one end while
+/// the other holds a shared reference to the channel, hence the cell.
 struct SyncChannel<T> {
-    state: u8,
-    message: MaybeUninit<T>,
+    state: AtomicU8,
+    message: UnsafeCell<MaybeUninit<T>>,
 }
 
 impl<T> SyncChannel<T> {
     fn new() -> Self {
         Self {
-            state: INIT,
-            message: MaybeUninit::uninit(),
+            state: AtomicU8::new(INIT),
+            message: UnsafeCell::new(MaybeUninit::uninit()),
         }
     }
 }
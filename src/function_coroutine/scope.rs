@@ -1,64 +1,143 @@
-use std::time::Duration;
+use std::{
+    ops::{Deref, DerefMut},
+    panic::Location,
+};
 
 use bevy::{
-    ecs::world::unsafe_world_cell::UnsafeWorldCell,
+    ecs::{
+        event::{Event, Events, ManualEventReader},
+        world::unsafe_world_cell::UnsafeWorldCell,
+    },
     prelude::{Commands, Entity},
     utils::synccell::SyncCell,
 };
 
 use crate::{
-    executor::msg::{EmitMsg, NewCoroutine, SignalId},
+    coro_pool::CoroPool,
+    executor::{
+        msg::{EmitMsg, NewCoroutine, SignalId},
+        validate_access,
+    },
+    global_channel::CleanupQueue,
     id_alloc::Id,
+    CoroAccess, SourceId, STRICT_CHECKS,
 };
 
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+use super::await_external::ExternalTask;
 use super::{
     await_all::AwaitAll,
+    await_bridge::Bridge,
+    await_change::AwaitChange,
+    await_condition::AwaitCondition,
+    await_event::{EventCursors, NextEvent, ReadEvents},
     await_first::AwaitFirst,
-    await_time::{DurationFuture, NextTick},
+    await_recv::{Recv, TryRecv},
+    await_stream::CoroStream,
+    await_time::{DurationFuture, IntoDuration, NextTick},
+    borrow_tracker::{Borrow, BorrowTracker, Tracked},
+    coro_param::on_change::ChangeTracker,
     handle::{CoroHandle, HandleTuple},
     once_channel::{sync_once_channel, OnceSender},
     resume::Resume,
-    CoroStatus, CoroutineParamFunction, FunctionCoroutine, ResumeParam,
+    CoroStatus, CoroutineFunction, CoroutineParamFunction, FunctionCoroutine, ResumeParam,
 };
 
+const ERR_NOT_RESUMED: &str = "A scope was used while its coroutine isn't running. Scopes (and \
+sub-scopes) must only be used by their own coroutine.";
+
 /// The first parameter of any [`Coroutine`] It is used to spawn sub-coroutines, yield back to the
 /// scheduler, queue commands and so on. It is the most unsafe part of this library, but once
 /// proper coroutines are implemented in Rust, this would not be the case for the most part.
+///
+/// Reusable pieces of logic are best written as `async fn` taking `&mut Scope`, along with the
+/// parameters they need. They can await anything a coroutine can, and are called without
+/// starting a sub-coroutine:
+///
+/// ```ignore
+/// async fn blink(s: &mut Scope, sprite: &mut Wr<Sprite>, times: u32) {
+///     for _ in 0..times {
+///         sprite.get_mut(s).color.set_a(0.0);
+///         s.duration(0.1).await;
+///         sprite.get_mut(s).color.set_a(1.0);
+///         s.duration(0.1).await;
+///     }
+/// }
+///
+/// async fn on_hit(mut s: Scope, mut sprite: Wr<Sprite>) {
+///     blink(&mut s, &mut sprite, 3).await;
+/// }
+/// ```
+///
+/// Sub-coroutines are only needed to run several things concurrently, with [`Scope::all`] or
+/// [`Scope::first`].
 pub struct Scope {
     id: Id,
     owner: Option<Entity>,
     resume_param: Resume<ResumeParam>,
+    pub(super) borrows: Resume<BorrowTracker>,
+    cleanup: CleanupQueue,
+    pool: CoroPool,
+    pub(super) events: EventCursors,
+    /// The receiver which was last found disconnected by [`Scope::recv`], and whether the
+    /// coroutine was warned about awaiting it again.
+    pub(super) disconnected: Option<usize>,
+    pub(super) warned_disconnected: bool,
 }
 
 impl Scope {
-    pub(crate) fn new(id: Id, owner: Option<Entity>, resume_param: Resume<ResumeParam>) -> Self {
+    pub(crate) fn new(
+        id: Id,
+        owner: Option<Entity>,
+        resume_param: Resume<ResumeParam>,
+        cleanup: CleanupQueue,
+        pool: CoroPool,
+    ) -> Self {
         Self {
             id,
             owner,
             resume_param,
+            borrows: Resume::new(BorrowTracker::default()),
+            cleanup,
+            pool,
+            events: EventCursors::default(),
+            disconnected: None,
+            warned_disconnected: false,
         }
     }
 
     /// Returns a future that resolve once all of the underlying coroutine finishes.
+    #[track_caller]
     pub fn all<H: HandleTuple>(&mut self, handles: H) -> AwaitAll<'_, H> {
+        self.set_suspension_site(Location::caller());
         AwaitAll::new(self, handles)
     }
 
     /// Returns a future that resolve once any of the underlying coroutine finishes. Note that
     /// once this is done, all the others are dropped. The coroutines are resumed from top to
     /// bottom, in case multiple of them are ready to make progress at the same time.
+    #[track_caller]
     pub fn first<const N: usize, T>(&mut self, handles: [CoroHandle<T>; N]) -> AwaitFirst<'_, N, T>
     where
         T: Send + Sync + 'static,
     {
+        self.set_suspension_site(Location::caller());
         AwaitFirst::new(self, handles)
     }
 
-    /// Return a future that resolve once the underlying coroutine finishes.
+    /// Return a future that resolve to the output of the underlying coroutine, once it finishes.
+    /// Sub-coroutines can then be composed like functions:
+    ///
+    /// ```ignore
+    /// let damage = s.start(roll_dice.with_args((3, 6)));
+    /// let damage: u32 = s.on(damage).await;
+    /// ```
+    #[track_caller]
     pub fn on<T>(&mut self, handle: CoroHandle<T>) -> AwaitFirst<'_, 1, T>
     where
         T: Send + Sync + 'static,
     {
+        self.set_suspension_site(Location::caller());
         AwaitFirst::new(self, [handle])
     }
 
@@ -67,16 +146,237 @@ impl Scope {
     /// last frame (delta time).
     ///
     /// [`Executor`]: crate::executor::Executor
+    #[track_caller]
     pub fn next_tick(&mut self) -> NextTick<'_> {
+        self.set_suspension_site(Location::caller());
         NextTick::new(self)
     }
 
-    /// Returns a future that resolve after a certain [`Duration`]. Note that if the duration
-    /// is smaller than the time between two tick of the [`Executor`] it won't be compensated.
+    /// Returns a future that resolve after a certain duration, given as a [`Duration`] or a
+    /// number of seconds. The delta time of each tick of the [`Executor`] is accumulated, so the
+    /// wait follows the [`Time`] resource (or the fixed step of a deterministic executor). Note
+    /// that if the duration is smaller than the time between two tick it won't be compensated.
+    ///
+    /// ```ignore
+    /// s.duration(1.5).await;
+    /// s.duration(Duration::from_secs(2)).await;
+    /// ```
+    ///
+    /// [`Time`]: bevy::time::Time
+    /// [`Duration`]: std::time::Duration
+    /// [`Executor`]: crate::executor::Executor
+    #[track_caller]
+    pub fn duration(&mut self, duration: impl IntoDuration) -> DurationFuture<'_> {
+        self.set_suspension_site(Location::caller());
+        DurationFuture::new(self, duration.into_duration())
+    }
+
+    /// Returns a future that drives `future`, which may not be part of this library, until
+    /// completion. The future is polled once per tick, and waking it up only means it will be
+    /// polled again on the next tick.
+    ///
+    /// Directly awaiting such a future (without this bridge) is a bug, and makes the
+    /// [`Executor`](crate::executor::Executor) panic.
+    #[track_caller]
+    pub fn bridge<F>(&mut self, future: F) -> Bridge<'_, F>
+    where
+        F: std::future::Future + crate::MaybeSend,
+    {
+        self.set_suspension_site(Location::caller());
+        Bridge::new(self, future)
+    }
+
+    /// Spawn `future` on a tokio runtime, and returns a future that resolve once it completes
+    /// (or `None` if the task got dropped before completion).
+    #[cfg(feature = "tokio")]
+    #[track_caller]
+    pub fn spawn_tokio<F>(
+        &mut self,
+        handle: &tokio::runtime::Handle,
+        future: F,
+    ) -> Bridge<'_, ExternalTask<F::Output>>
+    where
+        F: std::future::Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, result) = super::await_external::external_task(future);
+        handle.spawn(task);
+        self.bridge(result)
+    }
+
+    /// Spawn `future` on the async-std runtime, and returns a future that resolve once it
+    /// completes (or `None` if the task got dropped before completion).
+    #[cfg(feature = "async-std")]
+    #[track_caller]
+    pub fn spawn_async_std<F>(&mut self, future: F) -> Bridge<'_, ExternalTask<F::Output>>
+    where
+        F: std::future::Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let (task, result) = super::await_external::external_task(future);
+        async_std::task::spawn(task);
+        self.bridge(result)
+    }
+
+    /// Returns a future that resolve once a value is received from `receiver`, or `None` if the
+    /// channel is disconnected. The channel is checked each time the coroutine is resumed, so
+    /// values sent from another thread are received at most one tick later. The receiver is
+    /// borrowed mutably, as receivers such as [`std::sync::mpsc::Receiver`] aren't `Sync`.
     ///
+    /// Awaiting again a receiver which was found disconnected can never receive anything, so it
+    /// logs a warning, once per coroutine.
+    #[track_caller]
+    pub fn recv<'a, R: TryRecv>(&'a mut self, receiver: &'a mut R) -> Recv<'a, R> {
+        self.set_suspension_site(Location::caller());
+        Recv::new(self, receiver, Location::caller())
+    }
+
+    /// Wraps an external [`Stream`], so that its items can be awaited from within this coroutine
+    /// using [`CoroStream::next`]. The stream is polled each time the coroutine is resumed, and
+    /// never blocks the [`Executor`].
+    ///
+    /// [`Stream`]: futures_core::Stream
     /// [`Executor`]: crate::executor::Executor
-    pub fn duration(&mut self, duration: Duration) -> DurationFuture<'_> {
-        DurationFuture::new(self, duration)
+    pub fn stream<S>(&self, stream: S) -> CoroStream<S>
+    where
+        S: futures_core::Stream + Unpin,
+    {
+        CoroStream::new(self.id, stream)
+    }
+
+    /// Returns a future that resolve the next time the [`ExternalSignal`] `S` is triggered for
+    /// all its waiters, with [`Triggered::All`].
+    ///
+    /// # Panics
+    /// Panics if `S` was never inserted as a resource.
+    ///
+    /// [`ExternalSignal`]: crate::external_signal::ExternalSignal
+    /// [`Triggered::All`]: crate::external_signal::Triggered::All
+    #[track_caller]
+    pub fn on_external<S: crate::external_signal::ExternalSignal>(&mut self) -> AwaitChange<'_> {
+        self.try_on_external::<S>()
+            .expect("The external signal must be inserted as a resource before being awaited")
+    }
+
+    /// Like [`on_external`](Scope::on_external), but returns `None` instead of panicking if `S`
+    /// was never inserted as a resource.
+    #[track_caller]
+    pub fn try_on_external<S: crate::external_signal::ExternalSignal>(
+        &mut self,
+    ) -> Option<AwaitChange<'_>> {
+        self.set_suspension_site(Location::caller());
+        self.external_signal::<S>(None)
+    }
+
+    /// Returns a future that resolve the next time the [`ExternalSignal`] `S` is triggered with
+    /// `key`, so that a source of wake ups can resume a single waiter, such as the coroutine
+    /// which sent a request. It isn't resolved when `S` is triggered for all its waiters.
+    ///
+    /// # Panics
+    /// Panics if `S` was never inserted as a resource.
+    ///
+    /// [`ExternalSignal`]: crate::external_signal::ExternalSignal
+    #[track_caller]
+    pub fn on_external_key<S: crate::external_signal::ExternalSignal>(
+        &mut self,
+        key: u64,
+    ) -> AwaitChange<'_> {
+        self.set_suspension_site(Location::caller());
+        self.external_signal::<S>(Some(key))
+            .expect("The external signal must be inserted as a resource before being awaited")
+    }
+
+    fn external_signal<S: crate::external_signal::ExternalSignal>(
+        &mut self,
+        key: Option<u64>,
+    ) -> Option<AwaitChange<'_>> {
+        let signal_type = self.world_cell().components().resource_id::<S>()?;
+
+        Some(AwaitChange::new(
+            self,
+            SignalId {
+                signal_type,
+                owner: None,
+                external: true,
+                key,
+            },
+        ))
+    }
+
+    /// Returns a future that resolve once `condition` returns `Some`. The condition is checked
+    /// right away, and then once per tick.
+    #[track_caller]
+    pub fn until<F, T>(&mut self, condition: F) -> AwaitCondition<'_, F>
+    where
+        F: FnMut(&bevy::prelude::World) -> Option<T>,
+    {
+        self.set_suspension_site(Location::caller());
+        AwaitCondition::new(self, condition)
+    }
+
+    /// Returns a future that resolve with the next event `E`. The scope keeps a cursor for each
+    /// type of event, so the events sent while the coroutine is doing something else are
+    /// returned by the following calls, as long as it waits again within two updates of
+    /// [`Events<E>`]. The first call only sees the events sent from then on.
+    ///
+    /// ```ignore
+    /// loop {
+    ///     let hit = s.next_event::<Hit>().await;
+    ///     health.get_mut(&mut s).0 -= hit.damage;
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// The future panics if the event `E` was never added to the app.
+    #[track_caller]
+    pub fn next_event<E: Event + Clone>(&mut self) -> NextEvent<'_, E, impl ReadEvents<E, E>> {
+        self.next_event_where(|e: &E| Some(e.clone()))
+    }
+
+    /// Like [`next_event`](Scope::next_event), but returns `None` instead of panicking if the
+    /// event `E` was never added to the app.
+    #[track_caller]
+    pub fn try_next_event<E: Event + Clone>(
+        &mut self,
+    ) -> Option<NextEvent<'_, E, impl ReadEvents<E, E>>> {
+        if !self.world().contains_resource::<Events<E>>() {
+            return None;
+        }
+
+        Some(self.next_event())
+    }
+
+    /// Like [`next_event`](Scope::next_event), but resolve with all the events `E` sent since the
+    /// last call, once there is at least one.
+    #[track_caller]
+    pub fn next_events<E: Event + Clone>(
+        &mut self,
+    ) -> NextEvent<'_, E, impl ReadEvents<E, Vec<E>>> {
+        self.set_suspension_site(Location::caller());
+        NextEvent::new(
+            self,
+            |cursor: &mut ManualEventReader<E>, events: &Events<E>| {
+                let batch: Vec<E> = cursor.iter(events).cloned().collect();
+                (!batch.is_empty()).then_some(batch)
+            },
+        )
+    }
+
+    /// Like [`next_event`](Scope::next_event), but resolve with the first event for which
+    /// `filter` returns `Some`. The events before it are skipped, the ones after it are left for
+    /// the following waits.
+    #[track_caller]
+    pub(crate) fn next_event_where<E: Event, T>(
+        &mut self,
+        mut filter: impl FnMut(&E) -> Option<T>,
+    ) -> NextEvent<'_, E, impl ReadEvents<E, T>> {
+        self.set_suspension_site(Location::caller());
+        NextEvent::new(
+            self,
+            move |cursor: &mut ManualEventReader<E>, events: &Events<E>| {
+                cursor.iter(events).find_map(&mut filter)
+            },
+        )
     }
 
     /// Start the `coroutine` when reaching the next `await`. When the scope is dropped, the
@@ -89,11 +389,12 @@ impl Scope {
         C: CoroutineParamFunction<Marker, T>,
         T: Sync + Send + 'static,
     {
-        self.build_coroutine(self.owner, true, Some(self.id), None, coroutine);
+        self.build_coroutine(self.owner, true, Some(self.id), None, false, coroutine);
     }
 
     /// Start the `coroutine` when reaching the next `await`, and returns a [`CoroHandle`] to it.
-    /// When the handle is dropped, the `coroutine` is automatically dropped as well.
+    /// When the handle is dropped, the `coroutine` is automatically dropped as well. Coroutines
+    /// picked at runtime can be given as a [`BoxedCoroutine`](super::BoxedCoroutine).
     ///
     /// Note: If the coroutine is invalid (with conflicting parameters for instance), this function
     /// panics.
@@ -115,10 +416,32 @@ impl Scope {
         T: Sync + Send + 'static,
     {
         let (result_sender, receiver) = sync_once_channel();
-        let id = self.build_coroutine(self.owner, true, None, Some(result_sender), coroutine)?;
+        let id = self.build_coroutine(
+            self.owner,
+            true,
+            None,
+            Some(result_sender),
+            false,
+            coroutine,
+        )?;
         Some(CoroHandle::Waiting { id, receiver })
     }
 
+    /// Like [`start`](Scope::start), but `coroutine` doesn't need to be [`Send`]: it can hold an
+    /// `Rc` or a UI context across awaits. It is always resumed on the current thread, even when
+    /// the executor resumes the others in parallel, and panics if that isn't possible.
+    pub fn start_non_send<Marker: 'static, T, C>(&mut self, coroutine: C) -> CoroHandle<T>
+    where
+        C: CoroutineFunction<Marker, T>,
+        T: Sync + Send + 'static,
+    {
+        let (result_sender, receiver) = sync_once_channel();
+        let id = self
+            .build_coroutine(self.owner, true, None, Some(result_sender), true, coroutine)
+            .unwrap();
+        CoroHandle::Waiting { id, receiver }
+    }
+
     /// Start the `coroutine` when reaching the next `await`. The coroutine cannot be dropped, and
     /// will be run until completion. This is unstructured and must be used with caution.
     ///
@@ -129,10 +452,60 @@ impl Scope {
         C: CoroutineParamFunction<Marker, T>,
         T: Sync + Send + 'static,
     {
-        self.build_coroutine(None, true, None, None, coroutine);
+        self.build_coroutine(None, true, None, None, false, coroutine);
+    }
+
+    /// Returns a guard which runs `f` when dropped, at the end of the current tick. Since the
+    /// guard is dropped with the coroutine holding it, this is how a coroutine can restore some
+    /// state whether it finishes normally or gets canceled.
+    pub fn cleanup_guard<F>(&self, f: F) -> CleanupGuard
+    where
+        F: FnOnce(&mut bevy::prelude::World) + Send + 'static,
+    {
+        CleanupGuard {
+            queue: self.cleanup.clone(),
+            f: Some(Box::new(f)),
+        }
     }
 
-    /// Returns the [`Entity`] owning this [`Coroutine`], if it exists.
+    /// Returns another handle to this scope, which can be stored by helpers that need to await on
+    /// their own, instead of borrowing the scope each time:
+    ///
+    /// ```ignore
+    /// struct Dialogue {
+    ///     s: SubScope,
+    ///     lines: Vec<String>,
+    /// }
+    ///
+    /// impl Dialogue {
+    ///     async fn play(&mut self) {
+    ///         for line in &self.lines {
+    ///             show(line);
+    ///             self.s.duration(2.0).await;
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// The [`SubScope`] belongs to the same coroutine, and must not be given to another one. Only
+    /// one of the handles of a coroutine can be awaiting at a time: awaiting on both the scope and
+    /// a sub-scope concurrently (with a `join` for instance) is a bug, which panics in debug
+    /// builds.
+    pub fn sub_scope(&self) -> SubScope {
+        SubScope(Self {
+            id: self.id,
+            owner: self.owner,
+            resume_param: self.resume_param.clone(),
+            borrows: self.borrows.clone(),
+            cleanup: self.cleanup.clone(),
+            pool: self.pool.clone(),
+            events: EventCursors::default(),
+        })
+    }
+
+    /// Returns the [`Entity`] owning this [`Coroutine`], if it exists. The coroutines started
+    /// from this scope have the same owner, which is given a
+    /// [`CoroutineOwner`](crate::executor::owners::CoroutineOwner) marker component.
     pub fn owner(&self) -> Option<Entity> {
         self.owner
     }
@@ -150,25 +523,105 @@ impl Scope {
     }
 
     pub fn bind_coroutine<Marker: 'static, T, C>(&self, to: Entity, coroutine: C) -> CoroHandle<T>
+    where
+        C: CoroutineParamFunction<Marker, T>,
+        T: Sync + Send + 'static,
+    {
+        self.try_bind_coroutine(to, coroutine).unwrap()
+    }
+
+    /// Like [`bind_coroutine`](Scope::bind_coroutine), but returns `None` instead of panicking
+    /// if the coroutine is invalid (if `to` doesn't have the components it accesses for
+    /// instance).
+    pub fn try_bind_coroutine<Marker: 'static, T, C>(
+        &self,
+        to: Entity,
+        coroutine: C,
+    ) -> Option<CoroHandle<T>>
     where
         C: CoroutineParamFunction<Marker, T>,
         T: Sync + Send + 'static,
     {
         let (sender, receiver) = sync_once_channel();
-        let id = self
-            .build_coroutine(Some(to), false, Some(self.id), Some(sender), coroutine)
-            .unwrap();
-        CoroHandle::Waiting { id, receiver }
+        let id = self.build_coroutine(
+            Some(to),
+            false,
+            Some(self.id),
+            Some(sender),
+            false,
+            coroutine,
+        )?;
+        Some(CoroHandle::Waiting { id, receiver })
     }
 
+    /// The world the coroutine is resumed in. Coroutines resumed in parallel share it, so it must
+    /// only be used through the accessors of [`UnsafeWorldCell`] for what the coroutine declared,
+    /// either in its parameters or through [`validate_access`].
     pub(crate) fn world_cell(&self) -> UnsafeWorldCell<'_> {
+        self.check_resumed();
+        // Safety: The world is set for the whole resume, and the scope can only be used by its
+        // coroutine while it is resumed.
+        unsafe { self.resume_param.get().world }.expect(ERR_NOT_RESUMED)
+    }
+
+    /// Wrap `value` in a [`Tracked`] reference, see [`borrow_tracker`](super::borrow_tracker).
+    #[track_caller]
+    pub(crate) fn track<R>(&self, value: R, borrow: Borrow) -> Tracked<R> {
+        // Safety: The tracker is only ever accessed through shared references
+        Tracked::new(value, unsafe { self.borrows.get() }, borrow)
+    }
+
+    /// Returns a readonly view of the world. Like [`Rd::get`](super::coro_param::component::Rd),
+    /// the result cannot be held across any await.
+    #[track_caller]
+    pub(crate) fn world(&self) -> Tracked<&bevy::prelude::World> {
+        validate_access(self.id, CoroAccess::add_world_read);
+        // Safety: The world is only mutated between two resumes, or through the scope which is
+        // borrowed here. In a parallel batch, the read was rejected above if another coroutine
+        // of the batch writes to the world.
+        self.track(unsafe { self.world_cell().world() }, Borrow::World)
+    }
+
+    /// Returns the component `C` of `entity` mutably. Like [`Wr::get_mut`], the result cannot be
+    /// held across any await.
+    ///
+    /// [`Wr::get_mut`]: super::coro_param::component::Wr::get_mut
+    #[track_caller]
+    pub(crate) fn component_mut<C: bevy::prelude::Component>(
+        &mut self,
+        entity: Entity,
+    ) -> Option<Tracked<bevy::prelude::Mut<'_, C>>> {
+        // Safety: The scope is borrowed mutably, so the coroutine cannot hold any other reference
+        // to the world.
         unsafe {
-            self.resume_param
-                .get()
-                .world
-                .as_mut()
-                .unwrap()
-                .as_unsafe_world_cell()
+            let cell = self.world_cell();
+            let component_id = cell.components().component_id::<C>()?;
+            validate_access(self.id, |access| {
+                access.add_write(SourceId::Entity(entity), component_id);
+            });
+            let entity_cell = cell.get_entity(entity)?;
+            let component = entity_cell.get_mut::<C>()?;
+            if entity_cell.contains::<ChangeTracker<C>>() {
+                self.emit_signal(SignalId {
+                    signal_type: component_id,
+                    owner: Some(entity),
+                    external: false,
+                    key: None,
+                });
+            }
+            Some(self.track(component, Borrow::Write(entity, component_id)))
+        }
+    }
+
+    /// Modify the component `C` of `entity` right away. Nothing happens if the entity doesn't
+    /// have such component.
+    pub(crate) fn modify<C, F>(&mut self, entity: Entity, f: F)
+    where
+        C: bevy::prelude::Component,
+        F: FnOnce(&mut C),
+    {
+        if let Some(mut c) = self.component_mut::<C>(entity) {
+            f(&mut c);
         }
     }
 
@@ -182,11 +635,57 @@ impl Scope {
         };
     }
 
+    /// Set what happens to this coroutine if its owner entity is despawned, overriding the policy
+    /// of the [`Executor`](crate::executor::Executor).
+    pub fn set_despawn_policy(&mut self, policy: crate::executor::DespawnPolicy) {
+        // Safety: Same as `yield_`
+        unsafe {
+            self.resume_param.get_mut().despawn_policy = Some(policy);
+        }
+    }
+
+    /// Set the despawn policy of the wait the coroutine is about to be suspended on.
+    pub(crate) fn set_wait_despawn_policy(&mut self, policy: crate::executor::DespawnPolicy) {
+        // Safety: Same as `yield_`
+        unsafe {
+            self.resume_param.get_mut().wait_despawn_policy = Some(policy);
+        }
+    }
+
+    /// Remember where the coroutine is about to be suspended, see [`Executor::info`].
+    ///
+    /// [`Executor::info`]: crate::executor::Executor::info
+    pub(crate) fn set_suspension_site(&mut self, location: &'static Location<'static>) {
+        // Safety: Same as `yield_`
+        unsafe {
+            self.resume_param.get_mut().suspended_at = Some(location);
+        }
+    }
+
     /// Yield with the following status
     pub(crate) fn yield_(&mut self, status: CoroStatus) {
+        self.check_resumed();
         // Safety: When polled, the scope owns CoroParam which own each parameter
         unsafe {
-            self.resume_param.get_mut().yield_sender = Some(status);
+            let param = self.resume_param.get_mut();
+            // Otherwise one of the awaits would never be woken up
+            debug_assert!(
+                param.yield_sender.is_none(),
+                "A coroutine awaited two things at once{}, for instance by joining futures using \
+                both its scope and a sub-scope. Only one await can be pending at a time.",
+                param
+                    .suspended_at
+                    .map_or(String::new(), |l| format!(" (last at {l})"))
+            );
+            param.yield_sender = Some(status);
+        }
+    }
+
+    /// With [`STRICT_CHECKS`], panic if the coroutine of this scope isn't being resumed.
+    fn check_resumed(&self) {
+        // Safety: Only reads whether the world is set, which happens before each resume.
+        if STRICT_CHECKS && unsafe { self.resume_param.get() }.world.is_none() {
+            panic!("{ERR_NOT_RESUMED}");
         }
     }
 
@@ -207,8 +706,19 @@ impl Scope {
         unsafe { self.resume_param.get().ids.as_ref().unwrap().allocate_id() }
     }
 
+    pub(crate) fn id(&self) -> Id {
+        self.id
+    }
+
     pub(crate) fn check_ownership(&self, other_id: Id) {
-        debug_assert_eq!(self.id, other_id);
+        if STRICT_CHECKS {
+            assert_eq!(
+                self.id, other_id,
+                "A coroutine parameter was used with the scope of another coroutine"
+            );
+        } else {
+            debug_assert_eq!(self.id, other_id);
+        }
     }
 
     /// Build a new coroutine with various parameter
@@ -218,10 +728,11 @@ impl Scope {
         start_now: bool,
         parent_scope: Option<Id>,
         result_sender: Option<OnceSender<T>>,
+        non_send: bool,
         coroutine: C,
     ) -> Option<Id>
     where
-        C: CoroutineParamFunction<Marker, T>,
+        C: CoroutineFunction<Marker, T>,
         T: Sync + Send + 'static,
     {
         let resume_param = Resume::new(ResumeParam::new());
@@ -229,11 +740,15 @@ impl Scope {
             id: self.alloc_id(),
             owner,
             resume_param: resume_param.clone(),
+            borrows: Resume::new(BorrowTracker::default()),
+            cleanup: self.cleanup.clone(),
+            pool: self.pool.clone(),
+            events: EventCursors::default(),
         };
 
         let new_id = new_scope.id;
 
-        let coroutine = FunctionCoroutine::new(
+        let mut coroutine = FunctionCoroutine::new(
             new_scope,
             self.world_cell(),
             resume_param,
@@ -241,12 +756,16 @@ impl Scope {
             result_sender,
             coroutine,
         )?;
+        if non_send {
+            coroutine = coroutine.bind_to_current_thread();
+        }
 
         self.send_new_coro(NewCoroutine {
             id: new_id,
             ran_after: self.curr_node(),
-            coroutine: SyncCell::new(Box::pin(coroutine)),
+            coroutine: SyncCell::new(self.pool.alloc(coroutine)),
             is_owned_by: parent_scope,
+            started_by: self.id,
             should_start_now: start_now,
         });
 
@@ -260,6 +779,47 @@ impl Scope {
 
 unsafe impl Send for Scope {}
 
+/// A handle to the [`Scope`] of a coroutine, obtained with [`Scope::sub_scope`]. It can do
+/// everything the scope can.
+pub struct SubScope(Scope);
+
+impl Deref for SubScope {
+    type Target = Scope;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SubScope {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Runs a cleanup operation when dropped. See [`Scope::cleanup_guard`].
+#[must_use = "the cleanup runs as soon as the guard is dropped"]
+pub struct CleanupGuard {
+    queue: CleanupQueue,
+    #[allow(clippy::type_complexity)]
+    f: Option<Box<dyn FnOnce(&mut bevy::prelude::World) + Send>>,
+}
+
+impl CleanupGuard {
+    /// Drop the guard without running the cleanup.
+    pub fn disarm(mut self) {
+        self.f = None;
+    }
+}
+
+impl Drop for CleanupGuard {
+    fn drop(&mut self) {
+        if let Some(f) = self.f.take() {
+            self.queue.push(f);
+        }
+    }
+}
+
 //pub struct DeferredOps<'a> {
 //    scope: &'a Scope,
 //    queue: CommandQueue,
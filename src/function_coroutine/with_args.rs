@@ -0,0 +1,64 @@
+use std::future::Future;
+
+use super::{scope::Scope, CoroutineFunction};
+
+/// Functions taking extra arguments after their [`Scope`], which are given when the coroutine is
+/// started instead of being captured by a closure.
+///
+/// ```ignore
+/// async fn boss_intro(mut s: Scope, boss: Entity, speed: f32) {
+///     // ...
+/// }
+///
+/// commands.add(root_coroutine(boss_intro.with_args((boss, 2.0))));
+/// ```
+pub trait IntoCoroutine<Args>: Sized {
+    /// Bind the extra arguments of this function, to get a coroutine.
+    fn with_args(self, args: Args) -> WithArgs<Self, Args> {
+        WithArgs { f: self, args }
+    }
+}
+
+/// A function and the extra arguments it will be called with. See [`IntoCoroutine`].
+pub struct WithArgs<F, Args> {
+    f: F,
+    args: Args,
+}
+
+#[doc(hidden)]
+pub struct WithArgsMarker<M>(M);
+
+macro_rules! impl_with_args {
+    ($($arg: ident),*) => {
+        impl<F, Fut, $($arg),*> IntoCoroutine<($($arg,)*)> for F
+        where
+            F: FnOnce(Scope, $($arg),*) -> Fut,
+        {
+        }
+
+        #[allow(non_snake_case)]
+        impl<F, Fut, T, $($arg),*> CoroutineFunction<WithArgsMarker<fn($($arg,)*) -> Fut>, T>
+            for WithArgs<F, ($($arg,)*)>
+        where
+            F: FnOnce(Scope, $($arg),*) -> Fut + 'static,
+            Fut: Future<Output = T> + 'static,
+            $($arg: 'static,)*
+            T: Send + Sync + 'static,
+        {
+            type Future = Fut;
+            type Params = ();
+
+            fn init(self, scope: Scope, _: ()) -> Self::Future {
+                let ($($arg,)*) = self.args;
+                (self.f)(scope, $($arg),*)
+            }
+        }
+    };
+}
+
+impl_with_args!(A);
+impl_with_args!(A, B);
+impl_with_args!(A, B, C);
+impl_with_args!(A, B, C, D);
+impl_with_args!(A, B, C, D, E);
+impl_with_args!(A, B, C, D, E, G);
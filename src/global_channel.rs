@@ -1,4 +1,7 @@
-use std::cell::UnsafeCell;
+use std::{
+    cell::UnsafeCell,
+    sync::mpsc::{self, Receiver, Sender},
+};
 
 use bevy::{
     ecs::{
@@ -6,6 +9,7 @@ use bevy::{
         system::{Command, CommandQueue},
     },
     prelude::{Commands, World},
+    utils::synccell::SyncCell,
 };
 use thread_local::ThreadLocal;
 
@@ -53,3 +57,50 @@ impl CommandChannel {
         }
     }
 }
+
+type Cleanup = Box<dyn FnOnce(&mut World) + Send>;
+
+/// A queue of cleanup operations, which can be pushed to from anywhere (including when a
+/// coroutine is dropped), and are applied at the end of each tick of the executor.
+///
+/// Pushing doesn't take any lock. Only the queue owned by the executor can apply the operations,
+/// the clones given to the scopes can only push.
+pub struct CleanupQueue {
+    sender: Sender<Cleanup>,
+    receiver: Option<SyncCell<Receiver<Cleanup>>>,
+}
+
+impl Default for CleanupQueue {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver: Some(SyncCell::new(receiver)),
+        }
+    }
+}
+
+impl Clone for CleanupQueue {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            receiver: None,
+        }
+    }
+}
+
+impl CleanupQueue {
+    pub fn push(&self, f: impl FnOnce(&mut World) + Send + 'static) {
+        // Fails only if the executor is gone, and with it the world to clean up
+        let _ = self.sender.send(Box::new(f));
+    }
+
+    pub fn apply(&mut self, world: &mut World) {
+        let Some(receiver) = &mut self.receiver else {
+            return;
+        };
+        for f in receiver.get().try_iter() {
+            f(world);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::{Gamepad, GamepadButton, GamepadButtonType, Gamepads, Input, World},
+};
+
+use crate::{
+    expect_resource,
+    function_coroutine::{
+        await_condition::AwaitCondition,
+        await_event::{NextEvent, ReadEvents},
+        scope::Scope,
+    },
+};
+
+impl Scope {
+    /// Returns a future that resolve the next time `button` is pressed on `gamepad`.
+    pub fn gamepad_button(
+        &mut self,
+        gamepad: Gamepad,
+        button: GamepadButtonType,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            expect_resource::<Input<GamepadButton>>(world)
+                .just_pressed(GamepadButton::new(gamepad, button))
+                .then_some(())
+        })
+    }
+
+    /// Returns a future that resolve the next time `button` is pressed on any connected gamepad,
+    /// and returns that gamepad. Useful for "press A to join" screens.
+    pub fn any_gamepad_button(
+        &mut self,
+        button: GamepadButtonType,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Gamepad>> {
+        self.until(move |world| {
+            let input = expect_resource::<Input<GamepadButton>>(world);
+            expect_resource::<Gamepads>(world)
+                .iter()
+                .find(|gamepad| input.just_pressed(GamepadButton::new(*gamepad, button)))
+        })
+    }
+
+    /// Returns a future that resolve the next time a gamepad is connected, and returns it.
+    pub fn gamepad_connected(
+        &mut self,
+    ) -> NextEvent<'_, GamepadConnectionEvent, impl ReadEvents<GamepadConnectionEvent, Gamepad>>
+    {
+        self.next_event_where(|e: &GamepadConnectionEvent| {
+            matches!(e.connection, GamepadConnection::Connected(_)).then_some(e.gamepad)
+        })
+    }
+
+    /// Returns a future that resolve once `gamepad` is disconnected. If it is not connected, the
+    /// future resolve immediately.
+    pub fn gamepad_disconnected(
+        &mut self,
+        gamepad: Gamepad,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            (!expect_resource::<Gamepads>(world).contains(gamepad)).then_some(())
+        })
+    }
+}
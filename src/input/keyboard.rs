@@ -0,0 +1,152 @@
+use std::time::Duration;
+
+use bevy::prelude::{Input, KeyCode, World};
+
+use crate::{
+    executor::TickTime,
+    expect_resource,
+    function_coroutine::{await_condition::AwaitCondition, await_time::IntoDuration, scope::Scope},
+};
+
+impl Scope {
+    /// Returns a future that resolve the next time `key` is pressed.
+    pub fn key_just_pressed(
+        &mut self,
+        key: KeyCode,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            expect_resource::<Input<KeyCode>>(world)
+                .just_pressed(key)
+                .then_some(())
+        })
+    }
+
+    /// Returns a future that resolve the next time `key` is released.
+    pub fn key_just_released(
+        &mut self,
+        key: KeyCode,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            expect_resource::<Input<KeyCode>>(world)
+                .just_released(key)
+                .then_some(())
+        })
+    }
+
+    /// Returns a future that resolve once `key` is held down. If it is already held, the future
+    /// resolve immediately.
+    pub fn key_pressed(
+        &mut self,
+        key: KeyCode,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            expect_resource::<Input<KeyCode>>(world)
+                .pressed(key)
+                .then_some(())
+        })
+    }
+
+    /// Returns a future that resolve the next time any key is pressed, and returns that key.
+    pub fn any_key_just_pressed(
+        &mut self,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<KeyCode>> {
+        self.until(|world| {
+            expect_resource::<Input<KeyCode>>(world)
+                .get_just_pressed()
+                .next()
+                .copied()
+        })
+    }
+}
+
+/// A condition on the keyboard state, built from one or more slots which must all be held at the
+/// same time. Each slot is satisfied by any of its keys, which allows to express both
+/// combinations ("Ctrl+S") and alternatives ("left or right Ctrl").
+///
+/// By default, the condition is met on the frame where the last missing key gets pressed. With
+/// [`held_for`](KeyCondition::held_for), it is met once all the slots have been held continuously
+/// for some time instead.
+///
+/// ```ignore
+/// let save = KeyCondition::key(KeyCode::S).and_any([KeyCode::ControlLeft, KeyCode::ControlRight]);
+/// s.key_condition(save).await;
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct KeyCondition {
+    slots: Vec<Vec<KeyCode>>,
+    hold_for: Option<Duration>,
+}
+
+impl KeyCondition {
+    /// A condition met when `key` is pressed.
+    pub fn key(key: KeyCode) -> Self {
+        Self::default().and(key)
+    }
+
+    /// A condition met when any of `keys` is pressed.
+    pub fn any_of(keys: impl IntoIterator<Item = KeyCode>) -> Self {
+        Self::default().and_any(keys)
+    }
+
+    /// A condition met when all of `keys` are held at the same time.
+    pub fn chord(keys: impl IntoIterator<Item = KeyCode>) -> Self {
+        keys.into_iter().fold(Self::default(), Self::and)
+    }
+
+    /// Also require `key` to be held.
+    pub fn and(self, key: KeyCode) -> Self {
+        self.and_any([key])
+    }
+
+    /// Also require any of `keys` to be held.
+    pub fn and_any(mut self, keys: impl IntoIterator<Item = KeyCode>) -> Self {
+        self.slots.push(keys.into_iter().collect());
+        self
+    }
+
+    /// Only met once all the keys have been held continuously for `duration`.
+    pub fn held_for(mut self, duration: impl IntoDuration) -> Self {
+        let duration = duration.into_duration();
+        self.hold_for = Some(duration);
+        self
+    }
+
+    /// Returns true if every slot has at least one key held.
+    fn is_held(&self, input: &Input<KeyCode>) -> bool {
+        self.slots
+            .iter()
+            .all(|slot| input.any_pressed(slot.iter().copied()))
+    }
+
+    /// Returns true if any key of any slot was pressed this frame.
+    fn is_just_pressed(&self, input: &Input<KeyCode>) -> bool {
+        self.slots
+            .iter()
+            .any(|slot| input.any_just_pressed(slot.iter().copied()))
+    }
+}
+
+impl Scope {
+    /// Returns a future that resolve once `condition` is met.
+    pub fn key_condition(
+        &mut self,
+        condition: KeyCondition,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        let mut held = Duration::ZERO;
+        self.until(move |world| {
+            let input = expect_resource::<Input<KeyCode>>(world);
+            if !condition.is_held(input) {
+                held = Duration::ZERO;
+                return None;
+            }
+
+            match condition.hold_for {
+                Some(duration) => {
+                    held += TickTime::get(world).delta;
+                    (held >= duration).then_some(())
+                }
+                None => condition.is_just_pressed(input).then_some(()),
+            }
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::{Entity, World};
+use leafwing_input_manager::{action_state::ActionState, Actionlike};
+
+use crate::{
+    expect_resource,
+    function_coroutine::{await_condition::AwaitCondition, scope::Scope},
+};
+
+impl Scope {
+    /// Returns a future that resolve the next time `action` is pressed, according to the global
+    /// [`ActionState<A>`] resource.
+    pub fn action_just_pressed<A: Actionlike>(
+        &mut self,
+        action: A,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            expect_resource::<ActionState<A>>(world)
+                .just_pressed(action.clone())
+                .then_some(())
+        })
+    }
+
+    /// Returns a future that resolve the next time `action` is released, according to the global
+    /// [`ActionState<A>`] resource.
+    pub fn action_just_released<A: Actionlike>(
+        &mut self,
+        action: A,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            expect_resource::<ActionState<A>>(world)
+                .just_released(action.clone())
+                .then_some(())
+        })
+    }
+
+    /// Returns a future that resolve the next time `action` is pressed, according to the
+    /// [`ActionState<A>`] component of `entity`. Never resolve if `entity` has no such component.
+    pub fn action_just_pressed_on<A: Actionlike>(
+        &mut self,
+        entity: Entity,
+        action: A,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            world
+                .get::<ActionState<A>>(entity)?
+                .just_pressed(action.clone())
+                .then_some(())
+        })
+    }
+
+    /// Returns a future that resolve the next time `action` is released, according to the
+    /// [`ActionState<A>`] component of `entity`. Never resolve if `entity` has no such component.
+    pub fn action_just_released_on<A: Actionlike>(
+        &mut self,
+        entity: Entity,
+        action: A,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            world
+                .get::<ActionState<A>>(entity)?
+                .just_released(action.clone())
+                .then_some(())
+        })
+    }
+}
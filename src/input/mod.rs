@@ -0,0 +1,55 @@
+//! Waits on user inputs and window events. All of these are checked once per tick, so they must
+//! be awaited from a coroutine running after the input systems (which is the case when using
+//! [`CorentinPlugin`](crate::plugin::CorentinPlugin)).
+//!
+//! They read the input resources of bevy, such as `Input<KeyCode>`, and panic if these are
+//! missing, which only happens when the input plugin wasn't added. A wait which must survive
+//! that can use [`Scope::until`](crate::prelude::Scope::until) with
+//! [`World::get_resource`] instead, and the event waits have
+//! [`Scope::try_next_event`](crate::prelude::Scope::try_next_event).
+
+use bevy::{
+    prelude::{Entity, World},
+    window::{PrimaryWindow, Window},
+};
+
+pub mod gamepad;
+pub mod keyboard;
+#[cfg(feature = "leafwing")]
+pub mod leafwing;
+pub mod mouse;
+pub mod sequence;
+pub mod touch;
+pub mod ui;
+pub mod window;
+
+pub mod prelude {
+    #[doc(hidden)]
+    pub use super::keyboard::KeyCondition;
+
+    #[doc(hidden)]
+    pub use super::sequence::{InputSequence, InputStep, SequenceError};
+
+    #[doc(hidden)]
+    pub use super::touch::Drag;
+
+    #[doc(hidden)]
+    pub use super::ui::DragAndDrop;
+}
+
+/// Returns the primary [`Window`], caching its entity in `cache`.
+pub(crate) fn primary_window<'w>(
+    world: &'w World,
+    cache: &mut Option<Entity>,
+) -> Option<&'w Window> {
+    if let Some(window) = cache.and_then(|e| world.get::<Window>(e)) {
+        return Some(window);
+    }
+
+    let entity = world
+        .iter_entities()
+        .find(|e| e.contains::<PrimaryWindow>() && e.contains::<Window>())?
+        .id();
+    *cache = Some(entity);
+    world.get::<Window>(entity)
+}
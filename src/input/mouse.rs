@@ -0,0 +1,93 @@
+use bevy::{
+    input::mouse::MouseWheel,
+    prelude::{Input, MouseButton, Rect, Vec2, World},
+};
+
+use crate::{
+    expect_resource,
+    function_coroutine::{
+        await_condition::AwaitCondition,
+        await_event::{NextEvent, ReadEvents},
+        scope::Scope,
+    },
+};
+
+use super::primary_window;
+
+impl Scope {
+    /// Returns a future that resolve the next time `button` is pressed, with the position of the
+    /// cursor in the primary window (if it is inside the window).
+    pub fn mouse_just_pressed(
+        &mut self,
+        button: MouseButton,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Option<Vec2>>> {
+        let mut window = None;
+        self.until(move |world| {
+            if !expect_resource::<Input<MouseButton>>(world).just_pressed(button) {
+                return None;
+            }
+            Some(primary_window(world, &mut window).and_then(|w| w.cursor_position()))
+        })
+    }
+
+    /// Returns a future that resolve the next time `button` is released, with the position of the
+    /// cursor in the primary window (if it is inside the window).
+    pub fn mouse_just_released(
+        &mut self,
+        button: MouseButton,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Option<Vec2>>> {
+        let mut window = None;
+        self.until(move |world| {
+            if !expect_resource::<Input<MouseButton>>(world).just_released(button) {
+                return None;
+            }
+            Some(primary_window(world, &mut window).and_then(|w| w.cursor_position()))
+        })
+    }
+
+    /// Returns a future that resolve the next time the cursor enters `region` (in logical
+    /// window coordinates), with the position of the cursor. If the cursor is already inside the
+    /// region, it must leave it first.
+    pub fn cursor_entered(
+        &mut self,
+        region: Rect,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Vec2>> {
+        let mut window = None;
+        let mut was_inside = None;
+        self.until(move |world| {
+            let position = primary_window(world, &mut window).and_then(|w| w.cursor_position());
+            let inside = position.is_some_and(|p| region.contains(p));
+            let entered = was_inside == Some(false) && inside;
+            was_inside = Some(inside);
+            if entered {
+                position
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns a future that resolve the next time the cursor leaves `region` (in logical
+    /// window coordinates), with the last position of the cursor if it is still in the window.
+    pub fn cursor_left(
+        &mut self,
+        region: Rect,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Option<Vec2>>> {
+        let mut window = None;
+        let mut was_inside = None;
+        self.until(move |world| {
+            let position = primary_window(world, &mut window).and_then(|w| w.cursor_position());
+            let inside = position.is_some_and(|p| region.contains(p));
+            let left = was_inside == Some(true) && !inside;
+            was_inside = Some(inside);
+            left.then_some(position)
+        })
+    }
+
+    /// Returns a future that resolve with the next [`MouseWheel`] event.
+    pub fn mouse_wheel(
+        &mut self,
+    ) -> NextEvent<'_, MouseWheel, impl ReadEvents<MouseWheel, MouseWheel>> {
+        self.next_event_where(|e: &MouseWheel| Some(*e))
+    }
+}
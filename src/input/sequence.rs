@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use bevy::prelude::{GamepadButton, Input, KeyCode, MouseButton, World};
+
+use crate::{
+    executor::TickTime,
+    expect_resource,
+    function_coroutine::{await_condition::AwaitCondition, await_time::IntoDuration, scope::Scope},
+};
+
+/// A single input of an [`InputSequence`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputStep {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl From<KeyCode> for InputStep {
+    fn from(key: KeyCode) -> Self {
+        InputStep::Key(key)
+    }
+}
+
+impl From<MouseButton> for InputStep {
+    fn from(button: MouseButton) -> Self {
+        InputStep::Mouse(button)
+    }
+}
+
+impl From<GamepadButton> for InputStep {
+    fn from(button: GamepadButton) -> Self {
+        InputStep::Gamepad(button)
+    }
+}
+
+impl InputStep {
+    fn just_pressed(&self, world: &World) -> bool {
+        match self {
+            InputStep::Key(key) => expect_resource::<Input<KeyCode>>(world).just_pressed(*key),
+            InputStep::Mouse(button) => {
+                expect_resource::<Input<MouseButton>>(world).just_pressed(*button)
+            }
+            InputStep::Gamepad(button) => {
+                expect_resource::<Input<GamepadButton>>(world).just_pressed(*button)
+            }
+        }
+    }
+}
+
+/// An ordered sequence of inputs, such as a fighting game combo or a cheat code. Each step
+/// (except the first one) must be pressed within a time window after the previous one.
+///
+/// ```ignore
+/// let hadoken = InputSequence::new(KeyCode::Down)
+///     .then(KeyCode::Right, Duration::from_millis(200))
+///     .then(KeyCode::J, Duration::from_millis(200));
+/// if s.input_sequence(hadoken).await.is_ok() { ... }
+/// ```
+#[derive(Clone, Debug)]
+pub struct InputSequence {
+    first: InputStep,
+    steps: Vec<(InputStep, Duration)>,
+}
+
+/// The reason an [`InputSequence`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequenceError {
+    /// The input of step `step` was not pressed in time.
+    TimedOut { step: usize },
+    /// Another input of the sequence was pressed instead of the one of step `step`.
+    WrongInput { step: usize },
+}
+
+impl InputSequence {
+    /// A new sequence, starting with `first`.
+    pub fn new(first: impl Into<InputStep>) -> Self {
+        Self {
+            first: first.into(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Add a step, which must be pressed at most `window` after the previous one.
+    pub fn then(mut self, step: impl Into<InputStep>, window: impl IntoDuration) -> Self {
+        let window = window.into_duration();
+        self.steps.push((step.into(), window));
+        self
+    }
+
+    fn step(&self, index: usize) -> InputStep {
+        match index {
+            0 => self.first,
+            i => self.steps[i - 1].0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.steps.len() + 1
+    }
+}
+
+impl Scope {
+    /// Returns a future that waits for the first input of `sequence` to be pressed, and then
+    /// resolve once the whole sequence was entered, or as soon as it is broken.
+    ///
+    /// The sequence is broken when a step is not pressed within its window, or when another
+    /// input of the sequence is pressed instead. Inputs not part of the sequence are ignored.
+    pub fn input_sequence(
+        &mut self,
+        sequence: InputSequence,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Result<(), SequenceError>>> {
+        let mut next = 0;
+        let mut since_last = Duration::ZERO;
+        self.until(move |world| {
+            if next > 0 {
+                since_last += TickTime::get(world).delta;
+                if since_last > sequence.steps[next - 1].1 {
+                    return Some(Err(SequenceError::TimedOut { step: next }));
+                }
+            }
+
+            let expected = sequence.step(next);
+            if expected.just_pressed(world) {
+                next += 1;
+                since_last = Duration::ZERO;
+                return (next == sequence.len()).then_some(Ok(()));
+            }
+
+            let wrong = (0..sequence.len())
+                .map(|i| sequence.step(i))
+                .any(|step| step != expected && step.just_pressed(world));
+            if next > 0 && wrong {
+                return Some(Err(SequenceError::WrongInput { step: next }));
+            }
+
+            None
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use bevy::{
+    input::touch::Touch,
+    prelude::{Touches, Vec2, World},
+    utils::HashMap,
+};
+
+use crate::{
+    executor::TickTime,
+    expect_resource,
+    function_coroutine::{await_condition::AwaitCondition, await_time::IntoDuration, scope::Scope},
+};
+
+/// A recognized drag gesture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Drag {
+    /// The id of the finger which performed the drag.
+    pub id: u64,
+    /// The position where the touch started.
+    pub start: Vec2,
+    /// The position where the touch ended.
+    pub end: Vec2,
+}
+
+impl Scope {
+    /// Returns a future that resolve the next time a finger touches the screen.
+    pub fn touch_started(&mut self) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Touch>> {
+        self.until(|world| {
+            expect_resource::<Touches>(world)
+                .iter_just_pressed()
+                .next()
+                .copied()
+        })
+    }
+
+    /// Returns a future that resolve the next time a finger is lifted from the screen.
+    pub fn touch_ended(&mut self) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Touch>> {
+        self.until(|world| {
+            expect_resource::<Touches>(world)
+                .iter_just_released()
+                .next()
+                .copied()
+        })
+    }
+
+    /// Returns a future that resolve on the next tap, and returns its position. A tap is a touch
+    /// which is released within `max_duration`, without moving more than `max_distance`.
+    pub fn tap(
+        &mut self,
+        max_distance: f32,
+        max_duration: impl IntoDuration,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Vec2>> {
+        let max_duration = max_duration.into_duration();
+        let mut started_at = HashMap::<u64, Duration>::new();
+        self.until(move |world| {
+            let now = TickTime::get(world).elapsed;
+            let touches = expect_resource::<Touches>(world);
+            for touch in touches.iter_just_pressed() {
+                started_at.insert(touch.id(), now);
+            }
+
+            touches.iter_just_released().find_map(|touch| {
+                let start = started_at.remove(&touch.id())?;
+                let is_tap = now - start <= max_duration
+                    && touch.start_position().distance(touch.position()) <= max_distance;
+                is_tap.then_some(touch.position())
+            })
+        })
+    }
+
+    /// Returns a future that resolve on the next drag, which is a touch released after moving
+    /// more than `min_distance` from where it started.
+    pub fn drag(
+        &mut self,
+        min_distance: f32,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Drag>> {
+        self.until(move |world| {
+            expect_resource::<Touches>(world)
+                .iter_just_released()
+                .find(|touch| touch.start_position().distance(touch.position()) > min_distance)
+                .map(|touch| Drag {
+                    id: touch.id(),
+                    start: touch.start_position(),
+                    end: touch.position(),
+                })
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::{
+    Entity, GlobalTransform, Input, Interaction, MouseButton, Node, Rect, Style, Val, Vec2, World,
+};
+
+use crate::{
+    expect_resource,
+    function_coroutine::{await_condition::AwaitCondition, scope::Scope},
+};
+
+use super::primary_window;
+
+impl Scope {
+    /// Returns a future that resolve the next time the [`Interaction`] of `button` transitions
+    /// to [`Interaction::Pressed`]. A button which is already pressed must be released first.
+    ///
+    /// Note that the future never resolve if `button` is despawned.
+    pub fn clicked(
+        &mut self,
+        button: Entity,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        let mut condition = on_click(vec![button]);
+        self.until(move |world| condition(world).map(|_| ()))
+    }
+
+    /// Returns a future that resolve the next time any of `buttons` is clicked, and returns that
+    /// button. See [`Scope::clicked`] for the details.
+    pub fn clicked_any(
+        &mut self,
+        buttons: impl IntoIterator<Item = Entity>,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Entity>> {
+        self.until(on_click(buttons.into_iter().collect()))
+    }
+}
+
+/// Returns a condition resolving when any of `buttons` transitions to [`Interaction::Pressed`].
+fn on_click(buttons: Vec<Entity>) -> impl FnMut(&World) -> Option<Entity> {
+    // The last seen interaction of each button, `None` until first checked
+    let mut previous: Vec<Option<Interaction>> = vec![None; buttons.len()];
+    move |world| {
+        let mut clicked = None;
+        for (button, previous) in buttons.iter().zip(previous.iter_mut()) {
+            let current = world.get::<Interaction>(*button).copied();
+            let was_pressed = previous.map_or(true, |p| p == Interaction::Pressed);
+            if clicked.is_none() && current == Some(Interaction::Pressed) && !was_pressed {
+                clicked = Some(*button);
+            }
+            *previous = current.or(*previous);
+        }
+        clicked
+    }
+}
+
+/// The outcome of [`Scope::drag_and_drop`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DragAndDrop {
+    /// The drop target under the cursor when the item was released, if any.
+    pub dropped_on: Option<Entity>,
+    /// The top left corner of the item before it was dragged.
+    pub start: Vec2,
+    /// The top left corner of the item when it was released.
+    pub end: Vec2,
+}
+
+/// Returns the rectangle covered by the UI node `entity`, in logical window coordinates.
+fn node_rect(world: &World, entity: Entity) -> Option<Rect> {
+    let node = world.get::<Node>(entity)?;
+    let transform = world.get::<GlobalTransform>(entity)?;
+    Some(Rect::from_center_size(
+        transform.translation().truncate(),
+        node.size(),
+    ))
+}
+
+impl Scope {
+    /// Wait until `item` is pressed, make it follow the cursor (keeping the offset between the
+    /// cursor and the item) until the left mouse button is released, and returns which of
+    /// `targets` it was dropped on.
+    ///
+    /// The item is moved by setting the `left` and `top` fields of its [`Style`], so it should be
+    /// an absolutely positioned node without a parent. It is left where it was dropped.
+    pub async fn drag_and_drop(
+        &mut self,
+        item: Entity,
+        targets: impl IntoIterator<Item = Entity>,
+    ) -> DragAndDrop {
+        let targets: Vec<Entity> = targets.into_iter().collect();
+        let mut window = None;
+
+        loop {
+            self.clicked(item).await;
+            let (cursor, rect) = {
+                let world = self.world();
+                let cursor = primary_window(&world, &mut window).and_then(|w| w.cursor_position());
+                (cursor, node_rect(&world, item))
+            };
+            if let (Some(cursor), Some(rect)) = (cursor, rect) {
+                let start = rect.min;
+                let offset = start - cursor;
+                let mut end = start;
+
+                while !expect_resource::<Input<MouseButton>>(&self.world())
+                    .just_released(MouseButton::Left)
+                {
+                    let cursor = primary_window(&self.world(), &mut window)
+                        .and_then(|w| w.cursor_position());
+                    if let Some(cursor) = cursor {
+                        end = cursor + offset;
+                        self.modify(item, move |style: &mut Style| {
+                            style.left = Val::Px(end.x);
+                            style.top = Val::Px(end.y);
+                        });
+                    }
+                    self.next_tick().await;
+                }
+
+                let cursor = end - offset;
+                let world = self.world();
+                let dropped_on = targets.iter().copied().find(|target| {
+                    node_rect(&world, *target).is_some_and(|rect| rect.contains(cursor))
+                });
+                return DragAndDrop {
+                    dropped_on,
+                    start,
+                    end,
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use bevy::{
+    prelude::Entity,
+    window::{WindowCloseRequested, WindowFocused, WindowResized},
+};
+
+use crate::function_coroutine::{
+    await_event::{NextEvent, ReadEvents},
+    scope::Scope,
+};
+
+impl Scope {
+    /// Returns a future that resolve the next time a window is resized, with the event.
+    pub fn window_resized(
+        &mut self,
+    ) -> NextEvent<'_, WindowResized, impl ReadEvents<WindowResized, WindowResized>> {
+        self.next_event_where(|e: &WindowResized| Some(e.clone()))
+    }
+
+    /// Returns a future that resolve the next time a window gains focus, and returns it.
+    pub fn window_focus_gained(
+        &mut self,
+    ) -> NextEvent<'_, WindowFocused, impl ReadEvents<WindowFocused, Entity>> {
+        self.next_event_where(|e: &WindowFocused| e.focused.then_some(e.window))
+    }
+
+    /// Returns a future that resolve the next time a window loses focus, and returns it.
+    pub fn window_focus_lost(
+        &mut self,
+    ) -> NextEvent<'_, WindowFocused, impl ReadEvents<WindowFocused, Entity>> {
+        self.next_event_where(|e: &WindowFocused| (!e.focused).then_some(e.window))
+    }
+
+    /// Returns a future that resolve the next time the user requests to close a window, and
+    /// returns it.
+    pub fn window_close_requested(
+        &mut self,
+    ) -> NextEvent<'_, WindowCloseRequested, impl ReadEvents<WindowCloseRequested, Entity>> {
+        self.next_event_where(|e: &WindowCloseRequested| Some(e.window))
+    }
+}
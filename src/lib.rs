@@ -1,3 +1,6 @@
+// Lets the procedural macros refer to `::corentin` from within this crate as well
+extern crate self as corentin;
+
 use std::pin::Pin;
 
 use bevy::ecs::component::ComponentId;
@@ -19,12 +22,42 @@ use self::executor::msg::NewCoroutine;
 
 use self::id_alloc::Ids;
 
+pub mod ability;
+pub mod animation;
+pub mod audio;
+#[cfg(feature = "behavior-tree")]
+pub mod behavior;
+pub mod camera;
+pub mod color;
 pub mod commands;
+mod coro_pool;
+#[cfg(feature = "cutscene")]
+pub mod cutscene;
+#[cfg(feature = "debug-overlay")]
+pub mod debug_overlay;
+#[cfg(feature = "dialogue")]
+pub mod dialogue;
 pub mod executor;
+pub mod external_signal;
+pub mod fade;
 pub mod function_coroutine;
 pub mod global_channel;
 pub mod id_alloc;
+pub mod input;
+pub mod physics;
 pub mod plugin;
+pub mod quest;
+pub mod scene;
+#[cfg(all(feature = "rhai", not(target_arch = "wasm32")))]
+pub mod scripting;
+pub mod state_machine;
+pub mod test;
+pub mod timeline;
+pub mod transform;
+pub mod turn;
+pub mod tween;
+pub mod utility;
+pub mod wave;
 
 pub mod prelude {
     #[doc(hidden)]
@@ -33,10 +66,95 @@ pub mod prelude {
     #[doc(hidden)]
     pub use crate::commands::*;
 
+    #[doc(hidden)]
+    pub use corentin_macros::coroutine_fn;
+
     #[doc(hidden)]
     pub use crate::plugin::*;
+
+    #[doc(hidden)]
+    pub use crate::executor::{coroutine_handle::CoroutineHandle, DespawnPolicy, Fairness};
+
+    #[doc(hidden)]
+    pub use crate::external_signal::{ExternalSignal, Triggered};
+
+    #[doc(hidden)]
+    pub use crate::input::prelude::*;
+
+    #[doc(hidden)]
+    pub use crate::ability::{Charge, Cooldowns};
+
+    #[doc(hidden)]
+    pub use crate::animation::AnimationMarkers;
+
+    #[doc(hidden)]
+    pub use crate::camera::CameraShake;
+
+    #[doc(hidden)]
+    pub use crate::color::{ColorSpace, Colored};
+
+    #[doc(hidden)]
+    pub use crate::fade::Fade;
+
+    #[doc(hidden)]
+    pub use crate::quest::Checkpoints;
+
+    #[doc(hidden)]
+    pub use crate::state_machine::StateMachine;
+
+    #[doc(hidden)]
+    pub use crate::timeline::Timeline;
+
+    #[doc(hidden)]
+    pub use crate::transform::{Pace, Spring, Target};
+
+    #[doc(hidden)]
+    pub use crate::tween::Ease;
+
+    #[doc(hidden)]
+    pub use crate::utility::Behaviors;
+}
+
+/// Returns the resource `R`, panicking with a helpful message if it is missing.
+pub(crate) fn expect_resource<R: bevy::prelude::Resource>(world: &World) -> &R {
+    world.get_resource::<R>().unwrap_or_else(|| {
+        panic!(
+            "The resource {} is missing, make sure the plugin providing it was added",
+            std::any::type_name::<R>()
+        )
+    })
 }
 
+/// Whether the `strict-checks` feature is enabled, in a debug build. The invariants the executor
+/// relies on are then validated at runtime, and their violations panic with a detailed message
+/// instead of misbehaving later:
+/// - A scope is only used while its coroutine is resumed, and not from another coroutine.
+/// - Parameters are only used with the scope of their own coroutine.
+///
+/// That a coroutine only awaits one thing at a time is checked in every debug build, since
+/// awaiting from a scope and a [`SubScope`](function_coroutine::scope::SubScope) at once (with a
+/// `join` for instance) would otherwise lose one of the wake-ups.
+///
+/// That nothing borrowed from the world (through [`Rd`](prelude::Rd) or [`Wr`](prelude::Wr))
+/// is held across an await is mostly checked at compile time, since awaiting borrows the scope
+/// mutably, and otherwise by the [borrow tracker](function_coroutine::borrow_tracker) of every
+/// debug build. In release builds, none of this is checked.
+pub(crate) const STRICT_CHECKS: bool = cfg!(all(feature = "strict-checks", debug_assertions));
+
+/// [`Send`] on every platform, except on wasm32. There everything runs on a single thread, so
+/// coroutines are allowed to hold values which are not [`Send`] (such as `JsValue`).
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+
+/// [`Send`] on every platform, except on wasm32. There everything runs on a single thread, so
+/// coroutines are allowed to hold values which are not [`Send`] (such as `JsValue`).
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
 // THINGS MISSING:
 // Dropping a scope should drop the local entities
 // SIGNALS !!!
@@ -81,12 +199,42 @@ pub trait Coroutine: Send + 'static {
 
     /// Returns this coroutine metadata
     fn meta(&self) -> &CoroMeta;
+
+    /// Returns where this coroutine was last suspended, if known.
+    fn suspended_at(&self) -> Option<&'static std::panic::Location<'static>> {
+        None
+    }
 }
 
 pub struct CoroMeta {
     id: Id,
+    name: &'static str,
+    despawn_policy: Option<executor::DespawnPolicy>,
+    /// The policy chosen for the wait the coroutine is suspended on, if any.
+    wait_despawn_policy: Option<executor::DespawnPolicy>,
     owner: Option<Entity>,
     access: CoroAccess,
+    /// The thread a non-[`Send`] coroutine is bound to.
+    thread: Option<std::thread::ThreadId>,
+}
+
+impl CoroMeta {
+    /// The components and resources this coroutine accesses each time it is resumed.
+    pub fn access(&self) -> &CoroAccess {
+        &self.access
+    }
+
+    /// The [`DespawnPolicy`](executor::DespawnPolicy) of this coroutine, if it overrides the one
+    /// of the executor. The policy of the current wait comes first.
+    pub fn despawn_policy(&self) -> Option<executor::DespawnPolicy> {
+        self.wait_despawn_policy.or(self.despawn_policy)
+    }
+
+    /// Returns true if this coroutine isn't [`Send`], and can only be resumed from the thread it
+    /// started on.
+    pub fn is_non_send(&self) -> bool {
+        self.thread.is_some()
+    }
 }
 
 #[derive(Default, Clone)]
@@ -128,15 +276,57 @@ impl CoroAccess {
 
         true
     }
+
+    /// Add a read access to the whole world, which conflicts with any write.
+    pub(crate) fn add_world_read(&mut self) {
+        self.reads.entry(SourceId::World).or_default();
+    }
+
+    /// Returns true if a coroutine with this access can be resumed at the same time as one with
+    /// the `other` access, i.e. neither of them writes to something the other one uses.
+    pub fn is_compatible(&self, other: &CoroAccess) -> bool {
+        !overlaps(&self.writes, &other.writes)
+            && !overlaps(&self.writes, &other.reads)
+            && !overlaps(&self.reads, &other.writes)
+    }
+
+    /// Add all the accesses of `other` to this one.
+    pub fn extend(&mut self, other: &CoroAccess) {
+        for (source, components) in &other.reads {
+            let reads = self.reads.entry(*source).or_default();
+            for c in components.iter() {
+                reads.insert(c);
+            }
+        }
+        for (source, components) in &other.writes {
+            let writes = self.writes.entry(*source).or_default();
+            for c in components.iter() {
+                writes.insert(c);
+            }
+        }
+    }
+}
+
+fn overlaps(a: &HashMap<SourceId, SetUsize>, b: &HashMap<SourceId, SetUsize>) -> bool {
+    a.iter().any(|(source_a, components_a)| {
+        b.iter().any(|(source_b, components_b)| {
+            let same_source = match (source_a, source_b) {
+                (SourceId::World, _) | (_, SourceId::World) => return true,
+                (SourceId::AllEntities, _) | (_, SourceId::AllEntities) => true,
+                (SourceId::Entity(a), SourceId::Entity(b)) => a == b,
+            };
+            same_source && components_a.iter().any(|c| components_b.contains(c))
+        })
+    })
 }
 
 /// A heap allocated [`Coroutine`]
 /// It is pinned since most coroutine are implemented using [`Future`]. [`SyncCell`] is used to
 /// make them [`Sync`] while being only [`Send`].
-type HeapCoro = SyncCell<Pin<Box<dyn Coroutine>>>;
+type HeapCoro = SyncCell<Pin<coro_pool::PooledCoro>>;
 
 #[cfg(test)]
-mod test {
+mod tests {
     use std::{
         sync::{Arc, Mutex},
         thread,
@@ -145,17 +335,28 @@ mod test {
 
     use bevy::{
         ecs::system::{Command, EntityCommand},
-        prelude::{Component, Mut, World},
+        prelude::{App, Component, Entity, Event, Events, Input, KeyCode, Mut, With, World},
         time::Time,
     };
 
     use super::prelude::*;
 
-    use super::executor::Executor;
+    use super::executor::{owners::CoroutineOwner, Executor};
 
     #[derive(Component)]
     struct ExampleComponent(u32);
 
+    /// Advance the [`Time`] of `world` by `delta`, as the time plugin does on each frame.
+    fn advance_time(world: &mut World, delta: Duration) {
+        let mut time = world.resource_mut::<Time>();
+        let last_update = time.last_update().unwrap_or_else(|| {
+            let startup = time.startup();
+            time.update_with_instant(startup);
+            startup
+        });
+        time.update_with_instant(last_update + delta);
+    }
+
     #[test]
     fn wait_on_tick() {
         let mut world = World::new();
@@ -205,6 +406,263 @@ mod test {
         });
     }
 
+    #[test]
+    fn await_bridged_external_future() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+
+        // An external future, which is pending on its first poll
+        let mut polled = false;
+        let external = std::future::poll_fn(move |_| {
+            if polled {
+                std::task::Poll::Ready(3)
+            } else {
+                polled = true;
+                std::task::Poll::Pending
+            }
+        });
+
+        root_coroutine(|mut s: Scope| async move {
+            let value = s.bridge(external).await;
+            *b.lock().unwrap() = value;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 0);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 3);
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn awaiting_tokio_task() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let handle = runtime.handle().clone();
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(None));
+        let b = Arc::clone(&a);
+        root_coroutine(move |mut s: Scope| async move {
+            let value = s
+                .spawn_tokio(&handle, async {
+                    tokio::task::yield_now().await;
+                    6 * 7
+                })
+                .await;
+            *b.lock().unwrap() = Some(value);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            // The task only makes progress while the runtime is driven
+            executor.tick(w);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), None);
+            for _ in 0..4 {
+                runtime.block_on(tokio::task::yield_now());
+            }
+            executor.tick(w);
+        });
+        assert_eq!(*a.lock().unwrap(), Some(Some(42)));
+    }
+
+    #[cfg(feature = "async-std")]
+    #[test]
+    fn awaiting_async_std_task() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let (done, finished) = std::sync::mpsc::channel();
+        root_coroutine(move |mut s: Scope| async move {
+            let value = s
+                .spawn_async_std(async {
+                    async_std::task::yield_now().await;
+                    6 * 7
+                })
+                .await;
+            done.send(value).unwrap();
+        })
+        .apply(&mut world);
+
+        // The task runs on the threads of async-std, the coroutine is resumed until it completes
+        let value = world.resource_scope(|w, mut executor: Mut<Executor>| {
+            (0..1000).find_map(|_| {
+                executor.tick(w);
+                finished.recv_timeout(Duration::from_millis(5)).ok()
+            })
+        });
+        assert_eq!(value, Some(Some(42)));
+    }
+
+    #[test]
+    fn receiving_from_channel() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let (tx, mut rx) = std::sync::mpsc::channel();
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&a);
+
+        root_coroutine(|mut s: Scope| async move {
+            while let Some(value) = s.recv(&mut rx).await {
+                b.lock().unwrap().push(value);
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            assert!(a.lock().unwrap().is_empty());
+            tx.send(1).unwrap();
+            tx.send(2).unwrap();
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), vec![1, 2]);
+            drop(tx);
+            executor.tick_until_empty(w);
+        });
+    }
+
+    #[test]
+    fn waking_on_external_signal() {
+        #[derive(bevy::prelude::Resource)]
+        struct Trigger(bool);
+
+        impl ExternalSignal for Trigger {
+            fn poll(&mut self) -> Option<Triggered> {
+                std::mem::take(&mut self.0).then_some(Triggered::All)
+            }
+        }
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.insert_resource(Trigger(false));
+        world
+            .resource_mut::<Executor>()
+            .register_external_signal::<Trigger>();
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+
+        root_coroutine(|mut s: Scope| async move {
+            loop {
+                s.on_external::<Trigger>().await;
+                *b.lock().unwrap() += 1;
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 0);
+            w.resource_mut::<Trigger>().0 = true;
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 1);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 1);
+        });
+    }
+
+    #[test]
+    fn waking_single_waiter_of_external_signal() {
+        #[derive(bevy::prelude::Resource, Default)]
+        struct Responses(Vec<u64>);
+
+        impl ExternalSignal for Responses {
+            fn poll(&mut self) -> Option<Triggered> {
+                self.0.pop().map(Triggered::Key)
+            }
+        }
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Responses>();
+        world
+            .resource_mut::<Executor>()
+            .register_external_signal::<Responses>();
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        for request in 0..3 {
+            let b = Arc::clone(&a);
+            root_coroutine(move |mut s: Scope| async move {
+                s.on_external_key::<Responses>(request).await;
+                b.lock().unwrap().push(request);
+            })
+            .apply(&mut world);
+        }
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            w.resource_mut::<Responses>().0.push(1);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), [1]);
+            // Several keys can be triggered on the same tick
+            w.resource_mut::<Responses>().0.extend([0, 2]);
+            executor.tick(w);
+            a.lock().unwrap().sort();
+            assert_eq!(*a.lock().unwrap(), [0, 1, 2]);
+        });
+    }
+
+    #[test]
+    fn trying_without_resources() {
+        #[derive(bevy::prelude::Resource)]
+        struct Trigger;
+
+        impl ExternalSignal for Trigger {
+            fn poll(&mut self) -> Option<Triggered> {
+                None
+            }
+        }
+
+        #[derive(Event, Clone)]
+        struct Hit;
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let target = world.spawn_empty().id();
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&a);
+        root_coroutine(move |mut s: Scope| async move {
+            let mut b = b.lock().unwrap();
+            b.push(s.try_on_external::<Trigger>().is_none());
+            b.push(s.try_next_event::<Hit>().is_none());
+            b.push(
+                s.try_bind_coroutine(
+                    target,
+                    |mut s: Scope, _ex: Rd<ExampleComponent>| async move {
+                        s.next_tick().await;
+                    },
+                )
+                .is_none(),
+            );
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+        });
+        assert_eq!(*a.lock().unwrap(), [true, true, true]);
+    }
+
     #[test]
     fn waiting_on_first() {
         let mut world = World::new();
@@ -345,69 +803,3587 @@ mod test {
     }
 
     #[test]
-    fn waiting_on_internal_change() {
+    fn waiting_on_all_vec_result() {
         let mut world = World::new();
         world.init_resource::<Executor>();
         world.insert_resource(Time::new(Instant::now()));
 
-        let e = world
-            .spawn((
-                ExampleComponent(0),
-                ChangeTracker::new() as ChangeTracker<ExampleComponent>,
-            ))
-            .id();
-
-        let a = Arc::new(Mutex::new(0));
-        let b = Arc::clone(&a);
-
-        coroutine(
-            |mut s: Scope, mut example: Wr<ExampleComponent>| async move {
-                for _ in 0..5 {
-                    s.next_tick().await;
-                    example.get_mut(&s).0 += 1;
-                }
-            },
-        )
-        .apply(e, &mut world);
+        root_coroutine(|mut s: Scope| async move {
+            let handles = (0..3)
+                .map(|i| {
+                    s.start(move |mut s: Scope| async move {
+                        for _ in 0..i {
+                            s.next_tick().await;
+                        }
+                        i
+                    })
+                })
+                .collect::<Vec<_>>();
 
-        coroutine(
-            |mut fib: Scope, on_change: OnChange<ExampleComponent>| async move {
-                for _ in 0..5 {
-                    on_change.observe(&mut fib).await;
-                    *b.lock().unwrap() += 1;
-                }
-            },
-        )
-        .apply(e, &mut world);
+            let res = s.all(handles).await;
+            assert_eq!(res, vec![0, 1, 2]);
+        })
+        .apply(&mut world);
 
         world.resource_scope(|w, mut executor: Mut<Executor>| {
-            for i in 0..5 {
-                executor.tick(w);
-                assert_eq!(*a.lock().unwrap(), i);
-            }
+            executor.tick_until_empty(w);
         });
     }
 
     #[test]
-    fn applying_commands_from_coroutine() {
+    fn dumping_coroutine_tree() {
         let mut world = World::new();
         world.init_resource::<Executor>();
         world.insert_resource(Time::new(Instant::now()));
 
         root_coroutine(|mut s: Scope| async move {
-            let e = s.commands().spawn(ExampleComponent(0)).id();
-            s.next_tick().await;
-            s.commands().entity(e).remove::<ExampleComponent>();
+            let handles = (0..2)
+                .map(|_| {
+                    s.start(|mut s: Scope| async move {
+                        s.next_tick().await;
+                    })
+                })
+                .collect::<Vec<_>>();
+            s.all(handles).await;
         })
         .apply(&mut world);
 
-        world.resource_scope(|world, mut executor: Mut<Executor>| {
-            let mut state = world.query::<&ExampleComponent>();
-            assert_eq!(state.iter(world).len(), 0);
-            executor.tick(world);
-            assert_eq!(state.iter(world).len(), 1);
-            executor.tick(world);
-            assert_eq!(state.iter(world).len(), 0);
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            let tree = executor.dump_tree();
+            let lines: Vec<&str> = tree.lines().collect();
+            assert_eq!(lines.len(), 3);
+            assert!(lines[0].starts_with('#') && lines[0].contains("waiting on all of"));
+            assert!(
+                lines[1..]
+                    .iter()
+                    .all(|l| l.starts_with("  #")
+                        && l.contains("waiting on next tick at src/lib.rs:"))
+            );
+        });
+    }
+
+    #[test]
+    fn replaying_recording() {
+        use super::executor::record::ScheduleEvent;
+
+        fn setup() -> World {
+            let mut world = World::new();
+            world.init_resource::<Executor>();
+            world.insert_resource(Time::new(Instant::now()));
+            root_coroutine(|mut s: Scope| async move {
+                let racers = [0, 1, 2].map(|i| {
+                    s.start(move |mut s: Scope| async move {
+                        s.next_tick().await;
+                        i
+                    })
+                });
+                s.first(racers).await;
+            })
+            .apply(&mut world);
+            world
+        }
+
+        let mut world = setup();
+        let recording = world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.start_recording();
+            executor.tick_until_empty(w);
+            executor.stop_recording().unwrap()
+        });
+        assert!(recording
+            .ticks
+            .iter()
+            .flatten()
+            .any(|e| matches!(e, ScheduleEvent::FirstResolved { .. })));
+
+        let mut world = setup();
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.replay(recording.clone());
+            for _ in 0..recording.ticks.len() {
+                executor.tick(w);
+                assert!(executor.is_replaying());
+            }
+            executor.tick(w);
+            assert!(!executor.is_replaying());
+        });
+    }
+
+    #[test]
+    fn test_executor_advances_time() {
+        use super::{executor::info::CoroutineState, test::TestExecutor};
+
+        let mut test = TestExecutor::new();
+        let result = test.run(|mut s: Scope| async move {
+            s.duration(Duration::from_secs(1)).await;
+            42
+        });
+
+        // The coroutine starts on the first frame
+        test.advance_frame();
+        test.advance_time(Duration::from_millis(500));
+        assert_eq!(result.get(), None);
+        test.expect_suspended_on(|state| matches!(state, CoroutineState::Duration { .. }));
+        test.advance_time(Duration::from_millis(500));
+        assert_eq!(result.get(), Some(42));
+        assert_eq!(test.run_until_done(1), 0);
+    }
+
+    #[test]
+    fn advancing_manual_time() {
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        root_coroutine(|mut s: Scope| async move {
+            s.duration(Duration::from_secs(10)).await;
+            *b.lock().unwrap() += 1;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            executor.advance(Duration::from_secs(9));
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 0);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 0);
+            executor.advance(Duration::from_secs(1));
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 1);
+        });
+    }
+
+    #[test]
+    fn cooldown_with_manual_time() {
+        use super::ability::Cooldowns;
+
+        // No `Time` resource, cooldowns follow the clock of the executor
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+        world.init_resource::<Cooldowns>();
+
+        let uses = Arc::new(Mutex::new(0));
+        let u = Arc::clone(&uses);
+        root_coroutine(|mut s: Scope| async move {
+            loop {
+                s.cooldown("fire", Duration::from_secs(1)).await;
+                *u.lock().unwrap() += 1;
+                s.next_tick().await;
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            assert_eq!(*uses.lock().unwrap(), 1);
+            executor.advance(Duration::from_millis(600));
+            executor.tick(w);
+            executor.advance(Duration::from_millis(300));
+            executor.tick(w);
+            assert_eq!(*uses.lock().unwrap(), 1);
+            executor.advance(Duration::from_millis(100));
+            executor.tick(w);
+            assert_eq!(*uses.lock().unwrap(), 2);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Cooldowns is missing")]
+    fn cooldown_without_resource() {
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+
+        root_coroutine(|mut s: Scope| async move {
+            s.cooldown("fire", Duration::from_secs(1)).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| executor.tick(w));
+    }
+
+    #[test]
+    fn sharing_cooldown_in_the_same_tick() {
+        use super::ability::Cooldowns;
+
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+        world.init_resource::<Cooldowns>();
+
+        let uses = Arc::new(Mutex::new(Vec::new()));
+        for user in ["a", "b"] {
+            let u = Arc::clone(&uses);
+            root_coroutine(move |mut s: Scope| async move {
+                s.cooldown("shared", Duration::from_secs(1)).await;
+                u.lock().unwrap().push(user);
+            })
+            .apply(&mut world);
+        }
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            assert_eq!(uses.lock().unwrap().len(), 1);
+            executor.advance(Duration::from_secs(1));
+            executor.tick(w);
+            assert_eq!(uses.lock().unwrap().len(), 2);
+        });
+    }
+
+    #[test]
+    fn holding_key_with_manual_time() {
+        use super::input::keyboard::KeyCondition;
+
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+        world.init_resource::<Input<KeyCode>>();
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+
+        let done = Arc::new(Mutex::new(false));
+        let d = Arc::clone(&done);
+        root_coroutine(|mut s: Scope| async move {
+            let held = KeyCondition::key(KeyCode::Space).held_for(Duration::from_secs(1));
+            s.key_condition(held).await;
+            *d.lock().unwrap() = true;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            executor.advance(Duration::from_millis(600));
+            executor.tick(w);
+
+            // Releasing the key starts over
+            w.resource_mut::<Input<KeyCode>>().release(KeyCode::Space);
+            executor.tick(w);
+            w.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+            executor.advance(Duration::from_millis(600));
+            executor.tick(w);
+            assert!(!*done.lock().unwrap());
+            executor.advance(Duration::from_millis(400));
+            executor.tick(w);
+            assert!(*done.lock().unwrap());
+        });
+    }
+
+    #[test]
+    fn input_sequence_timeout_with_manual_time() {
+        use super::input::sequence::{InputSequence, SequenceError};
+
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+        world.init_resource::<Input<KeyCode>>();
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Down);
+
+        let result = Arc::new(Mutex::new(None));
+        let r = Arc::clone(&result);
+        root_coroutine(|mut s: Scope| async move {
+            let sequence =
+                InputSequence::new(KeyCode::Down).then(KeyCode::Right, Duration::from_millis(200));
+            *r.lock().unwrap() = Some(s.input_sequence(sequence).await);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            w.resource_mut::<Input<KeyCode>>().clear();
+            executor.advance(Duration::from_millis(150));
+            executor.tick(w);
+            assert_eq!(*result.lock().unwrap(), None);
+            executor.advance(Duration::from_millis(100));
+            executor.tick(w);
+            assert_eq!(
+                *result.lock().unwrap(),
+                Some(Err(SequenceError::TimedOut { step: 1 }))
+            );
+        });
+    }
+
+    #[test]
+    fn ticking_deterministic_executor() {
+        fn simulate() -> Vec<(u32, &'static str)> {
+            // No `Time` resource, it is never read
+            let mut world = World::new();
+            world.insert_resource(Executor::deterministic(0.1));
+
+            let tick = Arc::new(Mutex::new(0));
+            let log = Arc::new(Mutex::new(Vec::new()));
+            for (name, secs) in [("slow", 0.35), ("fast", 0.15)] {
+                let (tick, log) = (Arc::clone(&tick), Arc::clone(&log));
+                root_coroutine(move |mut s: Scope| async move {
+                    s.duration(secs).await;
+                    log.lock().unwrap().push((*tick.lock().unwrap(), name));
+                })
+                .apply(&mut world);
+            }
+
+            world.resource_scope(|w, mut executor: Mut<Executor>| {
+                for _ in 0..6 {
+                    *tick.lock().unwrap() += 1;
+                    executor.tick(w);
+                }
+            });
+            let log = log.lock().unwrap().clone();
+            log
+        }
+
+        let log = simulate();
+        assert_eq!(
+            log.iter().map(|(_, name)| *name).collect::<Vec<_>>(),
+            ["fast", "slow"]
+        );
+        assert_eq!(log, simulate());
+    }
+
+    #[test]
+    fn saving_and_restoring_executor_state() {
+        use super::executor::save::CoroFactories;
+
+        let restored = Arc::new(Mutex::new(Vec::new()));
+        let r = Arc::clone(&restored);
+        let factories = CoroFactories::new().register("bomb", move |saved| {
+            let r = Arc::clone(&r);
+            let fuse = saved.remaining.unwrap();
+            boxed_coroutine(move |mut s: Scope| async move {
+                r.lock().unwrap().push(fuse);
+                s.duration(fuse).await;
+            })
+        });
+
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+        let owner = world.spawn_empty().id();
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor
+                .build(|mut s: Scope| async move {
+                    s.duration(5.0).await;
+                })
+                .name("bomb")
+                .owner(owner)
+                .spawn(w);
+            executor
+                .build(|mut s: Scope| async move {
+                    s.duration(5.0).await;
+                })
+                .name("not saved")
+                .spawn(w);
+            executor.tick(w);
+            executor.advance(2.0);
+            executor.tick(w);
+
+            let state = executor.save_state(w, &factories);
+            assert_eq!(state.coroutines.len(), 1);
+            assert_eq!(state.coroutines[0].owner, Some(owner));
+            assert_eq!(state.coroutines[0].remaining, Some(Duration::from_secs(3)));
+
+            let mut loaded = Executor::with_manual_time();
+            assert_eq!(loaded.restore_state(w, &state, &factories).len(), 1);
+            loaded.tick(w);
+        });
+        assert_eq!(*restored.lock().unwrap(), [Duration::from_secs(3)]);
+    }
+
+    #[test]
+    fn resyncing_deterministic_executor() {
+        use super::executor::{save::CoroFactories, TickTime};
+
+        let step = Duration::from_millis(100);
+        let factories = CoroFactories::new().register("fuse", |saved| {
+            let fuse = saved.remaining.unwrap();
+            boxed_coroutine(move |mut s: Scope| async move {
+                s.duration(fuse).await;
+            })
+        });
+
+        let mut world = World::new();
+        world.insert_resource(Executor::deterministic(step));
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor
+                .build(|mut s: Scope| async move {
+                    s.duration(Duration::from_secs(1)).await;
+                })
+                .name("fuse")
+                .spawn(w);
+            for _ in 0..3 {
+                executor.tick(w);
+            }
+
+            let state = executor.save_state(w, &factories);
+            assert_eq!(state.elapsed, Duration::from_millis(300));
+            assert_eq!(
+                state.coroutines[0].remaining,
+                Some(Duration::from_millis(800))
+            );
+
+            // The timer is started again, and measured against the clock going on from the saved one
+            let mut resynced = Executor::deterministic(step);
+            resynced.restore_state(w, &state, &factories);
+            resynced.tick(w);
+            assert_eq!(TickTime::get(w).elapsed, Duration::from_millis(400));
+            resynced.tick_until_empty(w);
+            assert_eq!(TickTime::get(w).elapsed, Duration::from_millis(1200));
+        });
+    }
+
+    #[test]
+    fn selecting_behaviors() {
+        use super::utility::Behaviors;
+
+        #[derive(Component)]
+        struct Threat(f32);
+
+        let mut app = App::new();
+        app.add_plugins(CorentinPlugin);
+        app.insert_resource(Time::new(Instant::now()));
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let behavior = |name: &'static str| {
+            let log = Arc::clone(&log);
+            move |_| {
+                let log = Arc::clone(&log);
+                boxed_coroutine(move |mut s: Scope| async move {
+                    log.lock().unwrap().push(name);
+                    loop {
+                        s.next_tick().await;
+                    }
+                })
+            }
+        };
+        let agent = app
+            .world
+            .spawn(Threat(0.0))
+            .insert(
+                Behaviors::new()
+                    .with(
+                        "flee",
+                        |agent, world| world.get::<Threat>(agent).unwrap().0,
+                        behavior("flee"),
+                    )
+                    .with("idle", |_, _| 0.5, behavior("idle")),
+            )
+            .id();
+
+        app.update();
+        app.update();
+        app.world.get_mut::<Threat>(agent).unwrap().0 = 1.0;
+        app.update();
+        app.update();
+        assert_eq!(*log.lock().unwrap(), ["idle", "flee"]);
+        assert_eq!(
+            app.world.get::<Behaviors>(agent).unwrap().running(),
+            Some("flee")
+        );
+    }
+
+    #[test]
+    fn partitioning_coroutines() {
+        let mut world = World::new();
+        world.insert_resource(Time::new(Instant::now()));
+        let mut executor = Executor::default();
+
+        let a = Arc::new(Mutex::new(0));
+        for client in [1, 1, 2] {
+            let b = Arc::clone(&a);
+            executor
+                .build(move |mut s: Scope| async move {
+                    // Started coroutines are in the same partition
+                    s.start_forget(|mut s: Scope| async move {
+                        loop {
+                            s.next_tick().await;
+                        }
+                    });
+                    loop {
+                        *b.lock().unwrap() += 1;
+                        s.next_tick().await;
+                    }
+                })
+                .partition(client)
+                .spawn(&world);
+        }
+        executor.set_partition_budget(1, Some(1));
+
+        // The budget leaves the second coroutine of the first client for the next tick
+        executor.tick(&mut world);
+        assert_eq!(executor.partition_stats(1).coroutines, 3);
+        assert_eq!(executor.partition_stats(2).coroutines, 2);
+        assert_eq!(*a.lock().unwrap(), 2);
+
+        executor.cancel_partition(1);
+        assert_eq!(executor.partition_stats(1).coroutines, 0);
+        *a.lock().unwrap() = 0;
+        executor.tick(&mut world);
+        assert_eq!(*a.lock().unwrap(), 1);
+        assert_eq!(executor.partition_stats(2).resumes, 2);
+    }
+
+    #[test]
+    fn canceling_from_handle() {
+        let mut world = World::new();
+        world.insert_resource(Time::new(Instant::now()));
+        let mut executor = Executor::default();
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        let handle = executor
+            .add_function_coroutine(None, &world, move |mut s: Scope| async move {
+                loop {
+                    *b.lock().unwrap() += 1;
+                    s.next_tick().await;
+                }
+            })
+            .unwrap();
+        let short = executor
+            .add_function_coroutine(None, &world, |_: Scope| async move {})
+            .unwrap();
+
+        // Handles can be stored in components
+        let enemy = world.spawn(handle.clone()).id();
+        executor.tick(&mut world);
+        executor.tick(&mut world);
+        assert!(short.is_finished());
+        assert!(!handle.is_finished());
+
+        world.get::<CoroutineHandle>(enemy).unwrap().cancel();
+        executor.tick(&mut world);
+        assert!(handle.is_finished());
+        assert_eq!(*a.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn canceling_on_owner_despawn() {
+        let mut world = World::new();
+        world.insert_resource(Time::new(Instant::now()));
+        let mut executor = Executor::default();
+        let e = world.spawn_empty().id();
+
+        // Never resumed again, and doesn't access any component of its owner
+        let waiting = executor
+            .add_function_coroutine(Some(e), &world, |mut s: Scope| async move {
+                s.duration(1000.0).await;
+            })
+            .unwrap();
+        let other = world.spawn_empty().id();
+        let unrelated = executor
+            .add_function_coroutine(Some(other), &world, |mut s: Scope| async move {
+                s.duration(1000.0).await;
+            })
+            .unwrap();
+
+        executor.tick(&mut world);
+        assert!(world.get::<CoroutineOwner>(e).is_some());
+        world.despawn(e);
+        executor.tick(&mut world);
+        assert!(waiting.is_finished());
+        assert!(!unrelated.is_finished());
+
+        // Despawned before its first tick, so before being marked
+        let e = world.spawn_empty().id();
+        let waiting = executor
+            .add_function_coroutine(Some(e), &world, |mut s: Scope| async move {
+                s.duration(1000.0).await;
+            })
+            .unwrap();
+        world.despawn(e);
+        executor.tick(&mut world);
+        assert!(waiting.is_finished());
+    }
+
+    #[test]
+    fn canceling_on_owner_despawn_between_ticks() {
+        let mut world = World::new();
+        world.insert_resource(Time::new(Instant::now()));
+        let mut executor = Executor::default();
+        let e = world.spawn_empty().id();
+
+        let waiting = executor
+            .add_function_coroutine(Some(e), &world, |mut s: Scope| async move {
+                s.duration(1000.0).await;
+            })
+            .unwrap();
+        executor.tick(&mut world);
+        world.despawn(e);
+        // The removals are dropped after two frames, the executor missed them
+        world.clear_trackers();
+        world.clear_trackers();
+        executor.tick(&mut world);
+        assert!(waiting.is_finished());
+    }
+
+    #[test]
+    fn waiting_on_events() {
+        #[derive(Event, Clone)]
+        struct Hit(u32);
+
+        let mut world = World::new();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Events<Hit>>();
+        let mut executor = Executor::default();
+
+        let a = Arc::new(Mutex::new(vec![]));
+        let b = Arc::clone(&a);
+        executor.add_function_coroutine(None, &world, move |mut s: Scope| async move {
+            // Both events are sent in the same frame, the second one isn't missed
+            for _ in 0..2 {
+                let hit = s.next_event::<Hit>().await;
+                b.lock().unwrap().push(hit.0);
+            }
+            let batch = s.next_events::<Hit>().await;
+            b.lock().unwrap().extend(batch.iter().map(|h| h.0 * 10));
+        });
+
+        executor.tick(&mut world);
+        assert!(a.lock().unwrap().is_empty());
+        world.send_event(Hit(1));
+        world.send_event(Hit(2));
+        executor.tick(&mut world);
+        executor.tick(&mut world);
+        assert_eq!(*a.lock().unwrap(), vec![1, 2]);
+
+        world.resource_mut::<Events<Hit>>().update();
+        world.send_event(Hit(3));
+        world.send_event(Hit(4));
+        executor.tick(&mut world);
+        assert_eq!(*a.lock().unwrap(), vec![1, 2, 30, 40]);
+    }
+
+    async fn increment_each_tick(s: &mut Scope, example: &mut Wr<ExampleComponent>, times: u32) {
+        for _ in 0..times {
+            s.next_tick().await;
+            example.get_mut(s).0 += 1;
+        }
+    }
+
+    struct Counter {
+        s: SubScope,
+        count: Arc<Mutex<u32>>,
+    }
+
+    impl Counter {
+        async fn count_ticks(&mut self, ticks: u32) {
+            for _ in 0..ticks {
+                self.s.next_tick().await;
+                *self.count.lock().unwrap() += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn waiting_on_collisions() {
+        use super::physics::CollisionEvent;
+
+        #[derive(Event)]
+        struct Contact(Entity, Entity, bool);
+
+        impl CollisionEvent for Contact {
+            fn started(&self) -> Option<(Entity, Entity)> {
+                self.2.then_some((self.0, self.1))
+            }
+        }
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Events<Contact>>();
+        let [hero, wall, coin] = [(); 3].map(|_| world.spawn_empty().id());
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        root_coroutine(move |mut s: Scope| async move {
+            let other = s.collision_started::<Contact>(hero).await;
+            l.lock().unwrap().push(other);
+            s.collision_with::<Contact>(coin, hero).await;
+            l.lock().unwrap().push(coin);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            // Stopped collisions and the ones of other entities are ignored
+            w.send_event(Contact(hero, wall, false));
+            w.send_event(Contact(coin, wall, true));
+            executor.tick(w);
+            assert!(log.lock().unwrap().is_empty());
+
+            // The entities may be reported in any order
+            w.send_event(Contact(wall, hero, true));
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), [wall]);
+            w.send_event(Contact(hero, wall, true));
+            w.send_event(Contact(hero, coin, true));
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), [wall, coin]);
+        });
+    }
+
+    #[test]
+    fn waiting_on_mouse_wheel() {
+        use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Events<MouseWheel>>();
+
+        let scrolled = Arc::new(Mutex::new(Vec::new()));
+        let s2 = Arc::clone(&scrolled);
+        root_coroutine(|mut s: Scope| async move {
+            loop {
+                let wheel = s.mouse_wheel().await;
+                s2.lock().unwrap().push(wheel.y);
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            for y in [1.0, 2.0] {
+                w.send_event(MouseWheel {
+                    unit: MouseScrollUnit::Line,
+                    x: 0.0,
+                    y,
+                    window: Entity::PLACEHOLDER,
+                });
+            }
+            // Both events are seen, even though the coroutine waits on them one at a time
+            executor.tick(w);
+            assert_eq!(*scrolled.lock().unwrap(), [1.0, 2.0]);
+        });
+    }
+
+    #[test]
+    fn resuming_quest_from_checkpoint() {
+        async fn quest(s: Scope, log: Arc<Mutex<Vec<&'static str>>>) {
+            let mut q = s.quest("main");
+            if !q.reached("met_elder") {
+                log.lock().unwrap().push("elder");
+                q.next_tick().await;
+                q.checkpoint("met_elder").await;
+            }
+            log.lock().unwrap().push("cave");
+            q.next_tick().await;
+            q.checkpoint("found_sword").await;
+        }
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        root_coroutine(quest.with_args((Arc::clone(&log),))).apply(&mut world);
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            for _ in 0..3 {
+                executor.tick(w);
+            }
+        });
+        assert_eq!(*log.lock().unwrap(), ["elder", "cave"]);
+        assert_eq!(
+            world.resource::<Checkpoints>().last("main"),
+            Some("met_elder")
+        );
+
+        // Load the saved progress in a new world
+        let saved = world.resource::<Checkpoints>().clone();
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.insert_resource(saved);
+        let log = Arc::new(Mutex::new(Vec::new()));
+        root_coroutine(quest.with_args((Arc::clone(&log),))).apply(&mut world);
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            for _ in 0..3 {
+                executor.tick(w);
+            }
+        });
+        assert_eq!(*log.lock().unwrap(), ["cave"]);
+        assert!(world
+            .resource::<Checkpoints>()
+            .reached("main", "found_sword"));
+    }
+
+    #[test]
+    fn running_state_machine() {
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        enum State {
+            Idle,
+            Walk(u32),
+            Stunned,
+            Done,
+        }
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (enter, exit) = (Arc::clone(&log), Arc::clone(&log));
+        let machine = StateMachine::new()
+            .state(State::Idle, |mut s: Scope| async move {
+                s.next_tick().await;
+                State::Walk(5)
+            })
+            .state_with(
+                |state| matches!(state, State::Walk(_)),
+                |mut s: Scope, state: State| async move {
+                    let State::Walk(steps) = state else {
+                        unreachable!()
+                    };
+                    for _ in 0..steps {
+                        s.next_tick().await;
+                    }
+                    State::Idle
+                },
+            )
+            .state(State::Stunned, |_: Scope| async move { State::Done })
+            .on_enter(move |_, state| enter.lock().unwrap().push(format!("enter {state:?}")))
+            .on_exit(move |_, state| exit.lock().unwrap().push(format!("exit {state:?}")));
+        let transitions = machine.transitions();
+
+        root_coroutine(|mut s: Scope| async move {
+            assert_eq!(machine.run(&mut s, State::Idle).await, State::Done);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            for _ in 0..3 {
+                executor.tick(w);
+            }
+            transitions.force(State::Stunned);
+            for _ in 0..3 {
+                executor.tick(w);
+            }
+        });
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "enter Idle",
+                "exit Idle",
+                "enter Walk(5)",
+                "exit Walk(5)",
+                "enter Stunned",
+                "exit Stunned",
+                "enter Done",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rhai")]
+    fn running_script() {
+        use super::scripting::ScriptBridge;
+        use rhai::Dynamic;
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&a);
+        let bridge = ScriptBridge::new().register("log", 1, move |args| {
+            let log = Arc::clone(&b);
+            boxed_coroutine(move |_: Scope| async move {
+                log.lock().unwrap().push(args[0].as_int().unwrap());
+                Dynamic::UNIT
+            })
+        });
+        let (done, finished) = std::sync::mpsc::channel();
+        root_coroutine(move |mut s: Scope| async move {
+            // Long enough to be suspended before the second call
+            let script = "log(1); next_tick(); for i in 0..20000 {} log(2);";
+            let result = bridge.run(&mut s, rhai::Scope::new(), script).await;
+            done.send(result).unwrap();
+        })
+        .apply(&mut world);
+
+        // The script only runs while its coroutine is resumed, no need to wait for its thread
+        let result = world.resource_scope(|w, mut executor: Mut<Executor>| {
+            (0..20).find_map(|_| {
+                executor.tick(w);
+                finished.try_recv().ok()
+            })
+        });
+        assert_eq!(result, Some(Ok(())));
+        assert_eq!(*a.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "dialogue")]
+    fn running_dialogue() {
+        use super::dialogue::*;
+
+        let node = |line: &str, commands: &[&str], next: Next| DialogueNode {
+            speaker: None,
+            line: line.to_owned(),
+            commands: commands.iter().map(|c| c.to_string()).collect(),
+            next,
+        };
+        let choice = |text: &str, next: &str, condition: Option<&str>| Choice {
+            text: text.to_owned(),
+            next: next.to_owned(),
+            condition: condition.map(str::to_owned),
+        };
+        let dialogue = Dialogue {
+            start: "hello".to_owned(),
+            nodes: [
+                (
+                    "hello".to_owned(),
+                    node(
+                        "Hello",
+                        &[],
+                        Next::Choices(vec![
+                            choice("Open the door", "open", Some("has_key")),
+                            choice("Leave", "bye", None),
+                        ]),
+                    ),
+                ),
+                ("open".to_owned(), node("It's open", &[], Next::End)),
+                ("bye".to_owned(), node("Bye", &["wave"], Next::End)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let steps = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&steps);
+        root_coroutine(move |s: Scope| async move {
+            let mut runner = DialogueRunner::new(dialogue).with_condition(|_, _| false);
+            while let Some(step) = runner.next(&s) {
+                if let Step::Choices(_) = step {
+                    runner.choose(0);
+                }
+                b.lock().unwrap().push(step);
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+        });
+        let line = |text: &str| Step::Line {
+            speaker: None,
+            text: text.to_owned(),
+        };
+        assert_eq!(
+            *steps.lock().unwrap(),
+            [
+                line("Hello"),
+                Step::Choices(vec!["Leave".to_owned()]),
+                Step::Command("wave".to_owned()),
+                line("Bye"),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "dialogue")]
+    fn running_loaded_dialogue() {
+        use super::dialogue::*;
+        use bevy::prelude::{Text, TextStyle};
+
+        let dialogue = Dialogue::from_ron(
+            r#"(
+                start: "hello",
+                nodes: {
+                    "hello": (
+                        speaker: Some("Guard"),
+                        line: "Halt!",
+                        next: Choices([
+                            (text: "Fight", next: "fight"),
+                            (text: "Flee", next: "flee"),
+                        ]),
+                    ),
+                    "fight": (line: "En garde!"),
+                    "flee": (line: "Coward!", commands: ["run"]),
+                },
+            )"#,
+        )
+        .unwrap();
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Input<KeyCode>>();
+        let text = world
+            .spawn(Text::from_section("", TextStyle::default()))
+            .id();
+
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let c = Arc::clone(&commands);
+        let done = Arc::new(Mutex::new(false));
+        let d = Arc::clone(&done);
+        root_coroutine(move |mut s: Scope| async move {
+            run_dialogue(&mut s, DialogueRunner::new(dialogue), text, |_, command| {
+                c.lock().unwrap().push(command.to_owned());
+            })
+            .await;
+            *d.lock().unwrap() = true;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let shown = |w: &World| w.get::<Text>(text).unwrap().sections[0].value.clone();
+            let press = |w: &mut World, key: Option<KeyCode>| {
+                let mut input = w.resource_mut::<Input<KeyCode>>();
+                input.release_all();
+                input.clear();
+                if let Some(key) = key {
+                    input.press(key);
+                }
+            };
+
+            // The line is typed, then the player advances to the choices
+            executor.tick(w);
+            executor.tick(w);
+            advance_time(w, Duration::from_secs(1));
+            executor.tick(w);
+            assert_eq!(shown(w), "Guard: Halt!");
+            press(w, Some(KeyCode::Space));
+            executor.tick(w);
+            assert_eq!(shown(w), "1. Fight\n2. Flee");
+
+            press(w, Some(KeyCode::Key2));
+            executor.tick(w);
+            assert_eq!(*commands.lock().unwrap(), ["run"]);
+            press(w, None);
+            executor.tick(w);
+            executor.tick(w);
+            assert_eq!(shown(w), "Coward!");
+            assert!(!*done.lock().unwrap());
+            press(w, Some(KeyCode::Space));
+            executor.tick(w);
+        });
+        assert!(*done.lock().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "dialogue")]
+    fn ending_dialogue_without_available_choices() {
+        use super::dialogue::*;
+
+        let choice = Choice {
+            text: "Open the door".to_owned(),
+            next: "hello".to_owned(),
+            condition: Some("has_key".to_owned()),
+        };
+        let hello = DialogueNode {
+            line: "Hello".to_owned(),
+            next: Next::Choices(vec![choice]),
+            ..Default::default()
+        };
+        let dialogue = Dialogue {
+            start: "hello".to_owned(),
+            nodes: [("hello".to_owned(), hello)].into_iter().collect(),
+        };
+
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+
+        let steps = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&steps);
+        root_coroutine(move |s: Scope| async move {
+            let mut runner = DialogueRunner::new(dialogue).with_condition(|_, _| false);
+            while let Some(step) = runner.next(&s) {
+                b.lock().unwrap().push(step);
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+        });
+        assert_eq!(
+            *steps.lock().unwrap(),
+            [Step::Line {
+                speaker: None,
+                text: "Hello".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "behavior-tree")]
+    fn running_behavior_tree() {
+        use super::behavior::{Node, Policy};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let leaf = |name: &'static str, ticks: u32, success: bool| {
+            let log = Arc::clone(&log);
+            Node::leaf(move |mut s: Scope| async move {
+                for _ in 0..ticks {
+                    s.next_tick().await;
+                }
+                log.lock().unwrap().push(name);
+                success
+            })
+        };
+
+        let tree = Node::selector([
+            Node::sequence([leaf("a", 0, true), leaf("b", 1, false), leaf("c", 0, true)]),
+            Node::parallel(Policy::Any, [leaf("d", 2, true), leaf("e", 5, true)]),
+            leaf("f", 0, true),
+        ])
+        .invert()
+        .invert();
+
+        let result = Arc::new(Mutex::new(None));
+        let r = Arc::clone(&result);
+        root_coroutine(move |mut s: Scope| async move {
+            *r.lock().unwrap() = Some(tree.run(&mut s).await);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            for _ in 0..8 {
+                executor.tick(w);
+            }
+        });
+        assert_eq!(*result.lock().unwrap(), Some(true));
+        assert_eq!(*log.lock().unwrap(), vec!["a", "b", "d"]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "awaited two things at once")]
+    fn awaiting_from_scope_and_sub_scope() {
+        use std::future::Future;
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        root_coroutine(|mut s: Scope| async move {
+            let mut sub = s.sub_scope();
+            let mut a = std::pin::pin!(s.next_tick());
+            let mut b = std::pin::pin!(sub.next_tick());
+            std::future::poll_fn(|cx| {
+                let _ = a.as_mut().poll(cx);
+                b.as_mut().poll(cx)
+            })
+            .await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "after being resumed")]
+    fn holding_component_across_await() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        // Lifetimes don't prevent it, since the sub-scope is borrowed instead of the scope
+        coroutine(|mut s: Scope, read: Rd<ExampleComponent>| async move {
+            let sub = s.sub_scope();
+            let value = read.get(&sub);
+            s.next_tick().await;
+            assert_eq!(value.0, 0);
+        })
+        .apply(e, &mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "while it still borrows the world")]
+    fn writing_while_reading_the_world() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        coroutine(|s: Scope, mut write: Wr<ExampleComponent>| async move {
+            let world = s.world();
+            write.get_mut(&s).0 += 1;
+            drop(world);
+        })
+        .apply(e, &mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+        });
+    }
+
+    #[test]
+    fn helper_holding_sub_scope() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        root_coroutine(move |mut s: Scope| async move {
+            let mut counter = Counter {
+                s: s.sub_scope(),
+                count: b,
+            };
+            counter.count_ticks(2).await;
+            s.next_tick().await;
+            counter.count_ticks(1).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            for i in 0..3 {
+                executor.tick(w);
+                assert_eq!(*a.lock().unwrap(), i);
+            }
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 2);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 3);
+        });
+    }
+
+    #[test]
+    fn helper_borrowing_scope() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        coroutine(
+            |mut s: Scope, mut example: Wr<ExampleComponent>| async move {
+                increment_each_tick(&mut s, &mut example, 2).await;
+                increment_each_tick(&mut s, &mut example, 3).await;
+            },
+        )
+        .apply(e, &mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+        assert_eq!(world.get::<ExampleComponent>(e).unwrap().0, 5);
+    }
+
+    #[test]
+    fn non_send_coroutine() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        let counter = std::rc::Rc::new(std::cell::Cell::new(0));
+        let c = counter.clone();
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.add_non_send_coroutine(None, w, move |mut s: Scope| async move {
+                s.next_tick().await;
+                c.set(c.get() + 1);
+                let c = c.clone();
+                let handle = s.start_non_send(move |mut s: Scope| async move {
+                    s.next_tick().await;
+                    c.set(c.get() + 1);
+                    c.get()
+                });
+                *b.lock().unwrap() = s.all(handle).await;
+            });
+            executor.tick_until_empty(w);
+        });
+        assert_eq!(counter.get(), 2);
+        assert_eq!(*a.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn dropping_non_send_coroutine_from_another_thread() {
+        let mut world = World::new();
+        world.insert_resource(Time::new(Instant::now()));
+        let counter = std::rc::Rc::new(());
+
+        let mut executor = Executor::default();
+        let c = counter.clone();
+        executor.add_non_send_coroutine(None, &world, move |mut s: Scope| async move {
+            let _c = c;
+            loop {
+                s.next_tick().await;
+            }
+        });
+        executor.tick(&mut world);
+
+        // The `Rc` isn't touched from the other thread, the future is leaked instead
+        thread::spawn(move || drop(executor)).join().unwrap();
+        assert_eq!(std::rc::Rc::strong_count(&counter), 2);
+
+        let mut executor = Executor::default();
+        let c = counter.clone();
+        executor.add_non_send_coroutine(None, &world, move |mut s: Scope| async move {
+            let _c = c;
+            s.next_tick().await;
+        });
+        executor.tick(&mut world);
+        drop(executor);
+        assert_eq!(std::rc::Rc::strong_count(&counter), 2);
+    }
+
+    #[test]
+    fn once_channel_across_threads() {
+        use crate::function_coroutine::once_channel::sync_once_channel;
+
+        // The message is dropped exactly once, whichever end goes first
+        let message = Arc::new(());
+        for i in 0..100 {
+            let (sender, receiver) = sync_once_channel();
+            let m = Arc::clone(&message);
+            let send = thread::spawn(move || match i % 2 {
+                0 => sender.send(m),
+                _ => drop(sender),
+            });
+            let recv = thread::spawn(move || {
+                if i % 3 == 0 {
+                    drop(receiver);
+                    return;
+                }
+                loop {
+                    match receiver.try_recv() {
+                        Ok(_) => {
+                            assert!(receiver.try_recv().is_err());
+                            return;
+                        }
+                        Err(oneshot::TryRecvError::Disconnected) => return,
+                        Err(oneshot::TryRecvError::Empty) => thread::yield_now(),
+                    }
+                }
+            });
+            send.join().unwrap();
+            recv.join().unwrap();
+        }
+        assert_eq!(Arc::strong_count(&message), 1);
+
+        // An unread message is dropped along with the channel
+        let (sender, receiver) = sync_once_channel();
+        sender.send(Arc::clone(&message));
+        thread::spawn(move || drop(receiver)).join().unwrap();
+        assert_eq!(Arc::strong_count(&message), 1);
+    }
+
+    #[test]
+    fn waiting_on_float_seconds() {
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        root_coroutine(|mut s: Scope| async move {
+            s.duration(1.5).await;
+            *b.lock().unwrap() += 1;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            executor.advance(1.0);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 0);
+            executor.advance(Duration::from_millis(500));
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 1);
+        });
+    }
+
+    #[test]
+    fn waiting_on_all_array_result() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        root_coroutine(|mut s: Scope| async move {
+            let handles = [2, 0, 1].map(|i| {
+                s.start(move |mut s: Scope| async move {
+                    for _ in 0..i {
+                        s.next_tick().await;
+                    }
+                    i
+                })
+            });
+
+            let res = s.all(handles).await;
+            assert_eq!(res, [2, 0, 1]);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+    }
+
+    fn resumed_with_budget(fairness: Fairness) -> Vec<u32> {
+        let mut world = World::new();
+        let mut executor = Executor::default();
+        executor.set_resume_budget(Some(1));
+        executor.set_fairness(fairness);
+        world.insert_resource(executor);
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        for i in 0..3 {
+            let b = Arc::clone(&a);
+            root_coroutine(move |mut s: Scope| async move {
+                loop {
+                    b.lock().unwrap().push(i);
+                    s.next_tick().await;
+                }
+            })
+            .apply(&mut world);
+        }
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            for _ in 0..4 {
+                executor.tick(w);
+            }
+        });
+        let resumed = a.lock().unwrap().clone();
+        resumed
+    }
+
+    #[test]
+    fn resuming_within_budget() {
+        assert_eq!(resumed_with_budget(Fairness::InOrder), vec![0, 0, 0, 0]);
+        assert_eq!(resumed_with_budget(Fairness::RoundRobin), vec![0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn recovering_from_panic() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        root_coroutine(|mut s: Scope| async move {
+            s.next_tick().await;
+            panic!("scripted panic");
+        })
+        .apply(&mut world);
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        root_coroutine(move |mut s: Scope| async move {
+            loop {
+                *b.lock().unwrap() += 1;
+                s.next_tick().await;
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                executor.tick(w);
+            }));
+            assert!(result.is_err());
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 3);
+            assert_eq!(executor.info().coroutines.len(), 1);
+        });
+    }
+
+    #[test]
+    fn recovering_from_panic_in_parallel() {
+        let mut world = World::new();
+        let mut executor = Executor::default();
+        // Safety: The coroutines don't access the world.
+        unsafe { executor.enable_parallel_resume() };
+        world.insert_resource(executor);
+        world.insert_resource(Time::new(Instant::now()));
+
+        for _ in 0..2 {
+            root_coroutine(|mut s: Scope| async move {
+                s.next_tick().await;
+                panic!("scripted panic");
+            })
+            .apply(&mut world);
+        }
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        root_coroutine(move |mut s: Scope| async move {
+            loop {
+                *b.lock().unwrap() += 1;
+                s.next_tick().await;
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                executor.tick(w);
+            }));
+            assert!(result.is_err());
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 3);
+            assert_eq!(executor.info().coroutines.len(), 1);
+        });
+    }
+
+    #[test]
+    fn detecting_deadlocks() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let slot: Arc<Mutex<Option<CoroHandle<()>>>> = Arc::new(Mutex::new(None));
+        let b_slot = Arc::clone(&slot);
+        root_coroutine(move |mut s: Scope| async move {
+            let b = s.start(move |mut s: Scope| async move {
+                let c = loop {
+                    if let Some(c) = b_slot.lock().unwrap().take() {
+                        break c;
+                    }
+                    s.next_tick().await;
+                };
+                s.on(c).await;
+            });
+            let c = s.start(move |mut s: Scope| async move {
+                s.on(b).await;
+            });
+            *slot.lock().unwrap() = Some(c);
+            loop {
+                s.next_tick().await;
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            assert!(executor.deadlocks().is_empty());
+            executor.tick(w);
+            executor.tick(w);
+            assert_eq!(executor.deadlocks().len(), 2);
+        });
+    }
+
+    #[test]
+    fn resuming_in_parallel() {
+        let mut world = World::new();
+        let mut executor = Executor::default();
+        // Safety: The coroutines only access the world through their parameters.
+        unsafe { executor.enable_parallel_resume() };
+        world.insert_resource(executor);
+        world.insert_resource(Time::new(Instant::now()));
+        let entities = [
+            world.spawn(ExampleComponent(0)).id(),
+            world.spawn(ExampleComponent(0)).id(),
+        ];
+
+        for e in entities {
+            coroutine(
+                |mut s: Scope, mut example: Wr<ExampleComponent>| async move {
+                    for _ in 0..3 {
+                        example.get_mut(&s).0 += 1;
+                        s.next_tick().await;
+                    }
+                },
+            )
+            .apply(e, &mut world);
+        }
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+
+        for e in entities {
+            assert_eq!(world.get::<ExampleComponent>(e).unwrap().0, 3);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "accessed the world in a way that conflicts")]
+    fn rejecting_world_reads_in_parallel() {
+        let mut world = World::new();
+        let mut executor = Executor::default();
+        // Safety: The coroutines only access the world through this crate.
+        unsafe { executor.enable_parallel_resume() };
+        world.insert_resource(executor);
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        coroutine(
+            |mut s: Scope, mut example: Wr<ExampleComponent>| async move {
+                loop {
+                    example.get_mut(&s).0 += 1;
+                    s.next_tick().await;
+                }
+            },
+        )
+        .apply(e, &mut world);
+        // Reading the whole world while the other coroutine writes to it is rejected
+        root_coroutine(|mut s: Scope| async move {
+            s.until(|_| None::<()>).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+        });
+    }
+
+    #[coroutine_fn]
+    async fn increment_by(mut s: Scope, amount: u32, #[param] mut example: Wr<ExampleComponent>) {
+        s.next_tick().await;
+        example.get_mut(&s).0 += amount;
+    }
+
+    #[test]
+    fn coroutine_fn_attribute() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        coroutine(increment_by(3)).apply(e, &mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+        assert_eq!(world.get::<ExampleComponent>(e).unwrap().0, 3);
+    }
+
+    async fn answer(mut s: Scope) -> u32 {
+        s.next_tick().await;
+        42
+    }
+
+    #[test]
+    fn plain_async_fn() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        root_coroutine(answer).apply(&mut world);
+        root_coroutine(move |mut s: Scope| async move {
+            let handle = s.start(answer);
+            *b.lock().unwrap() = s.all(handle).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+        assert_eq!(*a.lock().unwrap(), 42);
+    }
+
+    async fn store(mut s: Scope, out: Arc<Mutex<u32>>, value: u32) {
+        s.next_tick().await;
+        *out.lock().unwrap() = value;
+    }
+
+    #[test]
+    fn coroutine_with_args() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(0));
+        root_coroutine(store.with_args((Arc::clone(&a), 7))).apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+        assert_eq!(*a.lock().unwrap(), 7);
+    }
+
+    #[test]
+    fn any_handles() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&a);
+        root_coroutine(move |mut s: Scope| async move {
+            let number = s.start(|mut s: Scope| async move {
+                s.next_tick().await;
+                3u32
+            });
+            let text = s.start(|mut s: Scope| async move {
+                s.next_tick().await;
+                s.next_tick().await;
+                "done"
+            });
+
+            let handles = vec![number.into_any(), text.into_any()];
+            s.all(handles).await;
+            b.lock().unwrap().push(0);
+
+            let number = s.start(|_: Scope| async move { 5u32 }).into_any();
+            let number = match number.downcast::<&str>() {
+                Ok(_) => panic!("the output of the coroutine is a u32"),
+                Err(number) => number.downcast::<u32>().ok().unwrap(),
+            };
+            let value: u32 = s.on(number).await;
+            b.lock().unwrap().push(value);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+        assert_eq!(*a.lock().unwrap(), vec![0, 5]);
+    }
+
+    #[test]
+    fn cleanup_guard_runs_on_cancel() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        root_coroutine(move |mut s: Scope| async move {
+            let guarded = s.start(move |mut s: Scope| async move {
+                let _guard = s.cleanup_guard(move |w| {
+                    w.get_mut::<ExampleComponent>(e).unwrap().0 = 1;
+                });
+                loop {
+                    s.next_tick().await;
+                }
+            });
+            let other = s.start(|mut s: Scope| async move {
+                s.next_tick().await;
+            });
+            s.first([guarded, other]).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            assert_eq!(w.get::<ExampleComponent>(e).unwrap().0, 0);
+            executor.tick(w);
+            assert_eq!(w.get::<ExampleComponent>(e).unwrap().0, 1);
+        });
+    }
+
+    #[test]
+    fn dropping_finished_coroutines_within_budget() {
+        let mut world = World::new();
+        let mut executor = Executor::default();
+        executor.set_cleanup_budget(Some(1));
+        world.insert_resource(executor);
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        root_coroutine(move |mut s: Scope| async move {
+            for _ in 0..3 {
+                s.start_local(move |mut s: Scope| async move {
+                    let _guard = s.cleanup_guard(move |w| {
+                        w.get_mut::<ExampleComponent>(e).unwrap().0 += 1;
+                    });
+                    loop {
+                        s.next_tick().await;
+                    }
+                });
+            }
+            s.next_tick().await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            // The root coroutine ends on the second tick, and is dropped first
+            executor.tick(w);
+            executor.tick(w);
+            assert_eq!(w.get::<ExampleComponent>(e).unwrap().0, 0);
+            for i in 1..=3 {
+                executor.tick(w);
+                assert_eq!(w.get::<ExampleComponent>(e).unwrap().0, i);
+            }
+        });
+    }
+
+    #[test]
+    fn waiting_on_change_from_other_executor() {
+        let mut world = World::new();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world
+            .spawn((
+                ExampleComponent(0),
+                ChangeTracker::new() as ChangeTracker<ExampleComponent>,
+            ))
+            .id();
+
+        let mut writer = Executor::default();
+        let mut reader = Executor::default();
+        writer.add_function_coroutine(
+            Some(e),
+            &world,
+            |mut s: Scope, mut example: Wr<ExampleComponent>| async move {
+                for _ in 0..3 {
+                    s.next_tick().await;
+                    example.get_mut(&s).0 += 1;
+                }
+            },
+        );
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        for _ in 0..2 {
+            let b = Arc::clone(&a);
+            reader.add_function_coroutine(
+                Some(e),
+                &world,
+                move |mut s: Scope,
+                      on_change: OnChange<ExampleComponent>,
+                      rd: Rd<ExampleComponent>| async move {
+                    loop {
+                        on_change.observe(&mut s).await;
+                        b.lock().unwrap().push(rd.get(&s).0);
+                    }
+                },
+            );
+        }
+
+        writer.tick(&mut world);
+        reader.tick(&mut world);
+        for i in 1..=3 {
+            writer.tick(&mut world);
+            // The reader is not ticked in between, and still sees the first change
+            if i == 2 {
+                continue;
+            }
+            reader.tick(&mut world);
+            assert_eq!(a.lock().unwrap().len(), 2);
+            a.lock().unwrap().clear();
+        }
+    }
+
+    #[test]
+    fn waiting_on_internal_change() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let e = world
+            .spawn((
+                ExampleComponent(0),
+                ChangeTracker::new() as ChangeTracker<ExampleComponent>,
+            ))
+            .id();
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+
+        coroutine(
+            |mut s: Scope, mut example: Wr<ExampleComponent>| async move {
+                for _ in 0..5 {
+                    s.next_tick().await;
+                    example.get_mut(&s).0 += 1;
+                }
+            },
+        )
+        .apply(e, &mut world);
+
+        coroutine(
+            |mut fib: Scope, on_change: OnChange<ExampleComponent>| async move {
+                for _ in 0..5 {
+                    on_change.observe(&mut fib).await;
+                    *b.lock().unwrap() += 1;
+                }
+            },
+        )
+        .apply(e, &mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            for i in 0..5 {
+                executor.tick(w);
+                assert_eq!(*a.lock().unwrap(), i);
+            }
+        });
+    }
+
+    #[test]
+    fn boxed_coroutines() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        async fn wait_and_return(mut s: Scope) -> u32 {
+            s.next_tick().await;
+            1
+        }
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&a);
+        root_coroutine(move |mut s: Scope| async move {
+            let recipes: Vec<BoxedCoroutine<u32>> = vec![
+                boxed_coroutine(wait_and_return),
+                boxed_coroutine(|_: Scope| async { 2 }),
+                boxed_coroutine(|_: Scope| std::future::ready(3)),
+            ];
+            let handles: Vec<_> = recipes.into_iter().map(|c| s.start(c)).collect();
+            *b.lock().unwrap() = s.all(handles).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+        assert_eq!(*a.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn adding_coroutines_in_batch() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let entities: Vec<Entity> = (0..10)
+            .map(|i| world.spawn(ExampleComponent(i)).id())
+            .collect();
+
+        let increment = |s: Scope, mut example: Wr<ExampleComponent>| async move {
+            example.get_mut(&s).0 += 10;
+        };
+        coroutine_batch(entities.iter().map(|&e| (Some(e), increment))).apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+        for (i, e) in entities.into_iter().enumerate() {
+            assert_eq!(world.get::<ExampleComponent>(e).unwrap().0, i as u32 + 10);
+        }
+    }
+
+    #[test]
+    fn adding_coroutine_to_app() {
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&a);
+        let c = Arc::clone(&a);
+
+        let mut app = App::new();
+        app.add_coroutine(move |_: Scope| async move {
+            b.lock().unwrap().push(0);
+        })
+        .add_plugins(CorentinPlugin)
+        .add_coroutine(move |_: Scope| async move {
+            c.lock().unwrap().push(1);
+        })
+        .insert_resource(Time::new(Instant::now()));
+
+        assert!(a.lock().unwrap().is_empty());
+        app.update();
+        let mut started = a.lock().unwrap().clone();
+        started.sort();
+        assert_eq!(started, vec![0, 1]);
+    }
+
+    #[test]
+    fn updating_executor_info() {
+        use super::executor::info::ExecutorInfo;
+
+        let mut app = App::new();
+        app.add_plugins(CorentinPlugin)
+            .add_coroutine(|mut s: Scope| async move {
+                s.next_tick().await;
+            })
+            .insert_resource(Time::new(Instant::now()));
+        app.update();
+        // Only kept up to date by the `ExecutorInfoPlugin`
+        assert!(app.world.get_resource::<ExecutorInfo>().is_none());
+
+        app.add_plugins(ExecutorInfoPlugin);
+        app.update();
+        assert!(app.world.resource::<ExecutorInfo>().coroutines.is_empty());
+
+        app.add_coroutine(|mut s: Scope| async move {
+            s.next_tick().await;
+        });
+        app.update();
+        assert_eq!(app.world.resource::<ExecutorInfo>().coroutines.len(), 1);
+    }
+
+    #[test]
+    fn building_coroutine_with_options() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let id = executor
+                .build(|s: Scope, mut example: Wr<ExampleComponent>| async move {
+                    example.get_mut(&s).0 += 1;
+                })
+                .name("increment")
+                .owner(e)
+                .paused()
+                .spawn(w)
+                .unwrap();
+
+            let info = executor.info();
+            assert_eq!(info.coroutines[0].name, "increment");
+            assert_eq!(info.coroutines[0].owner, Some(e));
+
+            executor.tick(w);
+            assert_eq!(w.get::<ExampleComponent>(e).unwrap().0, 0);
+            executor.unpause(id);
+            executor.tick(w);
+            assert_eq!(w.get::<ExampleComponent>(e).unwrap().0, 1);
+
+            assert!(executor
+                .build(|_: Scope, _: Entity| async {})
+                .spawn(w)
+                .is_none());
+        });
+    }
+
+    #[test]
+    fn resuming_by_priority() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.set_resume_budget(Some(2));
+            for (name, priority) in [("low", -1), ("default", 0), ("high", 5)] {
+                let l = Arc::clone(&log);
+                executor
+                    .build(move |_: Scope| async move {
+                        l.lock().unwrap().push(name);
+                    })
+                    .priority(priority)
+                    .spawn(w);
+            }
+
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), ["high", "default"]);
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), ["high", "default", "low"]);
+        });
+    }
+
+    #[test]
+    fn canceling_tagged_coroutines() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let forever = |mut s: Scope| async move {
+                loop {
+                    s.next_tick().await;
+                }
+            };
+            let ai = executor.build(forever).tag("ai").spawn(w).unwrap();
+            let both = executor
+                .build(forever)
+                .tag("ai")
+                .tag("ui")
+                .spawn(w)
+                .unwrap();
+            let ui = executor.build(forever).tag("ui").spawn(w).unwrap();
+            executor.tick(w);
+
+            assert_eq!(executor.tags(both), ["ai", "ui"]);
+            let mut tagged: Vec<super::Id> = executor.tagged("ai").collect();
+            tagged.sort_by_key(|id| id.index());
+            assert_eq!(tagged, [ai, both]);
+
+            executor.cancel_tagged("ai");
+            executor.tick(w);
+            assert!(!executor.is_running(ai));
+            assert!(!executor.is_running(both));
+            assert!(executor.is_running(ui));
+            assert_eq!(executor.tagged("ai").count(), 0);
+            assert!(executor.tags(both).is_empty());
+        });
+    }
+
+    #[test]
+    fn owner_as_parameter() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&a);
+        coroutine(move |_: Scope, owner: Entity| async move {
+            b.lock().unwrap().push(owner);
+        })
+        .apply(e, &mut world);
+
+        let b = Arc::clone(&a);
+        root_coroutine(move |_: Scope, owner: Entity| async move {
+            b.lock().unwrap().push(owner);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick_until_empty(w);
+        });
+        assert_eq!(*a.lock().unwrap(), vec![e]);
+    }
+
+    #[test]
+    fn despawn_policy_of_a_wait() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        for policy in [
+            DespawnPolicy::Cancel,
+            DespawnPolicy::Resume,
+            DespawnPolicy::Sleep,
+        ] {
+            let b = Arc::clone(&a);
+            coroutine(
+                move |mut s: Scope, on_change: OnChange<ExampleComponent>| async move {
+                    on_change.observe(&mut s).on_despawn(policy).await;
+                    b.lock().unwrap().push(policy);
+                },
+            )
+            .apply(e, &mut world);
+        }
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            w.despawn(e);
+            executor.tick(w);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), vec![DespawnPolicy::Resume]);
+            assert_eq!(executor.info().coroutines.len(), 1);
+        });
+    }
+
+    #[test]
+    fn resuming_despawned_coroutine() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        let a = Arc::new(Mutex::new(None));
+        let b = Arc::clone(&a);
+        coroutine(
+            |mut s: Scope, on_change: OnChange<ExampleComponent>, rd: Rd<ExampleComponent>| async move {
+                s.set_despawn_policy(DespawnPolicy::Resume);
+                on_change.observe(&mut s).await;
+                *b.lock().unwrap() = Some(rd.try_get(&s).is_err());
+            },
+        )
+        .apply(e, &mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            w.despawn(e);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), Some(true));
+        });
+    }
+
+    #[test]
+    fn failing_write_does_not_wake_observers() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world
+            .spawn((
+                ExampleComponent(0),
+                ChangeTracker::new() as ChangeTracker<ExampleComponent>,
+            ))
+            .id();
+
+        let errors = Arc::new(Mutex::new(0));
+        let e2 = Arc::clone(&errors);
+        coroutine(
+            |mut s: Scope, mut example: Wr<ExampleComponent>| async move {
+                s.set_despawn_policy(DespawnPolicy::Resume);
+                loop {
+                    s.next_tick().await;
+                    if example.try_get_mut(&s).is_err() {
+                        *e2.lock().unwrap() += 1;
+                    }
+                }
+            },
+        )
+        .apply(e, &mut world);
+
+        let woken = Arc::new(Mutex::new(0));
+        let w2 = Arc::clone(&woken);
+        coroutine(
+            |mut s: Scope, on_change: OnChange<ExampleComponent>| async move {
+                s.set_despawn_policy(DespawnPolicy::Resume);
+                loop {
+                    on_change.observe(&mut s).await;
+                    *w2.lock().unwrap() += 1;
+                }
+            },
+        )
+        .apply(e, &mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            w.entity_mut(e).remove::<ExampleComponent>();
+            // The observer is resumed once, since its component was removed, but the writes
+            // failing afterwards don't wake it up
+            for _ in 0..3 {
+                executor.tick(w);
+            }
+        });
+        assert_eq!(*errors.lock().unwrap(), 3);
+        assert_eq!(*woken.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn reporting_stalled_coroutines() {
+        use super::executor::watchdog::StallThreshold;
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        coroutine(
+            |mut s: Scope, on_change: OnChange<ExampleComponent>| async move {
+                on_change.observe(&mut s).await;
+            },
+        )
+        .apply(e, &mut world);
+
+        let a = Arc::new(Mutex::new(Vec::new()));
+        let b = Arc::clone(&a);
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.set_watchdog_with(StallThreshold::Ticks(2), move |stall| {
+                b.lock().unwrap().push(stall.ticks);
+            });
+            for _ in 0..6 {
+                executor.tick(w);
+            }
+        });
+        assert_eq!(*a.lock().unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn applying_commands_from_coroutine() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        root_coroutine(|mut s: Scope| async move {
+            let e = s.commands().spawn(ExampleComponent(0)).id();
+            s.next_tick().await;
+            s.commands().entity(e).remove::<ExampleComponent>();
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|world, mut executor: Mut<Executor>| {
+            let mut state = world.query::<&ExampleComponent>();
+            assert_eq!(state.iter(world).len(), 0);
+            executor.tick(world);
+            assert_eq!(state.iter(world).len(), 1);
+            executor.tick(world);
+            assert_eq!(state.iter(world).len(), 0);
+        });
+    }
+
+    #[test]
+    fn consuming_external_stream() {
+        use futures_core::Stream;
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        // A stream which is only ready every other poll
+        struct Alternate {
+            ready: bool,
+            remaining: u32,
+        }
+
+        impl Stream for Alternate {
+            type Item = u32;
+
+            fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<u32>> {
+                self.ready = !self.ready;
+                if !self.ready {
+                    return Poll::Pending;
+                }
+                if self.remaining == 0 {
+                    return Poll::Ready(None);
+                }
+                self.remaining -= 1;
+                Poll::Ready(Some(self.remaining))
+            }
+        }
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+
+        root_coroutine(|mut s: Scope| async move {
+            let mut stream = s.stream(Alternate {
+                ready: true,
+                remaining: 2,
+            });
+            while stream.next(&mut s).await.is_some() {
+                *b.lock().unwrap() += 1;
+            }
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 0);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 1);
+            executor.tick(w);
+            assert_eq!(*a.lock().unwrap(), 2);
+            executor.tick_until_empty(w);
+            assert_eq!(*a.lock().unwrap(), 2);
+        });
+    }
+
+    #[test]
+    fn waiting_on_keys() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Input<KeyCode>>();
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        root_coroutine(|mut s: Scope| async move {
+            s.key_just_pressed(KeyCode::Space).await;
+            l.lock().unwrap().push(KeyCode::Space);
+            // The conditions are checked right away, so the key would still be just pressed
+            s.next_tick().await;
+            let key = s.any_key_just_pressed().await;
+            l.lock().unwrap().push(key);
+            s.until(|w| {
+                w.resource::<Input<KeyCode>>()
+                    .pressed(KeyCode::Up)
+                    .then_some(())
+            })
+            .await;
+            l.lock().unwrap().push(KeyCode::Up);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let mut press = |executor: &mut Executor, key: Option<KeyCode>| {
+                let mut input = w.resource_mut::<Input<KeyCode>>();
+                input.clear();
+                if let Some(key) = key {
+                    input.press(key);
+                }
+                executor.tick(w);
+            };
+
+            press(&mut executor, Some(KeyCode::A));
+            assert!(log.lock().unwrap().is_empty());
+            press(&mut executor, Some(KeyCode::Space));
+            press(&mut executor, None);
+            press(&mut executor, Some(KeyCode::B));
+            assert_eq!(*log.lock().unwrap(), [KeyCode::Space, KeyCode::B]);
+            press(&mut executor, Some(KeyCode::Up));
+            assert_eq!(
+                *log.lock().unwrap(),
+                [KeyCode::Space, KeyCode::B, KeyCode::Up]
+            );
+        });
+    }
+
+    #[test]
+    fn waiting_on_gamepad() {
+        use bevy::input::{
+            gamepad::{
+                GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadInfo,
+            },
+            InputPlugin,
+        };
+        use bevy::prelude::{Gamepad, GamepadButtonType};
+
+        let mut app = App::new();
+        app.add_plugins((InputPlugin, CorentinPlugin))
+            .insert_resource(Time::new(Instant::now()));
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        root_coroutine(move |mut s: Scope| async move {
+            let gamepad = s.gamepad_connected().await;
+            l.lock().unwrap().push("connected");
+            s.gamepad_button(gamepad, GamepadButtonType::South).await;
+            l.lock().unwrap().push("south");
+            s.gamepad_disconnected(gamepad).await;
+            l.lock().unwrap().push("disconnected");
+        })
+        .apply(&mut app.world);
+        app.update();
+
+        let gamepad = Gamepad::new(0);
+        let connect = |app: &mut App, connection| {
+            app.world.send_event(GamepadConnectionEvent {
+                gamepad,
+                connection,
+            });
+            app.update();
+        };
+        connect(
+            &mut app,
+            GamepadConnection::Connected(GamepadInfo {
+                name: "pad".to_owned(),
+            }),
+        );
+        assert_eq!(*log.lock().unwrap(), ["connected"]);
+
+        for button_type in [GamepadButtonType::North, GamepadButtonType::South] {
+            app.world.send_event(GamepadButtonChangedEvent {
+                gamepad,
+                button_type,
+                value: 1.0,
+            });
+            app.update();
+        }
+        assert_eq!(*log.lock().unwrap(), ["connected", "south"]);
+
+        connect(&mut app, GamepadConnection::Disconnected);
+        assert_eq!(*log.lock().unwrap(), ["connected", "south", "disconnected"]);
+    }
+
+    #[test]
+    fn clicking_buttons() {
+        use bevy::prelude::Interaction;
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let a = world.spawn(Interaction::Pressed).id();
+        let b = world.spawn(Interaction::None).id();
+
+        let clicks = Arc::new(Mutex::new(Vec::new()));
+        let c = Arc::clone(&clicks);
+        root_coroutine(move |mut s: Scope| async move {
+            s.clicked(a).await;
+            c.lock().unwrap().push(a);
+            let button = s.clicked_any([a, b]).await;
+            c.lock().unwrap().push(button);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            // Already pressed, it must be released first
+            executor.tick(w);
+            let mut interact = |executor: &mut Executor, button, interaction| {
+                *w.get_mut::<Interaction>(button).unwrap() = interaction;
+                executor.tick(w);
+            };
+
+            interact(&mut executor, a, Interaction::Hovered);
+            assert!(clicks.lock().unwrap().is_empty());
+            interact(&mut executor, a, Interaction::Pressed);
+            assert_eq!(*clicks.lock().unwrap(), [a]);
+
+            interact(&mut executor, b, Interaction::Pressed);
+            assert_eq!(*clicks.lock().unwrap(), [a, b]);
+        });
+    }
+
+    #[test]
+    fn waiting_on_touches() {
+        use bevy::input::{
+            touch::{TouchInput, TouchPhase},
+            InputPlugin,
+        };
+        use bevy::prelude::Vec2;
+
+        let mut app = App::new();
+        app.add_plugins((InputPlugin, CorentinPlugin))
+            .insert_resource(Time::new(Instant::now()));
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        root_coroutine(move |mut s: Scope| async move {
+            let touch = s.touch_started().await;
+            l.lock().unwrap().push(format!("started {}", touch.id()));
+            let at = s.tap(10.0, Duration::from_secs(1)).await;
+            l.lock().unwrap().push(format!("tap {}", at.x));
+            // The second touch is too short to be a drag
+            let drag = s.drag(50.0).await;
+            l.lock()
+                .unwrap()
+                .push(format!("drag {} {}", drag.id, drag.end.x));
+        })
+        .apply(&mut app.world);
+        app.update();
+
+        let mut touch = |id, phase, x| {
+            app.world.send_event(TouchInput {
+                phase,
+                position: Vec2::new(x, 0.0),
+                force: None,
+                id,
+            });
+            app.update();
+        };
+        // Bevy keeps the last position a touch moved to when it ends
+        for (id, x) in [(0, 2.0), (1, 20.0), (2, 100.0)] {
+            touch(id, TouchPhase::Started, 0.0);
+            touch(id, TouchPhase::Moved, x);
+            touch(id, TouchPhase::Ended, x);
+        }
+        assert_eq!(*log.lock().unwrap(), ["started 0", "tap 2", "drag 2 100"]);
+    }
+
+    #[test]
+    fn input_sequence_within_windows() {
+        use super::input::sequence::InputSequence;
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Input<KeyCode>>();
+
+        let result = Arc::new(Mutex::new(None));
+        let r = Arc::clone(&result);
+        root_coroutine(|mut s: Scope| async move {
+            let sequence = InputSequence::new(KeyCode::Down)
+                .then(KeyCode::Right, Duration::from_millis(200))
+                .then(KeyCode::J, Duration::from_millis(200));
+            *r.lock().unwrap() = Some(s.input_sequence(sequence).await);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            // Nothing happens until the first step is pressed, however long it takes
+            advance_time(w, Duration::from_secs(5));
+            executor.tick(w);
+            for key in [KeyCode::Down, KeyCode::Right, KeyCode::J] {
+                assert_eq!(*result.lock().unwrap(), None);
+                let mut input = w.resource_mut::<Input<KeyCode>>();
+                input.clear();
+                input.press(key);
+                // Inputs which are not part of the sequence are ignored
+                input.press(KeyCode::Space);
+                advance_time(w, Duration::from_millis(150));
+                executor.tick(w);
+            }
+            assert_eq!(*result.lock().unwrap(), Some(Ok(())));
+        });
+    }
+
+    #[test]
+    fn input_sequence_wrong_input() {
+        use super::input::sequence::{InputSequence, SequenceError};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Input<KeyCode>>();
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::Down);
+
+        let result = Arc::new(Mutex::new(None));
+        let r = Arc::clone(&result);
+        root_coroutine(|mut s: Scope| async move {
+            let sequence = InputSequence::new(KeyCode::Down)
+                .then(KeyCode::Right, Duration::from_millis(200))
+                .then(KeyCode::J, Duration::from_millis(200));
+            *r.lock().unwrap() = Some(s.input_sequence(sequence).await);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            let mut input = w.resource_mut::<Input<KeyCode>>();
+            input.clear();
+            input.press(KeyCode::J);
+            advance_time(w, Duration::from_millis(50));
+            executor.tick(w);
+            assert_eq!(
+                *result.lock().unwrap(),
+                Some(Err(SequenceError::WrongInput { step: 1 }))
+            );
+        });
+    }
+
+    #[test]
+    fn waiting_on_window_events() {
+        use bevy::window::{WindowCloseRequested, WindowFocused};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Events<WindowFocused>>();
+        world.init_resource::<Events<WindowCloseRequested>>();
+        let window = world.spawn_empty().id();
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        root_coroutine(|mut s: Scope| async move {
+            let lost = s.window_focus_lost().await;
+            l.lock().unwrap().push(("lost", lost));
+            let gained = s.window_focus_gained().await;
+            l.lock().unwrap().push(("gained", gained));
+            let closed = s.window_close_requested().await;
+            l.lock().unwrap().push(("closed", closed));
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            for focused in [true, false] {
+                w.send_event(WindowFocused { window, focused });
+            }
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), [("lost", window)]);
+            w.send_event(WindowFocused {
+                window,
+                focused: true,
+            });
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), [("lost", window), ("gained", window)]);
+            w.send_event(WindowCloseRequested { window });
+            executor.tick(w);
+            assert_eq!(log.lock().unwrap().len(), 3);
+        });
+    }
+
+    #[test]
+    fn waiting_on_despawned_audio() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        // Not loaded yet, so without a sink
+        let sound = world.spawn_empty().id();
+
+        let done = Arc::new(Mutex::new(false));
+        let d = Arc::clone(&done);
+        root_coroutine(move |mut s: Scope| async move {
+            s.audio_finished(sound).await;
+            *d.lock().unwrap() = true;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            executor.tick(w);
+            assert!(!*done.lock().unwrap());
+            w.despawn(sound);
+            executor.tick(w);
+            assert!(*done.lock().unwrap());
+        });
+    }
+
+    #[test]
+    fn waiting_on_animation() {
+        use bevy::{
+            animation::{EntityPath, Keyframes, VariableCurve},
+            asset::{AddAsset, AssetPlugin},
+            prelude::{AnimationClip, AnimationPlayer, Assets, Vec3},
+        };
+
+        let mut app = App::new();
+        app.add_plugins((AssetPlugin::default(), CorentinPlugin))
+            .add_asset::<AnimationClip>()
+            .insert_resource(Time::new(Instant::now()));
+
+        let mut clip = AnimationClip::default();
+        clip.add_curve_to_path(
+            EntityPath { parts: Vec::new() },
+            VariableCurve {
+                keyframe_timestamps: vec![0.0, 1.0],
+                keyframes: Keyframes::Translation(vec![Vec3::ZERO, Vec3::X]),
+            },
+        );
+        let clip = app.world.resource_mut::<Assets<AnimationClip>>().add(clip);
+        let mut player = AnimationPlayer::default();
+        player.play(clip).repeat();
+        let e = app
+            .world
+            .spawn((player, AnimationMarkers::default().with("hit", 0.5)))
+            .id();
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        root_coroutine(move |mut s: Scope| async move {
+            s.animation_marker(e, "hit").await;
+            l.lock().unwrap().push("hit");
+            s.animation_looped(e).await;
+            l.lock().unwrap().push("looped");
+        })
+        .apply(&mut app.world);
+        let l = Arc::clone(&log);
+        root_coroutine(move |mut s: Scope| async move {
+            s.animation_finished(e).await;
+            l.lock().unwrap().push("finished");
+        })
+        .apply(&mut app.world);
+
+        // The clip is never played, its elapsed time is set by hand
+        for (elapsed, expected) in [
+            (0.2, &[][..]),
+            (0.6, &["hit"][..]),
+            (0.9, &["hit"][..]),
+            (1.1, &["hit", "looped"][..]),
+        ] {
+            app.world
+                .get_mut::<AnimationPlayer>(e)
+                .unwrap()
+                .set_elapsed(elapsed);
+            app.update();
+            assert_eq!(*log.lock().unwrap(), expected);
+        }
+
+        // Only a clip which doesn't repeat finishes
+        let mut player = app.world.get_mut::<AnimationPlayer>(e).unwrap();
+        player.stop_repeating();
+        player.set_elapsed(1.2);
+        app.update();
+        assert_eq!(*log.lock().unwrap(), ["hit", "looped", "finished"]);
+    }
+
+    #[test]
+    fn waiting_on_scenes() {
+        use bevy::{
+            asset::AssetPlugin,
+            prelude::{Assets, BuildWorldChildren, Children, Parent, Scene, SceneSpawner},
+            scene::ScenePlugin,
+        };
+
+        let mut app = App::new();
+        app.add_plugins((AssetPlugin::default(), ScenePlugin, CorentinPlugin))
+            .register_type::<Parent>()
+            .register_type::<Children>()
+            .insert_resource(Time::new(Instant::now()));
+
+        // A root with a single child
+        let mut scene = World::new();
+        scene.spawn_empty().with_children(|p| {
+            p.spawn_empty();
+        });
+        let scene = app
+            .world
+            .resource_mut::<Assets<Scene>>()
+            .add(Scene::new(scene));
+        let instance = app
+            .world
+            .resource_mut::<SceneSpawner>()
+            .spawn(scene.clone());
+        let parent = app.world.spawn(scene).id();
+
+        let roots = Arc::new(Mutex::new([None, None]));
+        let r = Arc::clone(&roots);
+        root_coroutine(move |mut s: Scope| async move {
+            r.lock().unwrap()[0] = Some(s.scene_ready(instance).await);
+        })
+        .apply(&mut app.world);
+        let r = Arc::clone(&roots);
+        root_coroutine(move |mut s: Scope| async move {
+            r.lock().unwrap()[1] = Some(s.scene_ready_on(parent).await);
+        })
+        .apply(&mut app.world);
+
+        for _ in 0..3 {
+            app.update();
+        }
+        // Only the root of each instance is returned, not its child
+        let roots = roots
+            .lock()
+            .unwrap()
+            .clone()
+            .map(|ready| match ready.as_deref() {
+                Some(&[root]) => root,
+                _ => panic!("expected a single root, got {ready:?}"),
+            });
+        for root in roots {
+            assert_eq!(app.world.get::<Children>(root).unwrap().len(), 1);
+        }
+        assert_eq!(app.world.get::<Parent>(roots[1]).unwrap().get(), parent);
+    }
+
+    #[cfg(feature = "leafwing")]
+    #[test]
+    fn waiting_on_leafwing_actions() {
+        use bevy::prelude::Reflect;
+        use leafwing_input_manager::{action_state::ActionState, Actionlike};
+
+        #[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Reflect)]
+        enum Action {
+            Jump,
+            Fire,
+        }
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<ActionState<Action>>();
+        let player = world.spawn(ActionState::<Action>::default()).id();
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        root_coroutine(move |mut s: Scope| async move {
+            s.action_just_pressed(Action::Jump).await;
+            l.lock().unwrap().push("jump");
+            s.action_just_released_on(player, Action::Fire).await;
+            l.lock().unwrap().push("fire");
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            w.resource_mut::<ActionState<Action>>().press(Action::Fire);
+            executor.tick(w);
+            w.resource_mut::<ActionState<Action>>().press(Action::Jump);
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), ["jump"]);
+
+            // Only the action state of the entity counts
+            w.resource_mut::<ActionState<Action>>()
+                .release(Action::Fire);
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), ["jump"]);
+            let mut state = w.get_mut::<ActionState<Action>>(player).unwrap();
+            state.press(Action::Fire);
+            state.release(Action::Fire);
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), ["jump", "fire"]);
+        });
+    }
+
+    #[test]
+    fn waiting_within_distance() {
+        use bevy::prelude::{Transform, Vec3};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let player = world.spawn(Transform::from_xyz(10.0, 0.0, 0.0)).id();
+        let door = world.spawn(Transform::default()).id();
+
+        let done = Arc::new(Mutex::new(Vec::new()));
+        let d = Arc::clone(&done);
+        root_coroutine(move |mut s: Scope| async move {
+            s.until_within(player, door, 2.0).await;
+            d.lock().unwrap().push("door");
+            s.until_within(player, Vec3::new(0.0, 5.0, 0.0), 1.0).await;
+            d.lock().unwrap().push("point");
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let mut place = |entity, x, y| {
+                w.get_mut::<Transform>(entity).unwrap().translation = Vec3::new(x, y, 0.0);
+                executor.tick(w);
+            };
+
+            place(player, 3.0, 0.0);
+            assert!(done.lock().unwrap().is_empty());
+            // The target entity moves too
+            place(door, 1.5, 0.0);
+            assert_eq!(*done.lock().unwrap(), ["door"]);
+            place(player, 0.0, 4.5);
+            assert_eq!(*done.lock().unwrap(), ["door", "point"]);
+        });
+    }
+
+    #[test]
+    fn spawning_wave() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+
+        let done = Arc::new(Mutex::new(false));
+        let d = Arc::clone(&done);
+        root_coroutine(move |mut s: Scope| async move {
+            s.wave(3, Duration::from_millis(500), |i| {
+                ExampleComponent(i as u32)
+            })
+            .await;
+            *d.lock().unwrap() = true;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let enemies = |w: &mut World| {
+                w.query_filtered::<Entity, With<ExampleComponent>>()
+                    .iter(w)
+                    .collect::<Vec<_>>()
+            };
+
+            for count in 1..=3 {
+                executor.tick(w);
+                assert_eq!(enemies(w).len(), count);
+                advance_time(w, Duration::from_millis(500));
+            }
+            executor.tick(w);
+
+            // The wave lasts until every enemy is despawned
+            let alive = enemies(w);
+            w.despawn(alive[0]);
+            executor.tick(w);
+            assert!(!*done.lock().unwrap());
+            for e in &alive[1..] {
+                w.despawn(*e);
+            }
+            executor.tick(w);
+            assert!(*done.lock().unwrap());
+        });
+    }
+
+    #[test]
+    fn taking_turns() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let actors = [world.spawn_empty().id(), world.spawn_empty().id()];
+        let despawned = world.spawn_empty().id();
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        for (i, actor) in actors.into_iter().enumerate() {
+            let log = Arc::clone(&log);
+            root_coroutine(move |mut s: Scope| async move {
+                loop {
+                    let turn = s.take_turn(actor).await;
+                    log.lock().unwrap().push(format!("actor {i}"));
+                    s.next_tick().await;
+                    turn.end();
+                }
+            })
+            .apply(&mut world);
+        }
+        let l = Arc::clone(&log);
+        root_coroutine(move |mut s: Scope| async move {
+            // An actor which is despawned can't hold the game
+            s.round([actors[0], despawned, actors[1]]).await;
+            l.lock().unwrap().push("round".to_owned());
+            s.grant_turn(actors[1]).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            w.despawn(despawned);
+            for _ in 0..10 {
+                executor.tick(w);
+            }
+        });
+        assert_eq!(
+            *log.lock().unwrap(),
+            ["actor 0", "actor 1", "round", "actor 1"]
+        );
+    }
+
+    #[test]
+    fn dragging_and_dropping() {
+        use bevy::{
+            prelude::{GlobalTransform, Interaction, MouseButton, Node, Style, Val, Vec2},
+            window::{PrimaryWindow, Window},
+        };
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Input<MouseButton>>();
+        let window = world.spawn((Window::default(), PrimaryWindow)).id();
+        // Nodes aren't laid out without the UI plugin, so they are empty rectangles
+        let node = |x, y| {
+            (
+                Node::default(),
+                GlobalTransform::from_xyz(x, y, 0.0),
+                Style::default(),
+                Interaction::None,
+            )
+        };
+        let item = world.spawn(node(10.0, 10.0)).id();
+        let target = world.spawn(node(50.0, 50.0)).id();
+
+        let dropped = Arc::new(Mutex::new(None));
+        let d = Arc::clone(&dropped);
+        root_coroutine(move |mut s: Scope| async move {
+            *d.lock().unwrap() = Some(s.drag_and_drop(item, [target]).await);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let mut move_cursor = |w: &mut World, x, y| {
+                w.get_mut::<Window>(window)
+                    .unwrap()
+                    .set_cursor_position(Some(Vec2::new(x, y)));
+                executor.tick(w);
+            };
+
+            move_cursor(w, 10.0, 10.0);
+            *w.get_mut::<Interaction>(item).unwrap() = Interaction::Pressed;
+            move_cursor(w, 10.0, 10.0);
+            move_cursor(w, 50.0, 50.0);
+            let style = w.get::<Style>(item).unwrap();
+            assert_eq!((style.left, style.top), (Val::Px(50.0), Val::Px(50.0)));
+
+            let mut buttons = w.resource_mut::<Input<MouseButton>>();
+            buttons.press(MouseButton::Left);
+            buttons.release(MouseButton::Left);
+            move_cursor(w, 50.0, 50.0);
+        });
+        assert_eq!(
+            *dropped.lock().unwrap(),
+            Some(DragAndDrop {
+                dropped_on: Some(target),
+                start: Vec2::new(10.0, 10.0),
+                end: Vec2::new(50.0, 50.0),
+            })
+        );
+    }
+
+    #[test]
+    fn moving_transforms() {
+        use bevy::prelude::{Quat, Transform, Vec3};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(Transform::default()).id();
+        let missing = world.spawn_empty().id();
+
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let r = Arc::clone(&results);
+        root_coroutine(move |mut s: Scope| async move {
+            let moved = s.move_to(e, Vec3::X * 2.0, Pace::Speed(2.0)).await;
+            r.lock().unwrap().push(moved);
+            let turn = Quat::from_rotation_z(1.0);
+            let rotated = s.rotate_to(e, turn, Duration::from_secs(1)).await;
+            r.lock().unwrap().push(rotated);
+            let scaled = s.scale_to(missing, Vec3::ONE, Pace::Speed(1.0)).await;
+            r.lock().unwrap().push(scaled);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let half_second = Duration::from_millis(500);
+            executor.tick(w);
+            advance_time(w, half_second);
+            executor.tick(w);
+            assert_eq!(w.get::<Transform>(e).unwrap().translation, Vec3::X);
+            advance_time(w, half_second);
+            executor.tick(w);
+            assert_eq!(*results.lock().unwrap(), [true]);
+
+            advance_time(w, half_second);
+            executor.tick(w);
+            let angle = w.get::<Transform>(e).unwrap().rotation.to_axis_angle().1;
+            assert!((angle - 0.5).abs() < 1e-4);
+            advance_time(w, half_second);
+            executor.tick(w);
+        });
+        // The entity without a transform can't be scaled
+        assert_eq!(*results.lock().unwrap(), [true, true, false]);
+    }
+
+    #[test]
+    fn fading_sprite_and_text() {
+        use bevy::prelude::{Color, Sprite, Text, TextSection, TextStyle};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let sprite = world.spawn(Sprite::default()).id();
+        let section = |value: &str| TextSection {
+            value: value.to_owned(),
+            style: TextStyle {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                ..Default::default()
+            },
+        };
+        let text = world
+            .spawn(Text::from_sections([section("a"), section("b")]))
+            .id();
+
+        root_coroutine(move |mut s: Scope| async move {
+            s.fade_out::<Sprite>(sprite, Duration::from_secs(1)).await;
+            s.fade_in::<Text>(text, Duration::from_secs(1)).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            advance_time(w, Duration::from_millis(500));
+            executor.tick(w);
+            assert_eq!(w.get::<Sprite>(sprite).unwrap().alpha(), 0.5);
+            advance_time(w, Duration::from_millis(500));
+            executor.tick(w);
+            assert_eq!(w.get::<Sprite>(sprite).unwrap().alpha(), 0.0);
+
+            advance_time(w, Duration::from_millis(250));
+            executor.tick(w);
+            // Every section is faded
+            let alphas: Vec<f32> = w
+                .get::<Text>(text)
+                .unwrap()
+                .sections
+                .iter()
+                .map(|s| s.style.color.a())
+                .collect();
+            assert_eq!(alphas, [0.25, 0.25]);
+        });
+    }
+
+    #[cfg(feature = "cutscene")]
+    #[test]
+    fn typing_text() {
+        use super::cutscene::typewriter;
+        use bevy::prelude::{Text, TextSection, TextStyle};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Input<KeyCode>>();
+        let text = world
+            .spawn(Text::from_sections(
+                ["Hel", "lo!"].map(|v| TextSection::new(v, TextStyle::default())),
+            ))
+            .id();
+
+        let done = Arc::new(Mutex::new(None));
+        let d = Arc::clone(&done);
+        root_coroutine(move |mut s: Scope| async move {
+            *d.lock().unwrap() = Some(typewriter(&mut s, text, 2.0).await);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let shown = |w: &World| -> Vec<String> {
+                let sections = &w.get::<Text>(text).unwrap().sections;
+                sections.iter().map(|s| s.value.clone()).collect()
+            };
+
+            executor.tick(w);
+            assert_eq!(shown(w), ["", ""]);
+            advance_time(w, Duration::from_secs(1));
+            executor.tick(w);
+            assert_eq!(shown(w), ["He", ""]);
+            advance_time(w, Duration::from_secs(1));
+            executor.tick(w);
+            assert_eq!(shown(w), ["Hel", "l"]);
+
+            // Advancing shows the rest of the text at once
+            advance_time(w, Duration::ZERO);
+            w.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+            executor.tick(w);
+            assert_eq!(shown(w), ["Hel", "lo!"]);
+        });
+        assert_eq!(*done.lock().unwrap(), Some(true));
+    }
+
+    #[cfg(feature = "cutscene")]
+    #[test]
+    fn playing_cutscene() {
+        use super::cutscene::{move_actor_to, show_text};
+        use bevy::prelude::{Text, TextStyle, Transform, Vec3};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<Input<KeyCode>>();
+        let text = world
+            .spawn(Text::from_section("", TextStyle::default()))
+            .id();
+        let hero = world.spawn(Transform::default()).id();
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let l = Arc::clone(&log);
+        root_coroutine(move |mut s: Scope| async move {
+            show_text(&mut s, text, "Where am I?").await;
+            l.lock().unwrap().push("shown");
+            move_actor_to(&mut s, hero, Vec3::X * 2.0, 2.0).await;
+            l.lock().unwrap().push("moved");
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            // The input which advanced the previous text doesn't advance this one
+            w.resource_mut::<Input<KeyCode>>().press(KeyCode::Space);
+            executor.tick(w);
+            assert_eq!(
+                w.get::<Text>(text).unwrap().sections[0].value,
+                "Where am I?"
+            );
+            w.resource_mut::<Input<KeyCode>>().clear();
+            executor.tick(w);
+            assert!(log.lock().unwrap().is_empty());
+
+            let mut input = w.resource_mut::<Input<KeyCode>>();
+            input.release(KeyCode::Space);
+            input.clear();
+            input.press(KeyCode::Space);
+            executor.tick(w);
+            assert_eq!(*log.lock().unwrap(), ["shown"]);
+
+            // The actor walks at two units per second
+            advance_time(w, Duration::from_millis(500));
+            executor.tick(w);
+            assert_eq!(w.get::<Transform>(hero).unwrap().translation, Vec3::X);
+            assert_eq!(*log.lock().unwrap(), ["shown"]);
+            advance_time(w, Duration::from_millis(500));
+            executor.tick(w);
+            assert_eq!(w.get::<Transform>(hero).unwrap().translation, Vec3::X * 2.0);
+            assert_eq!(*log.lock().unwrap(), ["shown", "moved"]);
+        });
+    }
+
+    #[test]
+    fn following_path() {
+        use bevy::prelude::{Transform, Vec3};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(Transform::default()).id();
+
+        let waypoints = Arc::new(Mutex::new(Vec::new()));
+        let w2 = Arc::clone(&waypoints);
+        root_coroutine(move |mut s: Scope| async move {
+            let path = [Vec3::X, Vec3::new(1.0, 1.0, 0.0)];
+            let arrived = s
+                .follow_path_with(e, path, 2.0, |i| w2.lock().unwrap().push(i))
+                .await;
+            assert!(arrived);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            // The distance left after the corner is travelled along the next segment
+            advance_time(w, Duration::from_millis(750));
+            executor.tick(w);
+            let translation = w.get::<Transform>(e).unwrap().translation;
+            assert_eq!(translation, Vec3::new(1.0, 0.5, 0.0));
+            assert_eq!(*waypoints.lock().unwrap(), [0]);
+            advance_time(w, Duration::from_millis(750));
+            executor.tick(w);
+            let translation = w.get::<Transform>(e).unwrap().translation;
+            assert_eq!(translation, Vec3::new(1.0, 1.0, 0.0));
+            assert_eq!(*waypoints.lock().unwrap(), [0, 1]);
+        });
+    }
+
+    #[test]
+    fn settling_spring() {
+        use bevy::prelude::{Transform, Vec3};
+
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let camera = world.spawn(Transform::default()).id();
+        let player = world.spawn(Transform::from_xyz(10.0, 0.0, 0.0)).id();
+
+        let settled = Arc::new(Mutex::new(None));
+        let s2 = Arc::clone(&settled);
+        root_coroutine(move |mut s: Scope| async move {
+            let target = move |w: &World| w.get::<Transform>(player).map(|t| t.translation);
+            *s2.lock().unwrap() = Some(
+                s.spring(camera, target, Spring::critically_damped(100.0))
+                    .await,
+            );
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let mut max_x: f32 = 0.0;
+            for _ in 0..120 {
+                advance_time(w, Duration::from_secs_f32(1.0 / 60.0));
+                executor.tick(w);
+                max_x = max_x.max(w.get::<Transform>(camera).unwrap().translation.x);
+            }
+            // Critically damped, it doesn't overshoot
+            assert!(max_x <= 10.0);
+        });
+        assert_eq!(*settled.lock().unwrap(), Some(true));
+        assert_eq!(
+            world.get::<Transform>(camera).unwrap().translation,
+            Vec3::new(10.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn tweening_field_by_path() {
+        use super::tween::TweenFieldError;
+        use bevy::{
+            ecs::reflect::ReflectComponent,
+            prelude::{AppTypeRegistry, Reflect},
+        };
+
+        #[derive(Component, Reflect, Default)]
+        #[reflect(Component)]
+        struct Glow {
+            alpha: f32,
+            label: String,
+        }
+
+        let mut world = World::new();
+        world.insert_resource(Time::new(Instant::now()));
+        world.init_resource::<AppTypeRegistry>();
+        world
+            .resource::<AppTypeRegistry>()
+            .write()
+            .register::<Glow>();
+        let glowing = world.spawn(Glow::default()).id();
+        let empty = world.spawn_empty().id();
+        let mut executor = Executor::default();
+
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let r = Arc::clone(&results);
+        executor.add_function_coroutine(None, &world, move |mut s: Scope| async move {
+            let second = Duration::from_secs(1);
+            for (entity, path) in [
+                (empty, "Glow.alpha"),
+                (glowing, "Glow.label"),
+                (glowing, "Glow.missing"),
+                (glowing, "Unknown.alpha"),
+            ] {
+                let result = s
+                    .tween_field(entity, path, 0.0, 1.0, second, Ease::Linear)
+                    .await;
+                r.lock().unwrap().push(result);
+            }
+            let result = s
+                .tween_field(glowing, "Glow.alpha", 0.0, 1.0, second, Ease::Linear)
+                .await;
+            r.lock().unwrap().push(result);
+        });
+
+        executor.tick(&mut world);
+        {
+            let results = results.lock().unwrap();
+            assert_eq!(results[0], Err(TweenFieldError::MissingComponent));
+            // Not a number
+            assert_eq!(
+                results[1],
+                Err(TweenFieldError::InvalidField("label".to_owned()))
+            );
+            assert!(matches!(results[2], Err(TweenFieldError::InvalidField(_))));
+            assert_eq!(
+                results[3],
+                Err(TweenFieldError::UnknownComponent("Unknown".to_owned()))
+            );
+        }
+        for alpha in [0.5, 1.0] {
+            advance_time(&mut world, Duration::from_millis(500));
+            executor.tick(&mut world);
+            assert_eq!(world.get::<Glow>(glowing).unwrap().alpha, alpha);
+        }
+        assert_eq!(results.lock().unwrap().last(), Some(&Ok(())));
+    }
+
+    #[test]
+    fn pausing_timed_coroutine() {
+        use super::id_alloc::Id;
+
+        let mut world = World::new();
+        let mut executor = Executor::with_manual_time();
+
+        let a = Arc::new(Mutex::new(0));
+        let b = Arc::clone(&a);
+        executor.add_function_coroutine(None, &world, move |mut s: Scope| async move {
+            loop {
+                s.duration(Duration::from_secs(1)).await;
+                *b.lock().unwrap() += 1;
+            }
+        });
+        let id = Id::from_bits(executor.info().coroutines[0].id);
+
+        let mut tick_for = |executor: &mut Executor, secs: f32| {
+            executor.advance(Duration::from_secs_f32(secs));
+            executor.tick(&mut world);
+            *a.lock().unwrap()
+        };
+
+        assert_eq!(tick_for(&mut executor, 0.0), 0);
+        // Paused from 0.2 to 0.5, the deadline moves from 1.0 to 1.3, and the entry of the old
+        // deadline left in the queue is skipped
+        tick_for(&mut executor, 0.2);
+        executor.pause(id);
+        assert_eq!(tick_for(&mut executor, 0.3), 0);
+        executor.unpause(id);
+        assert_eq!(tick_for(&mut executor, 0.6), 0);
+        assert_eq!(tick_for(&mut executor, 0.3), 1);
+
+        // Paused past the deadline, it is only resumed once unpaused, at its new deadline
+        executor.pause(id);
+        assert_eq!(tick_for(&mut executor, 1.5), 1);
+        executor.unpause(id);
+        assert_eq!(tick_for(&mut executor, 0.9), 1);
+        assert_eq!(tick_for(&mut executor, 0.2), 2);
+    }
+
+    #[test]
+    fn conflicting_parameters() {
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(Instant::now()));
+        let e = world.spawn(ExampleComponent(0)).id();
+
+        // The coroutine is not started, the other ones are
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let s1 = Arc::clone(&started);
+        coroutine(
+            move |_: Scope, _: Rd<ExampleComponent>, _: Wr<ExampleComponent>| async move {
+                s1.lock().unwrap().push("conflicting");
+            },
+        )
+        .apply(e, &mut world);
+        let s2 = Arc::clone(&started);
+        coroutine(move |_: Scope, _: Rd<ExampleComponent>| async move {
+            s2.lock().unwrap().push("valid");
+        })
+        .apply(e, &mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| executor.tick(w));
+        assert_eq!(*started.lock().unwrap(), ["valid"]);
+    }
+
+    #[test]
+    fn running_timeline_without_drift() {
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let entry = |i: u32| {
+            let started = Arc::clone(&started);
+            move |_: Scope| async move {
+                started.lock().unwrap().push(i);
+            }
+        };
+        let timeline = Timeline::new()
+            .at(0.5, entry(0))
+            .at(1.0, entry(1))
+            .at(1.5, entry(2));
+        root_coroutine(|mut s: Scope| async move {
+            timeline.run(&mut s).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            // Each entry starts on the first tick past its offset, even though the previous one
+            // started late
+            for (ticks, count) in [(2, 1), (2, 2), (1, 3)] {
+                for _ in 0..ticks {
+                    executor.advance(Duration::from_millis(300));
+                    executor.tick(w);
+                }
+                assert_eq!(started.lock().unwrap().len(), count);
+            }
+        });
+    }
+
+    #[test]
+    fn moving_camera() {
+        use bevy::prelude::{Transform, Vec3};
+
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+        let camera = world.spawn(Transform::default()).id();
+        let target = world.spawn(Transform::from_xyz(0.0, 5.0, 0.0)).id();
+
+        root_coroutine(move |mut s: Scope| async move {
+            let to = Transform::from_xyz(4.0, 0.0, 0.0);
+            s.camera_to(camera, to, 1.0, Ease::Linear).await;
+            s.camera_follow(camera, target, Vec3::Z, 1.0, f32::INFINITY)
+                .await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            let translation = |w: &World| w.get::<Transform>(camera).unwrap().translation;
+            executor.tick(w);
+            executor.advance(Duration::from_millis(500));
+            executor.tick(w);
+            assert_eq!(translation(w), Vec3::new(2.0, 0.0, 0.0));
+            // Following starts right after reaching the transform
+            executor.advance(Duration::from_millis(500));
+            executor.tick(w);
+            assert_eq!(translation(w), Vec3::new(0.0, 5.0, 1.0));
+            w.get_mut::<Transform>(target).unwrap().translation.x = 1.0;
+            executor.advance(Duration::from_millis(500));
+            executor.tick(w);
+            assert_eq!(translation(w), Vec3::new(1.0, 5.0, 1.0));
+        });
+    }
+
+    #[test]
+    fn shaking_moving_camera() {
+        use bevy::prelude::{Transform, Vec3};
+
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+        let shake = CameraShake {
+            trauma: 1.0,
+            decay: 2.0,
+            ..Default::default()
+        };
+        let camera = world.spawn((Transform::default(), shake)).id();
+
+        root_coroutine(move |mut s: Scope| async move {
+            s.camera_shake(camera).await;
+        })
+        .apply(&mut world);
+        root_coroutine(move |mut s: Scope| async move {
+            let to = Transform::from_xyz(4.0, 0.0, 0.0);
+            s.camera_to(camera, to, 1.0, Ease::Linear).await;
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            executor.advance(Duration::from_millis(250));
+            executor.tick(w);
+            let translation = w.get::<Transform>(camera).unwrap().translation;
+            assert_ne!(translation, Vec3::new(1.0, 0.0, 0.0));
+            for _ in 0..3 {
+                executor.advance(Duration::from_millis(250));
+                executor.tick(w);
+            }
+            // The shake is over, and didn't offset the transform reached by `camera_to`
+            assert_eq!(
+                *w.get::<Transform>(camera).unwrap(),
+                Transform::from_xyz(4.0, 0.0, 0.0)
+            );
+        });
+    }
+
+    #[test]
+    fn canceling_camera_shake() {
+        use bevy::prelude::{Quat, Transform};
+
+        let mut world = World::new();
+        world.insert_resource(Executor::with_manual_time());
+        let initial = Transform::from_xyz(1.0, 2.0, 3.0).with_rotation(Quat::from_rotation_y(0.3));
+        let shake = CameraShake {
+            trauma: 1.0,
+            decay: 0.0,
+            ..Default::default()
+        };
+        let camera = world.spawn((initial, shake)).id();
+
+        root_coroutine(move |mut s: Scope| async move {
+            let shake = s.start(move |mut s: Scope| async move {
+                s.camera_shake(camera).await;
+            });
+            s.duration(0.5).await;
+            drop(shake);
+        })
+        .apply(&mut world);
+
+        world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.tick(w);
+            executor.advance(Duration::from_millis(250));
+            executor.tick(w);
+            assert_ne!(*w.get::<Transform>(camera).unwrap(), initial);
+            executor.advance(Duration::from_millis(250));
+            executor.tick(w);
+            assert_eq!(*w.get::<Transform>(camera).unwrap(), initial);
         });
     }
 
@@ -0,0 +1,77 @@
+//! Waits on collisions, generic over the collision events of any physics crate. Implementations
+//! for `bevy_rapier` and `bevy_xpbd` are available behind the `rapier2d`, `rapier3d`, `xpbd2d`
+//! and `xpbd3d` features.
+
+use bevy::{ecs::event::Event, prelude::Entity};
+
+use crate::function_coroutine::{
+    await_event::{NextEvent, ReadEvents},
+    scope::Scope,
+};
+
+/// An [`Event`] which can notify that two entities started colliding.
+pub trait CollisionEvent: Event {
+    /// Returns the two entities which started colliding, if this event reports it.
+    fn started(&self) -> Option<(Entity, Entity)>;
+}
+
+impl Scope {
+    /// Returns a future that resolve the next time `entity` starts colliding with another
+    /// entity, and returns that other entity.
+    pub fn collision_started<E: CollisionEvent>(
+        &mut self,
+        entity: Entity,
+    ) -> NextEvent<'_, E, impl ReadEvents<E, Entity>> {
+        self.next_event_where(move |e: &E| match e.started()? {
+            (a, b) if a == entity => Some(b),
+            (a, b) if b == entity => Some(a),
+            _ => None,
+        })
+    }
+
+    /// Returns a future that resolve the next time `e1` and `e2` start colliding.
+    pub fn collision_with<E: CollisionEvent>(
+        &mut self,
+        e1: Entity,
+        e2: Entity,
+    ) -> NextEvent<'_, E, impl ReadEvents<E, ()>> {
+        self.next_event_where(move |e: &E| {
+            let (a, b) = e.started()?;
+            ((a, b) == (e1, e2) || (a, b) == (e2, e1)).then_some(())
+        })
+    }
+}
+
+#[cfg(feature = "rapier2d")]
+impl CollisionEvent for bevy_rapier2d::prelude::CollisionEvent {
+    fn started(&self) -> Option<(Entity, Entity)> {
+        match self {
+            Self::Started(a, b, _) => Some((*a, *b)),
+            Self::Stopped(..) => None,
+        }
+    }
+}
+
+#[cfg(feature = "rapier3d")]
+impl CollisionEvent for bevy_rapier3d::prelude::CollisionEvent {
+    fn started(&self) -> Option<(Entity, Entity)> {
+        match self {
+            Self::Started(a, b, _) => Some((*a, *b)),
+            Self::Stopped(..) => None,
+        }
+    }
+}
+
+#[cfg(feature = "xpbd2d")]
+impl CollisionEvent for bevy_xpbd_2d::prelude::CollisionStarted {
+    fn started(&self) -> Option<(Entity, Entity)> {
+        Some((self.0, self.1))
+    }
+}
+
+#[cfg(feature = "xpbd3d")]
+impl CollisionEvent for bevy_xpbd_3d::prelude::CollisionStarted {
+    fn started(&self) -> Option<(Entity, Entity)> {
+        Some((self.0, self.1))
+    }
+}
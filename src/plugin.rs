@@ -1,18 +1,117 @@
-use bevy::prelude::{Mut, Plugin, Update, World};
+use std::panic::{self, AssertUnwindSafe};
 
-use crate::executor::Executor;
+use bevy::{
+    ecs::system::CommandQueue,
+    prelude::{App, IntoSystemConfigs, Plugin, ResMut, Resource, Update, World},
+};
 
+use crate::{
+    ability::Cooldowns,
+    commands::root_coroutine,
+    executor::{
+        info::{CoroutineInfo, CoroutineState, ExecutorInfo},
+        Executor,
+    },
+    function_coroutine::CoroutineParamFunction,
+    quest::Checkpoints,
+    utility::select_behaviors,
+};
+
+/// Insert the [`Executor`] resource, and tick it once per frame in the [`Update`] schedule, so
+/// that no system driving the executor has to be written by hand. It also inserts the
+/// [`Cooldowns`], and selects the behaviors of the [`Behaviors`](crate::utility::Behaviors)
+/// agents right before the tick. Add the [`ExecutorInfoPlugin`] as well to inspect the
+/// coroutines.
+///
+/// ```ignore
+/// App::new()
+///     .add_plugins((DefaultPlugins, CorentinPlugin))
+///     .add_systems(Startup, |mut commands: Commands| {
+///         commands.add(root_coroutine(intro));
+///     })
+///     .run();
+/// ```
 pub struct CorentinPlugin;
 
 impl Plugin for CorentinPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.init_resource::<Executor>()
-            .add_systems(Update, run_coroutines);
+            .init_resource::<Cooldowns>()
+            .register_type::<Checkpoints>()
+            .add_systems(Update, (select_behaviors, run_coroutines).chain());
+    }
+}
+
+/// Keep the [`ExecutorInfo`] resource up to date, right after each tick of the
+/// [`CorentinPlugin`], and register its types for reflection so that inspectors can show it.
+/// Building the snapshot visits and sorts every coroutine, so it isn't part of the
+/// [`CorentinPlugin`]. The `DebugOverlayPlugin` adds it when it isn't there yet.
+pub struct ExecutorInfoPlugin;
+
+impl Plugin for ExecutorInfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExecutorInfo>()
+            .register_type::<ExecutorInfo>()
+            .register_type::<CoroutineInfo>()
+            .register_type::<CoroutineState>()
+            .add_systems(Update, update_executor_info.after(run_coroutines));
+    }
+}
+
+/// Coroutines added with [`AddCoroutine::add_coroutine`], waiting for the first tick.
+#[derive(Resource, Default)]
+struct StartupCoroutines(CommandQueue);
+
+/// Extension trait to add coroutines directly to an [`App`].
+pub trait AddCoroutine {
+    /// Add a root coroutine, started on the first tick of the [`Executor`]. Useful for scripts
+    /// driving the whole game, which aren't started by any system:
+    ///
+    /// ```ignore
+    /// App::new()
+    ///     .add_plugins((DefaultPlugins, CorentinPlugin))
+    ///     .add_coroutine(game_flow)
+    ///     .run();
+    /// ```
+    ///
+    /// The coroutines are started in the order they were added, and the [`CorentinPlugin`] can
+    /// be added before or after them.
+    fn add_coroutine<M, C, T>(&mut self, coroutine: C) -> &mut Self
+    where
+        C: CoroutineParamFunction<M, T>,
+        T: Send + Sync + 'static,
+        M: Send + 'static;
+}
+
+impl AddCoroutine for App {
+    fn add_coroutine<M, C, T>(&mut self, coroutine: C) -> &mut Self
+    where
+        C: CoroutineParamFunction<M, T>,
+        T: Send + Sync + 'static,
+        M: Send + 'static,
+    {
+        self.world
+            .get_resource_or_insert_with(StartupCoroutines::default)
+            .0
+            .push(root_coroutine(coroutine));
+        self
+    }
+}
+
+pub(crate) fn run_coroutines(world: &mut World) {
+    if let Some(mut startup) = world.remove_resource::<StartupCoroutines>() {
+        startup.0.apply(world);
+    }
+    // The executor is put back even if a coroutine panics, so that it is still there if the
+    // panic is caught.
+    let mut exec = world.remove_resource::<Executor>().unwrap();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| exec.tick(world)));
+    world.insert_resource(exec);
+    if let Err(payload) = result {
+        panic::resume_unwind(payload);
     }
 }
 
-fn run_coroutines(world: &mut World) {
-    world.resource_scope(|w, mut exec: Mut<Executor>| {
-        exec.tick(w);
-    })
+fn update_executor_info(mut exec: ResMut<Executor>, mut info: ResMut<ExecutorInfo>) {
+    *info = exec.info();
 }
@@ -0,0 +1,130 @@
+//! Persistent progress markers for long running quest scripts. A quest records the checkpoints it
+//! reaches in the [`Checkpoints`] resource, which can be saved and loaded along with the rest of
+//! the game (it implements [`Reflect`]). Once loaded, the sections of the quest leading to a
+//! reached checkpoint can be skipped:
+//!
+//! ```ignore
+//! async fn main_quest(mut s: Scope) {
+//!     let mut q = s.quest("main_quest");
+//!     if !q.reached("met_elder") {
+//!         walk_to_village(&mut q).await;
+//!         talk_to_elder(&mut q).await;
+//!         q.checkpoint("met_elder").await;
+//!     }
+//!     if !q.reached("found_sword") {
+//!         explore_cave(&mut q).await;
+//!         q.checkpoint("found_sword").await;
+//!     }
+//!     fight_dragon(&mut q).await;
+//! }
+//! ```
+
+use std::ops::{Deref, DerefMut};
+
+use bevy::{
+    prelude::{ReflectResource, Resource, World},
+    reflect::Reflect,
+    utils::HashMap,
+};
+
+use crate::function_coroutine::scope::{Scope, SubScope};
+
+/// The checkpoints reached by each quest, in order.
+#[derive(Resource, Reflect, Clone, Debug, Default, PartialEq)]
+#[reflect(Resource)]
+pub struct Checkpoints {
+    pub quests: HashMap<String, Vec<String>>,
+}
+
+impl Checkpoints {
+    /// Returns true if `quest` has reached `marker`.
+    pub fn reached(&self, quest: &str, marker: &str) -> bool {
+        self.quests
+            .get(quest)
+            .is_some_and(|markers| markers.iter().any(|m| m == marker))
+    }
+
+    /// Returns the last checkpoint reached by `quest`, if any.
+    pub fn last(&self, quest: &str) -> Option<&str> {
+        self.quests.get(quest)?.last().map(String::as_str)
+    }
+
+    /// Forget the progress of `quest`, so that it starts over the next time it is run.
+    pub fn reset(&mut self, quest: &str) {
+        self.quests.remove(quest);
+    }
+}
+
+/// A handle to the scope of a coroutine running a quest, see [`Scope::quest`]. It derefs to the
+/// [`Scope`], so anything the coroutine can await can be awaited from it.
+pub struct Quest {
+    scope: SubScope,
+    key: String,
+    loaded: Vec<String>,
+}
+
+impl Scope {
+    /// Run a quest identified by `key` from this coroutine. The checkpoints already reached by
+    /// that quest are read from the [`Checkpoints`] resource at this point.
+    pub fn quest(&self, key: impl Into<String>) -> Quest {
+        let key = key.into();
+        let loaded = self
+            .world()
+            .get_resource::<Checkpoints>()
+            .and_then(|checkpoints| checkpoints.quests.get(&key).cloned())
+            .unwrap_or_default();
+
+        Quest {
+            scope: self.sub_scope(),
+            key,
+            loaded,
+        }
+    }
+}
+
+impl Quest {
+    /// Returns true if `marker` was reached before this quest started, in which case the section
+    /// leading to it can be skipped.
+    pub fn reached(&self, marker: &str) -> bool {
+        self.loaded.iter().any(|m| m == marker)
+    }
+
+    /// Record that this quest reached `marker`, and wait until the [`Checkpoints`] resource is
+    /// updated (at the next tick). Passing a checkpoint which was already reached before the
+    /// quest started returns immediately.
+    pub async fn checkpoint(&mut self, marker: &str) {
+        if self.reached(marker) {
+            return;
+        }
+
+        let key = self.key.clone();
+        let marker = marker.to_owned();
+        self.scope.commands().add(move |world: &mut World| {
+            let mut checkpoints = world.get_resource_or_insert_with(Checkpoints::default);
+            let markers = checkpoints.quests.entry(key).or_default();
+            if !markers.contains(&marker) {
+                markers.push(marker);
+            }
+        });
+        self.scope.next_tick().await;
+    }
+
+    /// Returns the key of this quest.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+impl Deref for Quest {
+    type Target = Scope;
+
+    fn deref(&self) -> &Self::Target {
+        &self.scope
+    }
+}
+
+impl DerefMut for Quest {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.scope
+    }
+}
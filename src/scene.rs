@@ -0,0 +1,59 @@
+//! Waits on scene instances spawned with the [`SceneSpawner`] (either directly, or through a
+//! `SceneBundle`).
+
+use bevy::{
+    prelude::{Children, Entity, Parent, World},
+    scene::{InstanceId, SceneInstance, SceneSpawner},
+};
+
+use crate::{
+    expect_resource,
+    function_coroutine::{await_condition::AwaitCondition, scope::Scope},
+};
+
+impl Scope {
+    /// Returns a future that resolve once the scene `instance` has spawned all its entities, and
+    /// returns its root entities (the ones without a parent inside the instance).
+    pub fn scene_ready(
+        &mut self,
+        instance: InstanceId,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Vec<Entity>>> {
+        self.until(move |world| {
+            let spawner = expect_resource::<SceneSpawner>(world);
+            if !spawner.instance_is_ready(instance) {
+                return None;
+            }
+
+            let entities: Vec<Entity> = spawner.iter_instance_entities(instance).collect();
+            let roots = entities
+                .iter()
+                .copied()
+                .filter(|e| {
+                    world
+                        .get::<Parent>(*e)
+                        .map_or(true, |p| !entities.contains(&p.get()))
+                })
+                .collect();
+            Some(roots)
+        })
+    }
+
+    /// Returns a future that resolve once the scene spawned as a child of `parent` (by a
+    /// `SceneBundle` for instance) is ready, and returns the root entities of the scene.
+    pub fn scene_ready_on(
+        &mut self,
+        parent: Entity,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<Vec<Entity>>> {
+        self.until(move |world| {
+            let instance = world.get::<SceneInstance>(parent)?;
+            if !expect_resource::<SceneSpawner>(world).instance_is_ready(**instance) {
+                return None;
+            }
+            let children = world
+                .get::<Children>(parent)
+                .map(|c| c.to_vec())
+                .unwrap_or_default();
+            Some(children)
+        })
+    }
+}
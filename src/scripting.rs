@@ -0,0 +1,389 @@
+//! Run [rhai](https://rhai.rs) scripts as coroutines. Available behind the `rhai` feature, except
+//! on wasm, since each script runs on its own thread.
+//!
+//! Rhai can't suspend a script in the middle of a call, so each [`ScriptBridge::run`] and each
+//! `spawn` from a script holds an OS thread until the script is done. Still, the script only
+//! runs while its coroutine is resumed, the executor waiting for it to call a function, so
+//! scripts are as deterministic as other coroutines. A script running for more than
+//! [`OPERATIONS_PER_RESUME`] operations without calling a function is suspended until the next
+//! tick, so it can't block the executor. Games running many scripts at once should keep this
+//! cost in mind, starting a thread for each of them.
+//!
+//! The functions the script can call are registered on a [`ScriptBridge`], each of them starting
+//! a coroutine. The script is blocked until that coroutine returns, so from its point of view
+//! calling `wait(1.5)` simply takes 1.5 seconds:
+//!
+//! ```ignore
+//! let bridge = ScriptBridge::new().register("shake", 1, |args| {
+//!     let camera = args[0].clone().try_cast::<Entity>();
+//!     boxed_coroutine(move |mut s: Scope| async move {
+//!         if let Some(camera) = camera {
+//!             s.camera_shake(camera).await;
+//!         }
+//!         Dynamic::UNIT
+//!     })
+//! });
+//!
+//! let mut variables = rhai::Scope::new();
+//! variables.push("hero", Dynamic::from(hero));
+//! variables.push("camera", Dynamic::from(camera));
+//! bridge.run(&mut s, variables, r#"
+//!     move_to(hero, 100.0, 0.0, 0.0, 50.0);
+//!     wait(1.5);
+//!     shake(camera);
+//! "#).await?;
+//! ```
+//!
+//! Scripts can also be loaded as [`Script`] assets, from `.rhai` files, and run by the entities
+//! with a [`RunScript`] component. Once [`ScriptPlugin`] is added, editing the file of a script
+//! restarts the coroutines running it, when the asset server watches for changes.
+
+use std::{
+    any::TypeId,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex, Weak,
+    },
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    ecs::event::ManualEventReader,
+    prelude::{
+        AddAsset, App, AssetEvent, Assets, Component, Entity, Handle, IntoSystemConfigs, Local,
+        Mut, Plugin, Resource, Update, Vec3, World,
+    },
+    reflect::{TypePath, TypeUuid},
+    utils::{BoxedFuture, HashMap, HashSet},
+};
+use rhai::{Dynamic, Engine, EvalAltResult};
+
+use crate::{
+    executor::Executor,
+    function_coroutine::{boxed_coroutine, scope::Scope, BoxedCoroutine},
+    id_alloc::Id,
+    plugin::run_coroutines,
+    transform::Pace,
+};
+
+/// The number of operations a script can run before being suspended until the next tick, unless
+/// it calls a function before.
+pub const OPERATIONS_PER_RESUME: u64 = 10_000;
+
+type ScriptFn = Arc<dyn Fn(Vec<Dynamic>) -> BoxedCoroutine<Dynamic> + Send + Sync>;
+
+/// The functions scripts can call, see the [module level documentation](self). It comes with:
+/// * `wait(seconds)`, to wait for some time.
+/// * `next_tick()`, to wait until the next tick.
+/// * `move_to(entity, x, y, z, speed)`, to move an entity at a given speed.
+/// * `spawn(source)`, to run another script concurrently, with the same functions.
+///
+/// The bridge used by the scripts started by [`RunScript`] is the one inserted as a resource.
+#[derive(Resource, Clone)]
+pub struct ScriptBridge {
+    functions: HashMap<String, (usize, ScriptFn)>,
+}
+
+impl Default for ScriptBridge {
+    fn default() -> Self {
+        Self {
+            functions: HashMap::default(),
+        }
+        .register("wait", 1, |args| {
+            let seconds = float(&args[0]);
+            boxed_coroutine(move |mut s: Scope| async move {
+                s.duration(seconds).await;
+                Dynamic::UNIT
+            })
+        })
+        .register("next_tick", 0, |_| {
+            boxed_coroutine(|mut s: Scope| async move {
+                s.next_tick().await;
+                Dynamic::UNIT
+            })
+        })
+        .register("move_to", 5, |args| {
+            let entity = args[0].clone().try_cast::<Entity>();
+            let target = Vec3::new(float(&args[1]), float(&args[2]), float(&args[3]));
+            let speed = float(&args[4]);
+            boxed_coroutine(move |mut s: Scope| async move {
+                let Some(entity) = entity else {
+                    return Dynamic::FALSE;
+                };
+                Dynamic::from_bool(s.move_to(entity, target, Pace::Speed(speed)).await)
+            })
+        })
+    }
+}
+
+impl ScriptBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let scripts call `name` with `arity` arguments. Each call starts the coroutine returned by
+    /// `function`, and returns its output to the script once it is done.
+    pub fn register(
+        mut self,
+        name: impl Into<String>,
+        arity: usize,
+        function: impl Fn(Vec<Dynamic>) -> BoxedCoroutine<Dynamic> + Send + Sync + 'static,
+    ) -> Self {
+        self.functions
+            .insert(name.into(), (arity, Arc::new(function)));
+        self
+    }
+
+    /// Run the script `source` from `scope`, with the given `variables`, and returns once it is
+    /// done. The script runs on its own thread, but only while this coroutine is resumed, see the
+    /// [module level documentation](self). If the scope is dropped, the script is stopped right
+    /// away, even if it is looping without calling any registered function.
+    pub async fn run(
+        &self,
+        scope: &mut Scope,
+        variables: rhai::Scope<'static>,
+        source: impl Into<String>,
+    ) -> Result<(), String> {
+        let (sender, receiver) = mpsc::channel();
+        let names: Vec<_> = self
+            .functions
+            .iter()
+            .map(|(name, (arity, _))| (name.clone(), *arity))
+            .collect();
+        // Receivers cannot be shared, and the coroutine must be `Send`
+        let receiver = Arc::new(Mutex::new(receiver));
+        let listening = Arc::downgrade(&receiver);
+
+        let source = source.into();
+        std::thread::spawn(move || {
+            let result = run_script(names, variables, &source, sender.clone(), listening);
+            let _ = sender.send(Msg::Done(result.map_err(|e| e.to_string())));
+        });
+
+        loop {
+            // The script is running until its next message, which is waited for right away
+            let msg = receiver.lock().unwrap().recv().ok();
+
+            match msg {
+                Some(Msg::Call { name, args, reply }) => {
+                    let output = match self.functions.get(&name) {
+                        Some((_, function)) => {
+                            let handle = scope.start(function(args));
+                            scope.on(handle).await
+                        }
+                        None => Dynamic::UNIT,
+                    };
+                    let _ = reply.send(output);
+                }
+                Some(Msg::Yield(resume)) => {
+                    scope.next_tick().await;
+                    let _ = resume.send(());
+                }
+                Some(Msg::Spawn(source)) => self.spawn(scope, source),
+                Some(Msg::Done(result)) => return result,
+                None => return Err("The script panicked".to_owned()),
+            }
+        }
+    }
+
+    fn spawn(&self, scope: &mut Scope, source: String) {
+        let bridge = self.clone();
+        scope.start_forget(move |mut s: Scope| async move {
+            // Boxed, since the future of `run` would contain itself otherwise
+            let run: Pin<Box<dyn Future<Output = _> + Send + '_>> =
+                Box::pin(bridge.run(&mut s, rhai::Scope::new(), source));
+            if let Err(error) = run.await {
+                bevy::log::error!("A spawned script failed: {error}");
+            }
+        });
+    }
+}
+
+enum Msg {
+    Call {
+        name: String,
+        args: Vec<Dynamic>,
+        reply: mpsc::Sender<Dynamic>,
+    },
+    /// The script ran [`OPERATIONS_PER_RESUME`] operations, and waits for the next tick.
+    Yield(mpsc::Sender<()>),
+    Spawn(String),
+    Done(Result<(), String>),
+}
+
+fn run_script(
+    functions: Vec<(String, usize)>,
+    mut variables: rhai::Scope<'static>,
+    source: &str,
+    sender: mpsc::Sender<Msg>,
+    listening: Weak<Mutex<mpsc::Receiver<Msg>>>,
+) -> Result<(), Box<EvalAltResult>> {
+    let mut engine = Engine::new();
+    let yielded_at = AtomicU64::new(0);
+    let yields = sender.clone();
+    engine.on_progress(move |operations| {
+        // Abort once the coroutine running the script is dropped, so the thread doesn't leak
+        if listening.strong_count() == 0 {
+            return Some(Dynamic::UNIT);
+        }
+        if operations - yielded_at.load(Ordering::Relaxed) < OPERATIONS_PER_RESUME {
+            return None;
+        }
+        yielded_at.store(operations, Ordering::Relaxed);
+        let (resume, resumed) = mpsc::channel();
+        let dropped = yields.send(Msg::Yield(resume)).is_err() || resumed.recv().is_err();
+        dropped.then_some(Dynamic::UNIT)
+    });
+    for (name, arity) in functions {
+        let sender = sender.clone();
+        let called = name.clone();
+        engine.register_raw_fn(
+            name,
+            vec![TypeId::of::<Dynamic>(); arity],
+            move |_, args: &mut [&mut Dynamic]| {
+                let (reply, output) = mpsc::channel();
+                let args = args.iter_mut().map(|a| std::mem::take(*a)).collect();
+                sender
+                    .send(Msg::Call {
+                        name: called.clone(),
+                        args,
+                        reply,
+                    })
+                    .map_err(|_| "The coroutine running the script was dropped")?;
+                output
+                    .recv()
+                    .map_err(|_| "The coroutine running the script was dropped".into())
+            },
+        );
+    }
+
+    engine.register_fn("spawn", move |source: &str| {
+        let _ = sender.send(Msg::Spawn(source.to_owned()));
+    });
+
+    engine.run_with_scope(&mut variables, source)
+}
+
+fn float(value: &Dynamic) -> f32 {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|i| i as _))
+        .unwrap_or_default() as f32
+}
+
+/// The source of a rhai script, loaded from a `.rhai` file.
+#[derive(TypeUuid, TypePath, Clone, Debug)]
+#[uuid = "8f0c6a4e-2d7b-4b53-a1e9-6c3f5d2b7e14"]
+pub struct Script {
+    pub source: String,
+}
+
+#[derive(Default)]
+struct ScriptLoader;
+
+impl AssetLoader for ScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let source = std::str::from_utf8(bytes)?.to_owned();
+            load_context.set_default_asset(LoadedAsset::new(Script { source }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+/// Run `script` in a coroutine owned by this entity, using the [`ScriptBridge`] resource. The
+/// script can refer to the entity as `entity`. The coroutine starts once the script is loaded,
+/// and is restarted from the beginning each time the script is modified.
+#[derive(Component, Clone, Debug)]
+pub struct RunScript {
+    pub script: Handle<Script>,
+}
+
+/// Load [`Script`] assets, and run the scripts of the [`RunScript`] components.
+pub struct ScriptPlugin;
+
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Script>()
+            .init_asset_loader::<ScriptLoader>()
+            .init_resource::<ScriptBridge>()
+            .add_systems(Update, run_scripts.before(run_coroutines));
+    }
+}
+
+/// The coroutines started for each [`RunScript`] component, and the script they run.
+#[derive(Default)]
+struct RunningScripts {
+    coroutines: HashMap<Entity, (Handle<Script>, Id)>,
+    events: ManualEventReader<AssetEvent<Script>>,
+}
+
+fn run_scripts(world: &mut World, mut running: Local<RunningScripts>) {
+    let running = &mut *running;
+    let modified: HashSet<Handle<Script>> = running
+        .events
+        .iter(world.resource())
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => Some(handle.clone_weak()),
+            _ => None,
+        })
+        .collect();
+
+    let mut to_start = Vec::new();
+    let mut query = world.query::<(Entity, &RunScript)>();
+    for (entity, run) in query.iter(world) {
+        let outdated = match running.coroutines.get(&entity) {
+            Some((script, _)) => *script != run.script || modified.contains(&run.script),
+            None => true,
+        };
+        if !outdated {
+            continue;
+        }
+        if let Some(script) = world.resource::<Assets<Script>>().get(&run.script) {
+            to_start.push((entity, run.script.clone(), script.source.clone()));
+        }
+    }
+
+    world.resource_scope(|world, mut executor: Mut<Executor>| {
+        // Forget the coroutines of the entities which no longer run a script
+        running.coroutines.retain(|entity, (_, id)| {
+            let keep = world.get::<RunScript>(*entity).is_some();
+            if !keep {
+                executor.cancel_coroutine(*id);
+            }
+            keep
+        });
+
+        for (entity, handle, source) in to_start {
+            if let Some((_, id)) = running.coroutines.remove(&entity) {
+                executor.cancel_coroutine(id);
+            }
+
+            let bridge = world.resource::<ScriptBridge>().clone();
+            let started = executor
+                .build(move |mut s: Scope| async move {
+                    let mut variables = rhai::Scope::new();
+                    variables.push("entity", entity);
+                    if let Err(error) = bridge.run(&mut s, variables, source).await {
+                        bevy::log::error!("The script of {entity:?} failed: {error}");
+                    }
+                })
+                .name("script")
+                .owner(entity)
+                .spawn(world);
+            if let Some(id) = started {
+                running.coroutines.insert(entity, (handle, id));
+            }
+        }
+    });
+}
@@ -0,0 +1,170 @@
+//! A state machine where each state is a coroutine, which runs until it returns the next state.
+//!
+//! ```ignore
+//! #[derive(Clone, PartialEq, Eq, Hash)]
+//! enum Guard {
+//!     Patrol,
+//!     Chase(Entity),
+//!     Dead,
+//! }
+//!
+//! let machine = StateMachine::new()
+//!     .state(Guard::Patrol, patrol)
+//!     .state_with(|state| matches!(state, Guard::Chase(_)), chase)
+//!     .on_enter(|s, state| println!("entering {state:?}"));
+//!
+//! // Transitions can be forced from other coroutines or systems
+//! let transitions = machine.transitions();
+//!
+//! let last = machine.run(&mut s, Guard::Patrol).await;
+//! ```
+
+use std::{
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+use bevy::utils::HashMap;
+
+use crate::function_coroutine::{handle::CoroHandle, scope::Scope, CoroutineParamFunction};
+
+type StartState<S> = Box<dyn FnMut(&mut Scope, &S) -> CoroHandle<S> + Send>;
+type Hook<S> = Box<dyn FnMut(&mut Scope, &S) + Send>;
+type Matcher<S> = Box<dyn Fn(&S) -> bool + Send>;
+
+/// A set of states, each of them associated with a coroutine returning the next state. A state
+/// without coroutine is final: the machine stops once it is reached.
+pub struct StateMachine<S> {
+    states: HashMap<S, StartState<S>>,
+    matching: Vec<(Matcher<S>, StartState<S>)>,
+    on_enter: Vec<Hook<S>>,
+    on_exit: Vec<Hook<S>>,
+    transitions: Transitions<S>,
+}
+
+impl<S> Default for StateMachine<S> {
+    fn default() -> Self {
+        Self {
+            states: HashMap::default(),
+            matching: Vec::new(),
+            on_enter: Vec::new(),
+            on_exit: Vec::new(),
+            transitions: Transitions(Arc::new(Mutex::new(None))),
+        }
+    }
+}
+
+impl<S> StateMachine<S>
+where
+    S: Clone + Eq + Hash + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `coroutine` when entering `state`. Since a state can be entered several times,
+    /// `coroutine` must be [`Clone`].
+    pub fn state<Marker: 'static, C>(mut self, state: S, coroutine: C) -> Self
+    where
+        C: CoroutineParamFunction<Marker, S> + Clone + Send,
+    {
+        self.states
+            .insert(state, Box::new(move |s, _| s.start(coroutine.clone())));
+        self
+    }
+
+    /// Run `coroutine` when entering any state for which `matches` returns `true`, which is
+    /// given as an argument to `coroutine`. Useful for states holding data. States added with
+    /// [`state`](Self::state) are checked first.
+    pub fn state_with<C, Fut>(
+        mut self,
+        matches: impl Fn(&S) -> bool + Send + 'static,
+        coroutine: C,
+    ) -> Self
+    where
+        C: FnOnce(Scope, S) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = S> + Send + 'static,
+    {
+        let start = move |s: &mut Scope, state: &S| {
+            let (coroutine, state) = (coroutine.clone(), state.clone());
+            s.start(move |s: Scope| coroutine(s, state))
+        };
+        self.matching.push((Box::new(matches), Box::new(start)));
+        self
+    }
+
+    /// Call `hook` each time a state is entered, right before its coroutine starts.
+    pub fn on_enter(mut self, hook: impl FnMut(&mut Scope, &S) + Send + 'static) -> Self {
+        self.on_enter.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` each time a state is exited, right after its coroutine returned or was
+    /// interrupted by a forced transition.
+    pub fn on_exit(mut self, hook: impl FnMut(&mut Scope, &S) + Send + 'static) -> Self {
+        self.on_exit.push(Box::new(hook));
+        self
+    }
+
+    /// Returns a handle to force the transitions of this machine from the outside.
+    pub fn transitions(&self) -> Transitions<S> {
+        self.transitions.clone()
+    }
+
+    /// Run this machine from `scope`, starting in the `initial` state, and returns the final
+    /// state once it is reached. If the scope is dropped, the coroutine of the current state is
+    /// dropped as well.
+    pub async fn run(mut self, scope: &mut Scope, initial: S) -> S {
+        let mut current = initial;
+        loop {
+            for hook in self.on_enter.iter_mut() {
+                hook(scope, &current);
+            }
+
+            let Some(start) = self.start_of(&current) else {
+                return current;
+            };
+            let state = start(scope, &current);
+            let forced = Arc::clone(&self.transitions.0);
+            let forced = scope.start(move |mut s: Scope| async move {
+                s.until(move |_| forced.lock().unwrap().take()).await
+            });
+            let next = scope.first([state, forced]).await;
+
+            for hook in self.on_exit.iter_mut() {
+                hook(scope, &current);
+            }
+            current = next;
+        }
+    }
+
+    fn start_of(&mut self, state: &S) -> Option<&mut StartState<S>> {
+        match self.states.get_mut(state) {
+            Some(start) => Some(start),
+            None => self
+                .matching
+                .iter_mut()
+                .find(|(matches, _)| matches(state))
+                .map(|(_, start)| start),
+        }
+    }
+}
+
+/// A handle to force the transitions of a [`StateMachine`], see [`StateMachine::transitions`].
+pub struct Transitions<S>(Arc<Mutex<Option<S>>>);
+
+impl<S> Clone for Transitions<S> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<S> Transitions<S> {
+    /// Interrupt the current state of the machine, and go to `state` instead. The transition
+    /// happens the next time the machine is resumed. If multiple transitions are forced before
+    /// that, only the last one is kept.
+    pub fn force(&self, state: S) {
+        *self.0.lock().unwrap() = Some(state);
+    }
+}
@@ -0,0 +1,207 @@
+//! Helpers to unit test coroutines, without having to set up an app.
+//!
+//! ```ignore
+//! let mut test = TestExecutor::new();
+//! let result = test.run(|mut s: Scope| async move {
+//!     s.duration(Duration::from_secs(1)).await;
+//!     42
+//! });
+//!
+//! // The coroutine starts on the first frame
+//! test.advance_frame();
+//! test.advance_time(Duration::from_millis(500));
+//! assert_eq!(result.get(), None);
+//! test.expect_suspended_on(|state| matches!(state, CoroutineState::Duration { .. }));
+//! test.advance_time(Duration::from_millis(500));
+//! assert_eq!(result.get(), Some(42));
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    prelude::{Entity, Mut, World},
+    time::Time,
+};
+
+use crate::{
+    executor::{info::CoroutineState, Executor},
+    function_coroutine::{await_time::IntoDuration, scope::Scope, CoroutineParamFunction},
+};
+
+/// A [`World`] with an [`Executor`] and a [`Time`] driven manually.
+pub struct TestExecutor {
+    pub world: World,
+    now: Instant,
+    frame_time: Duration,
+}
+
+impl Default for TestExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestExecutor {
+    /// A new empty world, where each frame lasts 1/60th of a second.
+    pub fn new() -> Self {
+        let now = Instant::now();
+        let mut world = World::new();
+        world.init_resource::<Executor>();
+        world.insert_resource(Time::new(now));
+        Self {
+            world,
+            now,
+            frame_time: Duration::from_secs_f64(1.0 / 60.0),
+        }
+    }
+
+    /// Change how long each frame lasts.
+    pub fn with_frame_time(mut self, frame_time: impl IntoDuration) -> Self {
+        self.frame_time = frame_time.into_duration();
+        self
+    }
+
+    /// Start `coroutine` on the next frame, and returns a [`TestResult`] which holds its result
+    /// once it is done.
+    pub fn run<Marker: 'static, T, C>(&mut self, coroutine: C) -> TestResult<T>
+    where
+        C: CoroutineParamFunction<Marker, T>,
+        T: Sync + Send + 'static,
+    {
+        self.start(None, coroutine)
+    }
+
+    /// Like [`run`](TestExecutor::run), but the coroutine is bound to `owner`.
+    pub fn run_on<Marker: 'static, T, C>(&mut self, owner: Entity, coroutine: C) -> TestResult<T>
+    where
+        C: CoroutineParamFunction<Marker, T>,
+        T: Sync + Send + 'static,
+    {
+        self.start(Some(owner), coroutine)
+    }
+
+    fn start<Marker: 'static, T, C>(&mut self, owner: Option<Entity>, coroutine: C) -> TestResult<T>
+    where
+        C: CoroutineParamFunction<Marker, T>,
+        T: Sync + Send + 'static,
+    {
+        let result = TestResult {
+            value: Arc::new(Mutex::new(None)),
+        };
+        let slot = Arc::clone(&result.value);
+        self.world.resource_scope(|w, mut executor: Mut<Executor>| {
+            executor.add_function_coroutine(None, w, move |mut s: Scope| async move {
+                let handle = match owner {
+                    Some(owner) => s.bind_coroutine(owner, coroutine),
+                    None => s.start(coroutine),
+                };
+                let value = s.on(handle).await;
+                *slot.lock().unwrap() = Some(value);
+            });
+        });
+        result
+    }
+
+    /// Tick the executor once, `frame_time` after the previous tick.
+    pub fn advance_frame(&mut self) {
+        self.tick(self.frame_time);
+    }
+
+    /// Tick the executor `n` times.
+    pub fn advance_frames(&mut self, n: usize) {
+        for _ in 0..n {
+            self.advance_frame();
+        }
+    }
+
+    /// Tick the executor as many times as needed for `duration` to elapse, the last frame being
+    /// shorter if `duration` isn't a multiple of the frame time.
+    pub fn advance_time(&mut self, duration: impl IntoDuration) {
+        let mut duration = duration.into_duration();
+        while !duration.is_zero() {
+            let dt = duration.min(self.frame_time);
+            self.tick(dt);
+            duration -= dt;
+        }
+    }
+
+    /// Tick the executor until there are no coroutines left, and returns how many frames it took.
+    ///
+    /// # Panics
+    /// If the coroutines are still running after `max_frames`.
+    pub fn run_until_done(&mut self, max_frames: usize) -> usize {
+        for frame in 0..max_frames {
+            if self.executor().info().coroutines.is_empty() {
+                return frame;
+            }
+            self.advance_frame();
+        }
+        assert!(
+            self.executor().info().coroutines.is_empty(),
+            "Coroutines still running after {max_frames} frames:\n{}",
+            self.executor().dump_tree()
+        );
+        max_frames
+    }
+
+    /// Assert that at least one coroutine is suspended on a state matching `predicate`.
+    ///
+    /// # Panics
+    /// If no coroutine matches, with the tree of the running coroutines.
+    pub fn expect_suspended_on(&mut self, predicate: impl Fn(&CoroutineState) -> bool) {
+        let info = self.executor().info();
+        assert!(
+            info.coroutines.iter().any(|c| predicate(&c.state)),
+            "No coroutine is suspended on the expected state:\n{}",
+            info.tree()
+        );
+    }
+
+    /// Returns the [`Executor`] of the world.
+    pub fn executor(&mut self) -> Mut<'_, Executor> {
+        self.world.resource_mut::<Executor>()
+    }
+
+    fn tick(&mut self, dt: Duration) {
+        self.now += dt;
+        let now = self.now;
+        self.world.resource_mut::<Time>().update_with_instant(now);
+        self.world
+            .resource_scope(|w, mut executor: Mut<Executor>| executor.tick(w));
+    }
+}
+
+/// The result of a coroutine started by a [`TestExecutor`].
+pub struct TestResult<T> {
+    value: Arc<Mutex<Option<T>>>,
+}
+
+impl<T: Clone> TestResult<T> {
+    /// Returns the result of the coroutine, or `None` if it isn't done yet (or was canceled).
+    pub fn get(&self) -> Option<T> {
+        self.value.lock().unwrap().clone()
+    }
+}
+
+impl<T> TestResult<T> {
+    /// Returns true once the coroutine is done.
+    pub fn is_done(&self) -> bool {
+        self.value.lock().unwrap().is_some()
+    }
+
+    /// Take the result of the coroutine out, if it is done.
+    pub fn take(&self) -> Option<T> {
+        self.value.lock().unwrap().take()
+    }
+
+    /// Assert that the coroutine is done, and returns its result.
+    ///
+    /// # Panics
+    /// If the coroutine isn't done.
+    pub fn unwrap(&self) -> T {
+        self.take().expect("The coroutine is not done")
+    }
+}
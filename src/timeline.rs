@@ -0,0 +1,78 @@
+//! A declarative timeline, to author long choreographed sequences.
+//!
+//! ```ignore
+//! Timeline::new()
+//!     .at(0.0, |mut s: Scope| async move {
+//!         s.move_to(hero, target, Duration::from_secs(3)).await;
+//!     })
+//!     .at(0.5, |mut s: Scope| async move { s.fade_in::<Sprite>(title, 1.0).await; })
+//!     .at(2.0, play_music)
+//!     .run(&mut s)
+//!     .await;
+//! ```
+
+use std::time::Duration;
+
+use crate::{
+    executor::TickTime,
+    function_coroutine::{
+        await_time::IntoDuration, handle::CoroHandle, scope::Scope, CoroutineParamFunction,
+    },
+};
+
+type Start = Box<dyn FnOnce(&mut Scope) -> CoroHandle<()> + Send>;
+
+/// A set of coroutines, each starting at a given time. Coroutines can overlap, in which case they
+/// run concurrently, like tracks.
+#[derive(Default)]
+pub struct Timeline {
+    entries: Vec<(Duration, Start)>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start `coroutine` `offset` after the start of the timeline, given as a [`Duration`] or a
+    /// number of seconds.
+    pub fn at<Marker: 'static, C>(mut self, offset: impl IntoDuration, coroutine: C) -> Self
+    where
+        C: CoroutineParamFunction<Marker, ()> + Send,
+    {
+        let offset = offset.into_duration();
+        self.entries
+            .push((offset, Box::new(move |s: &mut Scope| s.start(coroutine))));
+        self
+    }
+
+    /// Returns the time at which the last coroutine of this timeline starts.
+    pub fn last_start(&self) -> Duration {
+        self.entries
+            .iter()
+            .map(|(offset, _)| *offset)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Run this timeline from `scope`, and returns once all of its coroutines are done. If the
+    /// scope is dropped, all the coroutines started so far are dropped as well.
+    pub async fn run(mut self, scope: &mut Scope) {
+        // Stable sort, so entries at the same time start in insertion order
+        self.entries.sort_by_key(|(offset, _)| *offset);
+
+        // The offsets are measured from the clock of the executor, since each wait ends on the
+        // first tick past its deadline, and adding up the waits would add up the overshoots too
+        let started_at = TickTime::get(&scope.world()).elapsed;
+        let mut handles = Vec::with_capacity(self.entries.len());
+        for (offset, start) in self.entries {
+            let elapsed = TickTime::get(&scope.world()).elapsed - started_at;
+            if elapsed < offset {
+                scope.duration(offset - elapsed).await;
+            }
+            handles.push(start(scope));
+        }
+
+        scope.all(handles).await;
+    }
+}
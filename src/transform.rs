@@ -0,0 +1,331 @@
+//! Helpers working on the [`Transform`] of entities.
+
+use std::time::Duration;
+
+use bevy::prelude::{Entity, Quat, Transform, Vec3, World};
+
+use crate::{
+    function_coroutine::{await_condition::AwaitCondition, scope::Scope},
+    tween::Ease,
+};
+
+/// A point in space, either fixed or following an entity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Point(Vec3),
+    Entity(Entity),
+}
+
+impl From<Vec3> for Target {
+    fn from(point: Vec3) -> Self {
+        Target::Point(point)
+    }
+}
+
+impl From<Entity> for Target {
+    fn from(entity: Entity) -> Self {
+        Target::Entity(entity)
+    }
+}
+
+impl Target {
+    /// Returns the current position of this target, if it still exists.
+    pub fn position(&self, world: &World) -> Option<Vec3> {
+        match self {
+            Target::Point(p) => Some(*p),
+            Target::Entity(e) => world.get::<Transform>(*e).map(|t| t.translation),
+        }
+    }
+}
+
+impl Scope {
+    /// Returns a future that resolve once the translation of `entity` is within `distance` of
+    /// `target`. If it already is, the future resolve immediately.
+    ///
+    /// Note that the future never resolve if `entity` (or the target entity) is despawned.
+    pub fn until_within(
+        &mut self,
+        entity: Entity,
+        target: impl Into<Target>,
+        distance: f32,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        let target = target.into();
+        self.until(move |world| {
+            let position = world.get::<Transform>(entity)?.translation;
+            let target = target.position(world)?;
+            (position.distance_squared(target) <= distance * distance).then_some(())
+        })
+    }
+}
+
+/// How a transform helper (such as [`Scope::move_to`]) reaches its target.
+#[derive(Clone, Copy, Debug)]
+pub enum Pace {
+    /// Reach the target in the given duration, following the easing curve.
+    Duration(Duration, Ease),
+    /// Go toward the target at a constant speed, in units (or radians) per second.
+    Speed(f32),
+}
+
+impl From<Duration> for Pace {
+    fn from(duration: Duration) -> Self {
+        Pace::Duration(duration, Ease::Linear)
+    }
+}
+
+/// The parameters of a damped spring, see [`Scope::spring`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spring {
+    pub stiffness: f32,
+    pub damping: f32,
+    /// The spring is considered settled once both the distance to the target and the speed are
+    /// below this value.
+    pub tolerance: f32,
+}
+
+impl Spring {
+    pub fn new(stiffness: f32, damping: f32) -> Self {
+        Self {
+            stiffness,
+            damping,
+            tolerance: 0.01,
+        }
+    }
+
+    /// A spring which reaches its target as fast as possible without overshooting.
+    pub fn critically_damped(stiffness: f32) -> Self {
+        Self::new(stiffness, 2.0 * stiffness.sqrt())
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+fn vec3_towards(from: Vec3, to: Vec3, max_delta: f32) -> Vec3 {
+    let delta = to - from;
+    if delta.length() <= max_delta {
+        to
+    } else {
+        from + delta.normalize() * max_delta
+    }
+}
+
+fn quat_towards(from: Quat, to: Quat, max_delta: f32) -> Quat {
+    let angle = from.angle_between(to);
+    if angle <= max_delta {
+        to
+    } else {
+        from.slerp(to, max_delta / angle)
+    }
+}
+
+impl Scope {
+    /// Animate the translation of `entity` toward `target`, and returns once it is reached. Returns
+    /// `false` if the entity or its [`Transform`] was removed before.
+    pub async fn move_to(&mut self, entity: Entity, target: Vec3, pace: impl Into<Pace>) -> bool {
+        self.animate_transform(
+            entity,
+            target,
+            pace.into(),
+            |t| t.translation,
+            |t, v| t.translation = v,
+            Vec3::lerp,
+            vec3_towards,
+        )
+        .await
+    }
+
+    /// Animate the rotation of `entity` toward `target`, and returns once it is reached. Returns
+    /// `false` if the entity or its [`Transform`] was removed before.
+    pub async fn rotate_to(&mut self, entity: Entity, target: Quat, pace: impl Into<Pace>) -> bool {
+        self.animate_transform(
+            entity,
+            target,
+            pace.into(),
+            |t| t.rotation,
+            |t, v| t.rotation = v,
+            Quat::slerp,
+            quat_towards,
+        )
+        .await
+    }
+
+    /// Animate the scale of `entity` toward `target`, and returns once it is reached. Returns
+    /// `false` if the entity or its [`Transform`] was removed before.
+    pub async fn scale_to(&mut self, entity: Entity, target: Vec3, pace: impl Into<Pace>) -> bool {
+        self.animate_transform(
+            entity,
+            target,
+            pace.into(),
+            |t| t.scale,
+            |t, v| t.scale = v,
+            Vec3::lerp,
+            vec3_towards,
+        )
+        .await
+    }
+
+    /// Animate a field of the [`Transform`] of `entity`. The transform is only written to when
+    /// the field actually changes, to avoid triggering change detection needlessly.
+    #[allow(clippy::too_many_arguments)]
+    async fn animate_transform<V: Copy + PartialEq>(
+        &mut self,
+        entity: Entity,
+        target: V,
+        pace: Pace,
+        get: fn(&Transform) -> V,
+        set: fn(&mut Transform, V),
+        lerp: fn(V, V, f32) -> V,
+        towards: fn(V, V, f32) -> V,
+    ) -> bool {
+        let Some(start) = self.world().get::<Transform>(entity).map(get) else {
+            return false;
+        };
+
+        let mut elapsed = Duration::ZERO;
+        let mut dt = Duration::ZERO;
+        loop {
+            let Some(current) = self.world().get::<Transform>(entity).map(get) else {
+                return false;
+            };
+
+            let (next, done) = match pace {
+                Pace::Duration(duration, ease) => {
+                    let t = if duration.is_zero() {
+                        1.0
+                    } else {
+                        elapsed.as_secs_f32() / duration.as_secs_f32()
+                    };
+                    if t >= 1.0 {
+                        (target, true)
+                    } else {
+                        (lerp(start, target, ease.sample(t)), false)
+                    }
+                }
+                Pace::Speed(speed) => {
+                    let next = towards(current, target, speed * dt.as_secs_f32());
+                    (next, next == target)
+                }
+            };
+
+            if next != current {
+                self.modify(entity, |t: &mut Transform| set(t, next));
+            }
+
+            if done {
+                return true;
+            }
+
+            dt = self.next_tick().await;
+            elapsed += dt;
+        }
+    }
+}
+
+impl Scope {
+    /// Move `entity` along the polyline going through `points`, at a constant `speed` in units
+    /// per second, and returns once the last point is reached. Returns `false` if the entity or
+    /// its [`Transform`] was removed before.
+    ///
+    /// The entity doesn't need to start on the first point, it first moves in a straight line
+    /// toward it.
+    pub async fn follow_path(
+        &mut self,
+        entity: Entity,
+        points: impl IntoIterator<Item = Vec3>,
+        speed: f32,
+    ) -> bool {
+        self.follow_path_with(entity, points, speed, |_| {}).await
+    }
+
+    /// Like [`follow_path`](Scope::follow_path), but calls `on_waypoint` with the index of each
+    /// point as soon as it is reached.
+    pub async fn follow_path_with(
+        &mut self,
+        entity: Entity,
+        points: impl IntoIterator<Item = Vec3>,
+        speed: f32,
+        mut on_waypoint: impl FnMut(usize),
+    ) -> bool {
+        let mut points = points.into_iter().enumerate().peekable();
+        let mut dt = Duration::ZERO;
+        loop {
+            let Some(start) = self.world().get::<Transform>(entity).map(|t| t.translation) else {
+                return false;
+            };
+
+            // Spend the distance travelled this tick along as many segments as needed, so the
+            // speed stays constant around the corners.
+            let mut position = start;
+            let mut budget = speed * dt.as_secs_f32();
+            while let Some(&(i, point)) = points.peek() {
+                let distance = position.distance(point);
+                if distance > budget {
+                    position = vec3_towards(position, point, budget);
+                    break;
+                }
+                budget -= distance;
+                position = point;
+                on_waypoint(i);
+                points.next();
+            }
+
+            if position != start {
+                self.modify(entity, |t: &mut Transform| t.translation = position);
+            }
+
+            if points.peek().is_none() {
+                return true;
+            }
+
+            dt = self.next_tick().await;
+        }
+    }
+}
+
+impl Scope {
+    /// Pull the translation of `entity` toward the position returned by `target` each tick, as if
+    /// they were linked by a [`Spring`]. Returns once the entity has settled on the target.
+    ///
+    /// Returns `false` if the entity or its [`Transform`] was removed, or if `target` returned
+    /// `None`.
+    pub async fn spring(
+        &mut self,
+        entity: Entity,
+        mut target: impl FnMut(&World) -> Option<Vec3>,
+        spring: Spring,
+    ) -> bool {
+        let mut velocity = Vec3::ZERO;
+        let mut dt = Duration::ZERO;
+        loop {
+            let (Some(position), Some(target)) = ({
+                let world = self.world();
+                (
+                    world.get::<Transform>(entity).map(|t| t.translation),
+                    target(&world),
+                )
+            }) else {
+                return false;
+            };
+
+            // Semi-implicit Euler, the velocity is updated first
+            let dt_secs = dt.as_secs_f32();
+            let offset = position - target;
+            velocity += (-spring.stiffness * offset - spring.damping * velocity) * dt_secs;
+            let next = position + velocity * dt_secs;
+
+            if next.distance(target) <= spring.tolerance && velocity.length() <= spring.tolerance {
+                self.modify(entity, |t: &mut Transform| t.translation = target);
+                return true;
+            }
+
+            if next != position {
+                self.modify(entity, |t: &mut Transform| t.translation = next);
+            }
+
+            dt = self.next_tick().await;
+        }
+    }
+}
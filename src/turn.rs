@@ -0,0 +1,105 @@
+//! An explicit turn token for turn based games. An arbiter coroutine grants turns with
+//! [`Scope::grant_turn`], while each actor waits for its turn with [`Scope::take_turn`].
+//!
+//! ```ignore
+//! // The arbiter
+//! loop {
+//!     s.round([player, goblin, orc]).await;
+//! }
+//!
+//! // An actor
+//! loop {
+//!     let turn = s.take_turn(goblin).await;
+//!     attack(&mut s).await;
+//!     turn.end();
+//! }
+//! ```
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use bevy::prelude::{Entity, Resource, World};
+
+use crate::function_coroutine::{await_condition::AwaitCondition, scope::Scope};
+
+/// The actor currently playing, if any.
+#[derive(Resource, Default)]
+pub struct Turns {
+    current: Option<(Entity, Arc<AtomicBool>)>,
+}
+
+impl Turns {
+    /// Returns the actor whose turn it currently is.
+    pub fn current(&self) -> Option<Entity> {
+        match &self.current {
+            Some((actor, ended)) if !ended.load(Ordering::Acquire) => Some(*actor),
+            _ => None,
+        }
+    }
+}
+
+/// Held by an actor while it is its turn. The turn ends when this token is dropped, or
+/// explicitly with [`TurnToken::end`].
+pub struct TurnToken {
+    ended: Arc<AtomicBool>,
+}
+
+impl TurnToken {
+    /// End the turn.
+    pub fn end(self) {}
+}
+
+impl Drop for TurnToken {
+    fn drop(&mut self) {
+        self.ended.store(true, Ordering::Release);
+    }
+}
+
+impl Scope {
+    /// Returns a future that resolve once the arbiter grants a turn to `actor`, and returns the
+    /// token representing that turn.
+    pub fn take_turn(
+        &mut self,
+        actor: Entity,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<TurnToken>> {
+        self.until(move |world| match &world.get_resource::<Turns>()?.current {
+            Some((current, ended)) if *current == actor && !ended.load(Ordering::Acquire) => {
+                Some(TurnToken {
+                    ended: ended.clone(),
+                })
+            }
+            _ => None,
+        })
+    }
+
+    /// Grant a turn to `actor`, and wait until it ends (or until `actor` is despawned). The
+    /// [`Turns`] resource is initialized if it is missing.
+    pub async fn grant_turn(&mut self, actor: Entity) {
+        let ended = Arc::new(AtomicBool::new(false));
+        let current = (actor, ended.clone());
+        self.commands().add(move |world: &mut World| {
+            world.get_resource_or_insert_with(Turns::default).current = Some(current);
+        });
+
+        // A despawned actor cannot end its turn
+        self.until(move |world| {
+            (ended.load(Ordering::Acquire) || world.get_entity(actor).is_none()).then_some(())
+        })
+        .await;
+
+        self.commands().add(|world: &mut World| {
+            if let Some(mut turns) = world.get_resource_mut::<Turns>() {
+                turns.current = None;
+            }
+        });
+    }
+
+    /// Grant a turn to each of `actors` in order, waiting for each turn to end.
+    pub async fn round(&mut self, actors: impl IntoIterator<Item = Entity>) {
+        for actor in actors {
+            self.grant_turn(actor).await;
+        }
+    }
+}
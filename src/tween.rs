@@ -0,0 +1,235 @@
+//! Tweening of components over time, with easing curves.
+//!
+//! ```ignore
+//! s.tween(e, Duration::from_secs(1), Ease::CubicInOut, |t: &mut Transform, k| {
+//!     t.translation.y = 100. * k;
+//! })
+//! .await;
+//! ```
+//!
+//! Fields unknown to the crate can also be tweened by their reflection path, provided their
+//! component is registered in the [`AppTypeRegistry`]:
+//!
+//! ```ignore
+//! s.tween_field(e, "Sprite.color.alpha", 0.0, 1.0, Duration::from_secs(1), Ease::Linear)
+//!     .await?;
+//! ```
+
+use std::{f32::consts::PI, time::Duration};
+
+use bevy::{
+    ecs::{component::ComponentId, reflect::ReflectComponent},
+    prelude::{AppTypeRegistry, Component, Entity},
+    reflect::GetPath,
+};
+
+use crate::{
+    executor::validate_access,
+    function_coroutine::{await_time::IntoDuration, borrow_tracker::Borrow, scope::Scope},
+    SourceId,
+};
+
+/// An easing curve, mapping the linear progress of a tween (between 0 and 1) to the
+/// interpolation factor. See <https://easings.net> for a visualization of each curve.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Ease {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    Custom(fn(f32) -> f32),
+}
+
+impl Ease {
+    /// Returns the interpolation factor at the linear progress `t`, which is clamped between 0
+    /// and 1. Note that some curves (like elastic ones) overshoot.
+    pub fn sample(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Ease::CubicIn => t * t * t,
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Ease::ElasticIn | Ease::ElasticOut | Ease::ElasticInOut if t == 0.0 || t == 1.0 => t,
+            Ease::ElasticIn => {
+                let c4 = 2.0 * PI / 3.0;
+                -(2f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * c4).sin()
+            }
+            Ease::ElasticOut => {
+                let c4 = 2.0 * PI / 3.0;
+                2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * c4).sin() + 1.0
+            }
+            Ease::ElasticInOut => {
+                let c5 = 2.0 * PI / 4.5;
+                if t < 0.5 {
+                    -(2f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * c5).sin()) / 2.0
+                } else {
+                    2f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * c5).sin() / 2.0 + 1.0
+                }
+            }
+            Ease::Custom(f) => f(t),
+        }
+    }
+}
+
+impl Scope {
+    /// Tween the component `C` of `entity` over `duration`. Each tick, `f` is called with the
+    /// component and the interpolation factor given by `ease`. It is called a last time with
+    /// the final factor (1 for most curves) before returning.
+    ///
+    /// Returns `false` if the entity or its component was removed before the end.
+    pub async fn tween<C: Component>(
+        &mut self,
+        entity: Entity,
+        duration: impl IntoDuration,
+        ease: Ease,
+        mut f: impl FnMut(&mut C, f32),
+    ) -> bool {
+        let duration = duration.into_duration();
+        let mut elapsed = Duration::ZERO;
+        loop {
+            let t = if duration.is_zero() {
+                1.0
+            } else {
+                elapsed.as_secs_f32() / duration.as_secs_f32()
+            };
+
+            match self.component_mut::<C>(entity) {
+                Some(mut c) => f(&mut c, ease.sample(t)),
+                None => return false,
+            }
+
+            if t >= 1.0 {
+                return true;
+            }
+            elapsed += self.next_tick().await;
+        }
+    }
+}
+
+/// The reasons why [`Scope::tween_field`] can fail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TweenFieldError {
+    /// The path isn't of the form `Component.field`.
+    MalformedPath,
+    /// No component with this name is registered in the [`AppTypeRegistry`], or it doesn't
+    /// reflect [`Component`].
+    UnknownComponent(String),
+    /// The entity was despawned, or doesn't have the component.
+    MissingComponent,
+    /// The field doesn't exist, or isn't a `f32` nor a `f64`.
+    InvalidField(String),
+}
+
+impl Scope {
+    /// Tween the numeric field designated by `path` from `from` to `to` over `duration`. The path
+    /// starts with the name of the component (either short or fully qualified) followed by the
+    /// [reflection path](GetPath) of the field, such as `"Sprite.color.alpha"`.
+    ///
+    /// Unlike [`tween`](Scope::tween), it doesn't notify coroutines awaiting a change of the
+    /// component.
+    pub async fn tween_field(
+        &mut self,
+        entity: Entity,
+        path: &str,
+        from: f32,
+        to: f32,
+        duration: impl IntoDuration,
+        ease: Ease,
+    ) -> Result<(), TweenFieldError> {
+        let duration = duration.into_duration();
+        let (component, field) = path.split_once('.').ok_or(TweenFieldError::MalformedPath)?;
+        let world = self.world();
+        let found = world
+            .get_resource::<AppTypeRegistry>()
+            .and_then(|registry| {
+                let registry = registry.read();
+                let registration = registry
+                    .get_with_short_name(component)
+                    .or_else(|| registry.get_with_name(component))?;
+                let component_id = world.components().get_id(registration.type_id())?;
+                Some((
+                    registration.data::<ReflectComponent>()?.clone(),
+                    component_id,
+                ))
+            })
+            .ok_or_else(|| TweenFieldError::UnknownComponent(component.to_owned()));
+        drop(world);
+        let (reflect_component, component_id) = found?;
+
+        let mut elapsed = Duration::ZERO;
+        loop {
+            let t = if duration.is_zero() {
+                1.0
+            } else {
+                elapsed.as_secs_f32() / duration.as_secs_f32()
+            };
+            let value = from + (to - from) * ease.sample(t.min(1.0));
+            self.set_field(entity, &reflect_component, component_id, field, value)?;
+
+            if t >= 1.0 {
+                return Ok(());
+            }
+            elapsed += self.next_tick().await;
+        }
+    }
+
+    fn set_field(
+        &mut self,
+        entity: Entity,
+        reflect_component: &ReflectComponent,
+        component_id: ComponentId,
+        field: &str,
+        value: f32,
+    ) -> Result<(), TweenFieldError> {
+        validate_access(self.id(), |access| {
+            access.add_write(SourceId::Entity(entity), component_id);
+        });
+        // Safety: The scope is borrowed mutably, so the coroutine cannot hold any other reference
+        // to the world.
+        let reflected = unsafe {
+            let entity_cell = self
+                .world_cell()
+                .get_entity(entity)
+                .ok_or(TweenFieldError::MissingComponent)?;
+            reflect_component
+                .reflect_unchecked_mut(entity_cell)
+                .ok_or(TweenFieldError::MissingComponent)?
+        };
+        let mut reflected = self.track(reflected, Borrow::Write(entity, component_id));
+
+        let target = reflected
+            .reflect_path_mut(field)
+            .map_err(|e| TweenFieldError::InvalidField(e.to_string()))?;
+        if let Some(v) = target.downcast_mut::<f32>() {
+            *v = value;
+        } else if let Some(v) = target.downcast_mut::<f64>() {
+            *v = value as f64;
+        } else {
+            return Err(TweenFieldError::InvalidField(field.to_owned()));
+        }
+        Ok(())
+    }
+}
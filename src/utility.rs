@@ -0,0 +1,159 @@
+//! Utility AI: each agent has a set of behaviors, each with a scorer and a coroutine. The
+//! behavior with the highest score runs, and is canceled as soon as another one scores higher.
+//!
+//! ```ignore
+//! let behaviors = Behaviors::new()
+//!     .with("flee", fear, |goblin| boxed_coroutine(flee.with_args((goblin,))))
+//!     .with("attack", aggressivity, |goblin| boxed_coroutine(attack.with_args((goblin,))))
+//!     .with("idle", |_, _| 0.1, |_| boxed_coroutine(idle))
+//!     .evaluate_every(0.5);
+//!
+//! commands.entity(goblin).insert(behaviors);
+//! ```
+//!
+//! The behaviors are evaluated by the [`CorentinPlugin`](crate::plugin::CorentinPlugin), right
+//! before the coroutines are resumed.
+
+use std::{sync::Arc, time::Duration};
+
+use bevy::prelude::{Component, Entity, Mut, World};
+
+use crate::{
+    executor::Executor,
+    function_coroutine::{await_time::IntoDuration, BoxedCoroutine},
+    id_alloc::Id,
+};
+
+type Scorer = Arc<dyn Fn(Entity, &World) -> f32 + Send + Sync>;
+type Factory = Arc<dyn Fn(Entity) -> BoxedCoroutine + Send + Sync>;
+
+#[derive(Clone)]
+struct Behavior {
+    name: &'static str,
+    scorer: Scorer,
+    factory: Factory,
+}
+
+/// The behaviors of an agent, see the [module level documentation](self).
+#[derive(Component, Clone, Default)]
+pub struct Behaviors {
+    /// The behaviors evaluated, frozen once the component is in the world.
+    behaviors: Arc<[Behavior]>,
+    /// The behaviors added since, see [`freeze`](Behaviors::freeze).
+    added: Vec<Behavior>,
+    every: Duration,
+    until_evaluation: Duration,
+    running: Option<(usize, Id)>,
+}
+
+impl Behaviors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a behavior, scored by `scorer`, and run in the coroutine returned by `factory`. Both
+    /// are given the agent, which owns the coroutine. Behaviors with a score of zero or less never
+    /// run. Behaviors can be added to a clone of a template shared by several agents.
+    pub fn with(
+        mut self,
+        name: &'static str,
+        scorer: impl Fn(Entity, &World) -> f32 + Send + Sync + 'static,
+        factory: impl Fn(Entity) -> BoxedCoroutine + Send + Sync + 'static,
+    ) -> Self {
+        self.added.push(Behavior {
+            name,
+            scorer: Arc::new(scorer),
+            factory: Arc::new(factory),
+        });
+        self
+    }
+
+    /// Move the behaviors added with [`with`](Behaviors::with) to the shared list. Done the first
+    /// time the component is seen in the world, so that clones of it share the list.
+    fn freeze(&mut self) {
+        if !self.added.is_empty() {
+            let added = std::mem::take(&mut self.added);
+            self.behaviors = self.behaviors.iter().cloned().chain(added).collect();
+        }
+    }
+
+    /// Evaluate the scores once per `period`, instead of every frame. A behavior which ends on
+    /// its own is replaced right away though.
+    pub fn evaluate_every(mut self, period: impl IntoDuration) -> Self {
+        self.every = period.into_duration();
+        self
+    }
+
+    /// Returns the name of the behavior currently running.
+    pub fn running(&self) -> Option<&'static str> {
+        self.running.map(|(i, _)| self.behaviors[i].name)
+    }
+
+    /// Returns the behavior with the highest positive score. In case of a tie, the first one
+    /// added wins.
+    fn best(&self, agent: Entity, world: &World) -> Option<usize> {
+        let mut best = None;
+        for (i, behavior) in self.behaviors.iter().enumerate() {
+            let score = (behavior.scorer)(agent, world);
+            if score > 0.0 && best.map_or(true, |(_, s)| score > s) {
+                best = Some((i, score));
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+}
+
+/// Start the best behavior of each agent, canceling the ones that aren't anymore.
+pub(crate) fn select_behaviors(world: &mut World) {
+    if !world.contains_resource::<Executor>() {
+        return;
+    }
+    for mut behaviors in world.query::<&mut Behaviors>().iter_mut(world) {
+        if !behaviors.added.is_empty() {
+            behaviors.freeze();
+        }
+    }
+
+    world.resource_scope(|world, mut executor: Mut<Executor>| {
+        let delta = executor.next_delta(world);
+        let mut agents = world.query::<(Entity, &Behaviors)>();
+        let mut selected = Vec::new();
+        for (agent, behaviors) in agents.iter(world) {
+            let running = behaviors.running.filter(|(_, id)| executor.is_running(*id));
+            let due = behaviors.until_evaluation <= delta;
+            if running.is_some() && !due {
+                selected.push((agent, due, None, false));
+                continue;
+            }
+
+            let best = behaviors.best(agent, world);
+            let changed = running.is_none() || best != running.map(|(i, _)| i);
+            selected.push((agent, due, best, changed));
+        }
+
+        for (agent, due, best, changed) in selected {
+            let mut behaviors: Mut<Behaviors> = world.get_mut(agent).unwrap();
+            behaviors.until_evaluation = match due {
+                true => behaviors.every,
+                false => behaviors.until_evaluation - delta,
+            };
+            if !changed {
+                continue;
+            }
+
+            if let Some((_, id)) = behaviors.running.take() {
+                executor.cancel_coroutine(id);
+            }
+            let Some(best) = best else {
+                continue;
+            };
+            let behavior = behaviors.behaviors[best].clone();
+            let started = executor
+                .build((behavior.factory)(agent))
+                .name(behavior.name)
+                .owner(agent)
+                .spawn(world);
+            world.get_mut::<Behaviors>(agent).unwrap().running = started.map(|id| (best, id));
+        }
+    });
+}
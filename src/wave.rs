@@ -0,0 +1,65 @@
+//! Helpers to orchestrate waves of entities, as found in arcade and tower defense games.
+//!
+//! ```ignore
+//! for wave in 1..=10 {
+//!     s.wave(wave * 5, Duration::from_millis(300), |_| enemy_bundle()).await;
+//!     s.duration(Duration::from_secs(3)).await;
+//! }
+//! ```
+
+use bevy::prelude::{Bundle, Entity, World};
+
+use crate::function_coroutine::{
+    await_condition::AwaitCondition, await_time::IntoDuration, scope::Scope,
+};
+
+impl Scope {
+    /// Spawn `count` entities, waiting `stagger` between each of them, and returns them. The
+    /// bundle of each entity is given by `spawn`, called with the index of the entity.
+    pub async fn spawn_staggered<B: Bundle>(
+        &mut self,
+        count: usize,
+        stagger: impl IntoDuration,
+        mut spawn: impl FnMut(usize) -> B,
+    ) -> Vec<Entity> {
+        let stagger = stagger.into_duration();
+        let mut entities = Vec::with_capacity(count);
+        for i in 0..count {
+            if i > 0 && !stagger.is_zero() {
+                self.duration(stagger).await;
+            }
+            entities.push(self.commands().spawn(spawn(i)).id());
+        }
+        entities
+    }
+
+    /// Returns a future that resolve once all of `entities` are despawned. Note that entities
+    /// spawned with [`Scope::commands`] are only spawned at the end of the current tick, so they
+    /// are not considered alive before.
+    pub fn all_despawned(
+        &mut self,
+        entities: Vec<Entity>,
+    ) -> AwaitCondition<'_, impl FnMut(&World) -> Option<()>> {
+        self.until(move |world| {
+            entities
+                .iter()
+                .all(|e| world.get_entity(*e).is_none())
+                .then_some(())
+        })
+    }
+
+    /// Spawn a wave of `count` entities (see [`Scope::spawn_staggered`]), and wait until they are
+    /// all despawned.
+    pub async fn wave<B: Bundle>(
+        &mut self,
+        count: usize,
+        stagger: impl IntoDuration,
+        spawn: impl FnMut(usize) -> B,
+    ) {
+        let stagger = stagger.into_duration();
+        let entities = self.spawn_staggered(count, stagger, spawn).await;
+        // Entities spawned with commands only exist once the tick is over
+        self.next_tick().await;
+        self.all_despawned(entities).await;
+    }
+}